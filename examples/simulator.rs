@@ -0,0 +1,34 @@
+// Drives a simulated 20x4 panel in the terminal.
+//
+//     cargo run --example simulator
+
+use std::thread;
+use std::time::Duration;
+
+use qwiic_lcd_rs::*;
+
+fn main() -> Result<(), QwiicLcdError> {
+    let mut screen = Screen::simulated(ScreenConfig::default());
+    screen.init()?;
+
+    let heart = [0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00];
+    screen.create_character(0, heart)?;
+
+    for (r, g, b, name) in [(0, 255, 0, "Green"), (255, 0, 0, "Red"), (0, 0, 255, "Blue")] {
+        screen.clear()?;
+        screen.change_backlight(r, g, b)?;
+        screen.print(&format!("BG: {}", name))?;
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    screen.change_backlight(255, 255, 255)?;
+    screen.clear()?;
+    screen.print("Simulated panel")?;
+    screen.move_cursor(1, 0)?;
+    screen.write_byte(0)?;
+    screen.print(" custom glyphs")?;
+    screen.move_cursor(3, 0)?;
+    screen.enable_blink(true)?;
+    screen.print("It Works!")?;
+    Ok(())
+}
@@ -0,0 +1,495 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::mem;
+use std::panic;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use i2cdev::linux::LinuxI2CError;
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::task::{self, JoinError};
+
+use crate::retry::RetryEngine;
+use crate::stats;
+use crate::{
+    Backlight, BitMode, CircuitBreakerConfig, CircuitState, EntryMode, EntryShift, FrameBuffer, I2COperation,
+    I2CStats, LinuxTransport, MoveDirection, QwiicLcdError, RetryFilter, RetryPolicy, Screen, ScreenConfig,
+    Transport,
+};
+
+type ScreenResult = Result<(), QwiicLcdError>;
+
+/// One step of an operation planned by the synchronous `Screen`
+#[derive(Debug)]
+enum Step {
+    Write {
+        operation: I2COperation,
+        register: Option<u8>,
+        bytes: Vec<u8>,
+    },
+    Delay(Duration),
+}
+
+/// Transport that records what `Screen` would send instead of sending it
+#[derive(Debug, Default)]
+struct Planner {
+    steps: Vec<Step>,
+}
+
+impl Planner {
+    fn push(&mut self, operation: I2COperation, register: Option<u8>, bytes: &[u8]) {
+        self.steps.push(Step::Write { operation, register, bytes: bytes.to_vec() });
+    }
+}
+
+impl Transport for Planner {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.push(I2COperation::WriteByte, None, &[value]);
+        Ok(())
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.push(I2COperation::WriteByteData, Some(register), &[value]);
+        Ok(())
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        self.push(I2COperation::WriteBlock, Some(register), data);
+        Ok(())
+    }
+
+    fn delay(&mut self, duration: Duration) {
+        self.steps.push(Step::Delay(duration));
+    }
+}
+
+struct Inner<T: Transport> {
+    /// Tracks the display state; its transport only records the steps
+    state: Screen<Planner>,
+    transport: Arc<Mutex<T>>,
+    retry: RetryEngine,
+    stats: I2CStats,
+}
+
+impl<T: Transport + 'static> Inner<T> {
+    async fn execute(&mut self, steps: Vec<Step>) -> ScreenResult {
+        self.retry.begin_operation();
+        let result = self.execute_steps(steps).await;
+        self.retry.end_operation();
+        result
+    }
+
+    async fn execute_steps(&mut self, steps: Vec<Step>) -> ScreenResult {
+        for step in steps {
+            match step {
+                Step::Delay(duration) => tokio::time::sleep(duration).await,
+                Step::Write { operation, register, bytes } => self.transfer(operation, register, bytes).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Async counterpart of `Screen::transfer`: attempts run on the blocking
+    /// pool and backoff delays on the tokio timer
+    async fn transfer(&mut self, operation: I2COperation, register: Option<u8>, bytes: Vec<u8>) -> ScreenResult {
+        let trial_only = self.retry.admit()?;
+        self.retry.start_transfer();
+        let started = Instant::now();
+        let mut attempts = 0;
+        let result = loop {
+            attempts += 1;
+            let attempt_started = Instant::now();
+            let transport = Arc::clone(&self.transport);
+            let data = bytes.clone();
+            let attempt = task::spawn_blocking(move || {
+                let mut transport = transport.lock().unwrap_or_else(|e| e.into_inner());
+                operation.send(&mut *transport, register, &data)
+            });
+            let outcome = attempt.await.map_err(join_error)?;
+            self.retry.spend(attempt_started.elapsed());
+            let error = match outcome {
+                Ok(()) => {
+                    self.retry.succeeded();
+                    break Ok(());
+                }
+                Err(e) => e,
+            };
+
+            match self.retry.backoff(attempts, trial_only, &error) {
+                Some(delay) => {
+                    tokio::time::sleep(delay).await;
+                    self.retry.spend(delay);
+                }
+                None => {
+                    self.retry.failed();
+                    break Err(QwiicLcdError::transfer(operation, register, &bytes, attempts, error));
+                }
+            }
+        };
+        let latency = started.elapsed();
+
+        let wire_bytes = bytes.len() + register.is_some() as usize;
+        self.stats.record(operation, wire_bytes, attempts, latency, result.is_err());
+        stats::trace_transaction(operation, register, &bytes, attempts, latency, &result);
+        result
+    }
+}
+
+/// Re-raises a panic from a worker task; a task cancelled by runtime
+/// shutdown is reported as a timeout
+fn join_error(error: JoinError) -> QwiicLcdError {
+    match error.try_into_panic() {
+        Ok(payload) => panic::resume_unwind(payload),
+        Err(_) => QwiicLcdError::CommunicationTimeout,
+    }
+}
+
+macro_rules! forward {
+    ($($(#[$doc:meta])* $name:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            $(#[$doc])*
+            pub async fn $name(&self, $($arg: $ty),*) -> ScreenResult {
+                self.run(move |screen| screen.$name($($arg),*)).await
+            }
+        )*
+    };
+}
+
+/// Tokio counterpart of `Screen`
+///
+/// The methods mirror `Screen` but never block the executor: I2C writes run
+/// on tokio's blocking pool, while command delays and retry backoff use
+/// `tokio::time::sleep`. The display state (framebuffer, cursor, glyphs...)
+/// is tracked by the same code as the synchronous API, so `present` still
+/// only sends the cells that changed.
+///
+/// Operations are cancel-safe: once started, an operation runs to
+/// completion on a background task even if the calling future is dropped,
+/// so the panel never sees half of a command. Operations on the same screen
+/// run one at a time, in call order. `AsyncScreen` is a cheap handle and
+/// can be cloned to share the panel between tasks.
+///
+/// The tracked state is updated when an operation is planned, so after a
+/// failed write it is ahead of the panel; `clear` and redraw to resync.
+///
+/// # Example
+/// ```no_run
+/// # use qwiic_lcd_rs::*;
+/// # async fn run() -> Result<(), QwiicLcdError> {
+/// let screen = AsyncScreen::new(ScreenConfig::default(), "/dev/i2c-1", 0x72)?;
+/// screen.init().await?;
+/// screen.change_backlight(0, 255, 0).await?;
+/// screen.move_cursor(0, 0).await?;
+/// screen.print("Hello from tokio").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncScreen<T: Transport + 'static = LinuxTransport> {
+    inner: Arc<AsyncMutex<Inner<T>>>,
+}
+
+impl<T: Transport + 'static> Clone for AsyncScreen<T> {
+    fn clone(&self) -> Self {
+        AsyncScreen { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl AsyncScreen<LinuxTransport> {
+    /// Opens the LCD on the given bus and address, like `Screen::new`
+    pub fn new(config: ScreenConfig, bus: &str, i2c_addr: u16) -> Result<AsyncScreen, QwiicLcdError> {
+        let transport = LinuxTransport::open(bus, i2c_addr).map_err(|e| {
+            QwiicLcdError::InitializationFailed(format!(
+                "Failed to open I2C device on {} at address 0x{:02X}: {}",
+                bus, i2c_addr, e
+            ))
+        })?;
+        Ok(AsyncScreen::with_transport(config, transport))
+    }
+}
+
+impl<T: Transport + 'static> AsyncScreen<T> {
+    /// Creates an AsyncScreen that talks through the given transport
+    pub fn with_transport(config: ScreenConfig, transport: T) -> AsyncScreen<T> {
+        let retry = RetryEngine::new(config.retry_config);
+        AsyncScreen {
+            inner: Arc::new(AsyncMutex::new(Inner {
+                state: Screen::with_transport(config, Planner::default()),
+                transport: Arc::new(Mutex::new(transport)),
+                retry,
+                stats: I2CStats::default(),
+            })),
+        }
+    }
+
+    /// Runs `f` with the underlying transport, once pending operations are done
+    pub async fn with_transport_ref<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let inner = self.inner.lock().await;
+        let transport = inner.transport.lock().unwrap_or_else(|e| e.into_inner());
+        f(&transport)
+    }
+
+    /// Plans an operation with the synchronous screen, then sends it
+    ///
+    /// Steps recorded before a validation error are still sent, matching
+    /// what the synchronous `Screen` does.
+    async fn run<F>(&self, op: F) -> ScreenResult
+    where
+        F: FnOnce(&mut Screen<Planner>) -> ScreenResult,
+    {
+        let mut inner = Arc::clone(&self.inner).lock_owned().await;
+        let planned = op(&mut inner.state);
+        let steps = mem::take(&mut inner.state.transport_mut().steps);
+        if steps.is_empty() {
+            return planned;
+        }
+        // The spawned task owns the lock, so dropping this future cannot
+        // interrupt the operation halfway through
+        let sent = tokio::spawn(async move { inner.execute(steps).await });
+        sent.await.map_err(join_error)??;
+        planned
+    }
+
+    forward! {
+        /// Initializes the LCD screen with default settings
+        init();
+        /// Changes the backlight color (RGB)
+        change_backlight(r: u8, g: u8, b: u8);
+        /// Clears the display and homes the cursor
+        clear();
+        /// Moves the cursor to the top left corner
+        home();
+        /// Moves the cursor to the given row and column
+        move_cursor(row: usize, col: usize);
+        /// Shows or hides the underline cursor
+        enable_cursor(activated: bool);
+        /// Turns the display on or off
+        enable_display(activated: bool);
+        /// Enables or disables the blinking block cursor
+        enable_blink(activated: bool);
+        /// Sends the tracked display status, cursor and blink state
+        apply_display_state();
+        /// Prints a string at the cursor position
+        print(s: &str);
+        /// Prints a string after checking that it only contains printable ASCII
+        print_ascii(s: &str);
+        /// Writes raw bytes at the cursor position
+        print_bytes(bytes: &[u8]);
+        /// Sends a single raw byte
+        write_byte(command: u8);
+        /// Sends a register byte followed by a data block
+        write_block(register: u8, data: Vec<u8>);
+        /// Sends a setting command
+        write_setting_cmd(command: u8);
+        /// Sends a special (HD44780) command
+        write_special_cmd(command: u8);
+        /// Sets the LCD contrast (0-255)
+        set_contrast(contrast: u8);
+        /// Changes the I2C address the panel answers to (0x08-0x77)
+        set_address(address: u8);
+        /// Enables or disables the splash screen shown at power-up
+        enable_splash(activated: bool);
+        /// Saves the current display contents as the power-up splash screen
+        save_splash();
+        /// Creates a custom character at the specified index (0-7)
+        create_character(index: u8, data: [u8; 8]);
+        /// Sets the text entry direction
+        set_entry_mode(mode: EntryMode);
+        /// Enables or disables display shifting on entry
+        set_entry_shift(shift: EntryShift);
+        /// Moves the cursor one position left or right
+        shift_cursor(direction: MoveDirection);
+        /// Scrolls the display one position left or right
+        shift_display(direction: MoveDirection);
+        /// Turns the backlight fully on or off
+        set_backlight_state(state: Backlight);
+        /// Configures the bit mode of the display (4-bit or 8-bit)
+        configure_bit_mode(mode: BitMode);
+        /// Updates the display to show `frame`, sending only the cells that differ
+        present(frame: &FrameBuffer);
+    }
+
+    /// Returns a copy of the tracked contents of the display
+    pub async fn framebuffer(&self) -> FrameBuffer {
+        self.inner.lock().await.state.framebuffer().clone()
+    }
+
+    /// Returns the last backlight color set through this screen, if any
+    pub async fn backlight(&self) -> Option<(u8, u8, u8)> {
+        self.inner.lock().await.state.backlight()
+    }
+
+    /// Returns the last contrast set through this screen, if any
+    pub async fn contrast(&self) -> Option<u8> {
+        self.inner.lock().await.state.contrast()
+    }
+
+    /// Replaces the retry policy (the default comes from `RetryConfig`)
+    pub async fn set_retry_policy<P: RetryPolicy + 'static>(&self, policy: P) {
+        self.inner.lock().await.retry.set_policy(Box::new(policy));
+    }
+
+    /// Sets an overall time budget for each call, including retries
+    ///
+    /// See `Screen::set_operation_deadline`.
+    pub async fn set_operation_deadline(&self, deadline: Option<Duration>) {
+        self.inner.lock().await.retry.set_deadline(deadline);
+    }
+
+    /// Selects which failures are retried (transient errors only by default)
+    pub async fn set_retry_filter(&self, filter: RetryFilter) {
+        self.inner.lock().await.retry.set_filter(filter);
+    }
+
+    /// Enables or disables the circuit breaker that fails fast after repeated outages
+    pub async fn set_circuit_breaker(&self, config: Option<CircuitBreakerConfig>) {
+        self.inner.lock().await.retry.set_circuit_breaker(config);
+    }
+
+    /// Returns the current circuit breaker state
+    pub async fn circuit_state(&self) -> CircuitState {
+        self.inner.lock().await.retry.circuit_state()
+    }
+
+    /// Returns a snapshot of the I2C traffic counters
+    pub async fn stats(&self) -> I2CStats {
+        self.inner.lock().await.stats
+    }
+
+    /// Resets the I2C traffic counters
+    pub async fn reset_stats(&self) {
+        self.inner.lock().await.stats = I2CStats::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, FixedBackoff, Transaction};
+    use std::future::Future;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    /// Logs transactions and fails the first `failures` of them with EREMOTEIO
+    #[derive(Default)]
+    struct FlakyTransport {
+        sent: Vec<Transaction>,
+        failures: u32,
+    }
+
+    impl FlakyTransport {
+        fn log(&mut self, transaction: Transaction) -> Result<(), LinuxI2CError> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(LinuxI2CError::Errno(121));
+            }
+            self.sent.push(transaction);
+            Ok(())
+        }
+    }
+
+    impl Transport for FlakyTransport {
+        fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+            self.log(Transaction::byte(value))
+        }
+
+        fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+            self.log(Transaction::byte_data(register, value))
+        }
+
+        fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+            self.log(Transaction::block(register, data))
+        }
+
+        fn delay(&mut self, _duration: Duration) {}
+    }
+
+    #[test]
+    fn test_matches_sync_screen() {
+        let expected = {
+            let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), FlakyTransport::default());
+            screen.init().unwrap();
+            screen.print("Hello").unwrap();
+            screen.create_character(2, [0x1F; 8]).unwrap();
+            screen.transport().sent.clone()
+        };
+        let actual = block_on(async {
+            let screen = AsyncScreen::with_transport(ScreenConfig::new(2, 16), FlakyTransport::default());
+            screen.init().await.unwrap();
+            screen.print("Hello").await.unwrap();
+            screen.create_character(2, [0x1F; 8]).await.unwrap();
+            assert_eq!(screen.framebuffer().await.row_text(0), "Hello           ");
+            screen.with_transport_ref(|transport| transport.sent.clone()).await
+        });
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_delays_use_tokio_timer() {
+        block_on(async {
+            let screen = AsyncScreen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+            let started = tokio::time::Instant::now();
+            screen.init().await.unwrap();
+            // The paused clock only advances through tokio sleeps
+            assert!(started.elapsed() >= Duration::from_millis(200));
+
+            let mut frame = FrameBuffer::new(2, 16);
+            frame.set(1, 3, b'!');
+            screen.present(&frame).await.unwrap();
+            assert_eq!(screen.with_transport_ref(|lcd| lcd.row_text(1)).await, "   !            ");
+        });
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        block_on(async {
+            let flaky = FlakyTransport { failures: 2, ..FlakyTransport::default() };
+            let screen = AsyncScreen::with_transport(ScreenConfig::new(2, 16), flaky);
+            screen
+                .set_retry_policy(FixedBackoff { max_retries: 3, delay: Duration::from_millis(50) })
+                .await;
+            let started = tokio::time::Instant::now();
+            screen.write_byte(b'x').await.unwrap();
+            assert!(started.elapsed() >= Duration::from_millis(100));
+            assert_eq!(screen.stats().await.total().retries, 2);
+
+            let flaky = FlakyTransport { failures: 5, ..FlakyTransport::default() };
+            let screen = AsyncScreen::with_transport(ScreenConfig::new(2, 16), flaky);
+            screen
+                .set_retry_policy(FixedBackoff { max_retries: 1, delay: Duration::from_millis(50) })
+                .await;
+            let error = screen.write_byte(b'x').await.unwrap_err();
+            assert!(matches!(error, QwiicLcdError::Transfer { attempts: 2, .. }), "{:?}", error);
+        });
+    }
+
+    #[test]
+    fn test_cancelled_operation_completes() {
+        block_on(async {
+            let screen = AsyncScreen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+            screen.print("stale").await.unwrap();
+            // Dropping `init` during its trailing delay must not cut it short
+            let _ = tokio::time::timeout(Duration::from_millis(50), screen.init()).await;
+            screen.print("after").await.unwrap();
+            assert_eq!(screen.with_transport_ref(|lcd| lcd.row_text(0)).await, "after           ");
+        });
+    }
+}
@@ -0,0 +1,82 @@
+// Argument parsing shared by the command-line tools.
+
+/// Parses an I2C address in hex (`0x72`) or decimal (`114`)
+pub fn parse_address(value: &str) -> Option<u16> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Parses a panel size given as `COLSxROWS` (e.g. `20x4`) into (rows, columns)
+pub fn parse_size(value: &str) -> Option<(u8, u8)> {
+    let (columns, rows) = value.split_once(['x', 'X'])?;
+    Some((rows.parse().ok()?, columns.parse().ok()?))
+}
+
+/// Parses a color name, `RRGGBB` / `#RRGGBB` hex or `r,g,b` decimal triple
+pub fn parse_color(value: &str) -> Option<(u8, u8, u8)> {
+    let named = match value.to_ascii_lowercase().as_str() {
+        "off" | "black" => Some((0, 0, 0)),
+        "on" | "white" => Some((255, 255, 255)),
+        "red" => Some((255, 0, 0)),
+        "green" => Some((0, 255, 0)),
+        "blue" => Some((0, 0, 255)),
+        "yellow" => Some((255, 255, 0)),
+        "cyan" => Some((0, 255, 255)),
+        "magenta" => Some((255, 0, 255)),
+        "orange" => Some((255, 128, 0)),
+        "purple" => Some((128, 0, 255)),
+        _ => None,
+    };
+    if named.is_some() {
+        return named;
+    }
+    if let Some((r, rest)) = value.split_once(',') {
+        let (g, b) = rest.split_once(',')?;
+        return Some((r.trim().parse().ok()?, g.trim().parse().ok()?, b.trim().parse().ok()?));
+    }
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Parses `on`/`off` style switches
+pub fn parse_switch(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "on" | "true" | "yes" | "1" => Some(true),
+        "off" | "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address_and_size() {
+        assert_eq!(parse_address("0x72"), Some(0x72));
+        assert_eq!(parse_address("114"), Some(0x72));
+        assert_eq!(parse_address("0xZZ"), None);
+        assert_eq!(parse_size("20x4"), Some((4, 20)));
+        assert_eq!(parse_size("16X2"), Some((2, 16)));
+        assert_eq!(parse_size("16"), None);
+    }
+
+    #[test]
+    fn test_parse_color_and_switch() {
+        assert_eq!(parse_color("Red"), Some((255, 0, 0)));
+        assert_eq!(parse_color("#0080ff"), Some((0, 128, 255)));
+        assert_eq!(parse_color("0080FF"), Some((0, 128, 255)));
+        assert_eq!(parse_color("10, 20,30"), Some((10, 20, 30)));
+        assert_eq!(parse_color("10,20"), None);
+        assert_eq!(parse_color("chartreuse"), None);
+        assert_eq!(parse_switch("ON"), Some(true));
+        assert_eq!(parse_switch("0"), Some(false));
+        assert_eq!(parse_switch("maybe"), None);
+    }
+}
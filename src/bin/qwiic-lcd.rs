@@ -0,0 +1,478 @@
+// Command-line control of Qwiic SerLCD panels for shell scripts.
+//
+//     qwiic-lcd [--bus PATH] [--address ADDR] [--size COLSxROWS] [--simulate] <command> [args]
+//
+// Errors map to distinct exit codes (see `exit_code`) so scripts can tell
+// a missing panel from a bad argument.
+
+use std::env;
+use std::process;
+
+use qwiic_lcd_rs::*;
+
+#[allow(dead_code)]
+mod common;
+
+use common::{parse_address, parse_color, parse_size, parse_switch};
+
+const USAGE: &str = "\
+usage: qwiic-lcd [--bus PATH] [--address ADDR] [--size COLSxROWS] [--simulate] <command> [args]
+
+commands:
+  print TEXT [--row N] [--col N | --align left|center|right] [--clear]
+                          print text; \\0-\\7 insert custom glyphs, --align rewrites the row,
+                          TEXT after -- may start with a dash
+  clear                   clear the display
+  backlight COLOR         name, RRGGBB or r,g,b
+  contrast N              0-255, lower is darker
+  cursor on|off           underline cursor
+  blink on|off            blinking block cursor
+  glyph define INDEX ROW... 8 rows as numbers (0x1F) or patterns (#.#.#)
+  scan                    list the I2C addresses answering on the bus (not with --simulate)
+  set-address ADDR        change the panel address (0x08-0x77)
+  splash on|off|save      enable, disable or save the power-up splash screen";
+
+/// Exit code for command-line mistakes
+const EXIT_USAGE: i32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Command {
+    Help,
+    Print { text: String, row: usize, col: usize, align: Option<Align>, clear: bool },
+    Clear,
+    Backlight(u8, u8, u8),
+    Contrast(u8),
+    Cursor(bool),
+    Blink(bool),
+    DefineGlyph { index: u8, rows: [u8; 8] },
+    Scan,
+    SetAddress(u8),
+    Splash(SplashAction),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SplashAction {
+    Enable,
+    Disable,
+    Save,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Options {
+    bus: String,
+    address: u16,
+    rows: u8,
+    columns: u8,
+    simulate: bool,
+    command: Command,
+}
+
+enum CliError {
+    Usage(String),
+    Lcd(QwiicLcdError),
+}
+
+impl From<QwiicLcdError> for CliError {
+    fn from(error: QwiicLcdError) -> Self {
+        CliError::Lcd(error)
+    }
+}
+
+/// Maps each error kind to its own exit code
+#[allow(deprecated)]
+fn exit_code(error: &QwiicLcdError) -> i32 {
+    match error {
+        QwiicLcdError::InitializationFailed(_) => 3,
+        QwiicLcdError::I2CError(_) | QwiicLcdError::Transfer { .. } if error.is_transient() => 4,
+        QwiicLcdError::I2CError(_) | QwiicLcdError::Transfer { .. } => 5,
+        QwiicLcdError::CommunicationTimeout => 6,
+        QwiicLcdError::CircuitOpen { .. } => 7,
+        QwiicLcdError::InvalidPosition { .. } => 8,
+        QwiicLcdError::InvalidCharacter(_) => 9,
+        QwiicLcdError::InvalidCustomCharIndex(_) => 10,
+        QwiicLcdError::InvalidAddress(_) => 11,
+        QwiicLcdError::InvalidContrastValue(_) => 12,
+    }
+}
+
+fn usage<T>(message: String) -> Result<T, CliError> {
+    Err(CliError::Usage(message))
+}
+
+fn number<T: std::str::FromStr>(what: &str, value: &str) -> Result<T, CliError> {
+    value.parse().or_else(|_| usage(format!("invalid {} '{}'", what, value)))
+}
+
+fn switch(value: Option<&String>) -> Result<bool, CliError> {
+    match value.map(|v| parse_switch(v)) {
+        Some(Some(on)) => Ok(on),
+        _ => usage("expected on or off".to_string()),
+    }
+}
+
+/// Parses one glyph row: a number (`0x1F`, `0b10101`, `31`) or a 5-dot
+/// pattern such as `#.#.#`
+fn parse_glyph_row(value: &str) -> Option<u8> {
+    if value.len() == 5 && value.chars().all(|c| c == '#' || c == '.') {
+        return Some(value.chars().fold(0, |bits, c| bits << 1 | (c == '#') as u8));
+    }
+    let parsed = if let Some(hex) = value.strip_prefix("0x") {
+        u8::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = value.strip_prefix("0b") {
+        u8::from_str_radix(binary, 2).ok()
+    } else {
+        value.parse().ok()
+    };
+    parsed.filter(|bits| *bits <= 0x1F)
+}
+
+fn is_help(arg: &str) -> bool {
+    arg == "-h" || arg == "--help"
+}
+
+fn parse_command(args: &[String]) -> Result<Command, CliError> {
+    let Some((name, args)) = args.split_first() else {
+        return usage("missing command".to_string());
+    };
+    // Past `--` a help flag is just text
+    if args.iter().take_while(|arg| *arg != "--").any(|arg| is_help(arg)) {
+        return Ok(Command::Help);
+    }
+    let one = |what: &str| -> Result<&String, CliError> {
+        match args {
+            [value] => Ok(value),
+            _ => usage(format!("{} expects exactly one argument: {}", name, what)),
+        }
+    };
+    let command = match name.as_str() {
+        "print" => {
+            let mut text = None;
+            let (mut row, mut col, mut align, mut clear) = (0, None, None, false);
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                let mut value = || args.next().map_or_else(|| usage(format!("{} needs a value", arg)), Ok);
+                match arg.as_str() {
+                    "--row" => row = number("row", value()?)?,
+                    "--col" => col = Some(number("column", value()?)?),
+                    "--align" => {
+                        align = Some(match value()?.as_str() {
+                            "left" => Align::Left,
+                            "center" => Align::Center,
+                            "right" => Align::Right,
+                            other => return usage(format!("invalid alignment '{}'", other)),
+                        })
+                    }
+                    "--clear" => clear = true,
+                    "--" if text.is_none() => {
+                        text = args.next().cloned();
+                        if let Some(extra) = args.next() {
+                            return usage(format!("unexpected argument '{}'", extra));
+                        }
+                    }
+                    _ if text.is_none() => text = Some(arg.clone()),
+                    other => return usage(format!("unexpected argument '{}'", other)),
+                }
+            }
+            let text = text.map_or_else(|| usage("print needs some text".to_string()), Ok)?;
+            if align.is_some() && col.is_some() {
+                return usage("--align rewrites the whole row and cannot be combined with --col".to_string());
+            }
+            Command::Print { text, row, col: col.unwrap_or(0), align, clear }
+        }
+        "clear" if args.is_empty() => Command::Clear,
+        "backlight" => {
+            let color = one("COLOR")?;
+            let (r, g, b) = parse_color(color).map_or_else(|| usage(format!("invalid color '{}'", color)), Ok)?;
+            Command::Backlight(r, g, b)
+        }
+        "contrast" => Command::Contrast(number("contrast", one("N")?)?),
+        "cursor" => Command::Cursor(switch(Some(one("on|off")?))?),
+        "blink" => Command::Blink(switch(Some(one("on|off")?))?),
+        "glyph" => {
+            let [action, index, rows @ ..] = args else {
+                return usage("usage: glyph define INDEX ROW...".to_string());
+            };
+            if action != "define" {
+                return usage(format!("unknown glyph action '{}'", action));
+            }
+            let rows: Vec<&str> = rows.iter().flat_map(|r| r.split(',')).map(str::trim).collect();
+            let rows: Option<Vec<u8>> = rows.iter().map(|r| parse_glyph_row(r)).collect();
+            let rows: [u8; 8] = rows
+                .and_then(|rows| rows.try_into().ok())
+                .map_or_else(|| usage("a glyph needs 8 rows of 5 dots".to_string()), Ok)?;
+            Command::DefineGlyph { index: number("glyph index", index)?, rows }
+        }
+        "scan" if args.is_empty() => Command::Scan,
+        "set-address" => {
+            let value = one("ADDR")?;
+            let address = parse_address(value)
+                .and_then(|a| u8::try_from(a).ok())
+                .map_or_else(|| usage(format!("invalid address '{}'", value)), Ok)?;
+            Command::SetAddress(address)
+        }
+        "splash" => Command::Splash(match one("on|off|save")?.as_str() {
+            "save" => SplashAction::Save,
+            value => match parse_switch(value) {
+                Some(true) => SplashAction::Enable,
+                Some(false) => SplashAction::Disable,
+                None => return usage(format!("invalid splash action '{}'", value)),
+            },
+        }),
+        "clear" | "scan" => return usage(format!("{} takes no arguments", name)),
+        other => return usage(format!("unknown command '{}'", other)),
+    };
+    Ok(command)
+}
+
+fn parse_args(args: &[String]) -> Result<Options, CliError> {
+    let (mut bus, mut address, mut rows, mut columns, mut simulate) = ("/dev/i2c-1".to_string(), 0x72, 4, 20, false);
+    let mut rest = args;
+    // Global flags come before the command
+    while let Some((flag, tail)) = rest.split_first() {
+        let value = || tail.first().map_or_else(|| usage(format!("{} needs a value", flag)), Ok);
+        match flag.as_str() {
+            "-h" | "--help" => {
+                rest = std::slice::from_ref(flag);
+                break;
+            }
+            "--bus" => bus = value()?.clone(),
+            "--address" => {
+                address = parse_address(value()?).map_or_else(|| usage(format!("invalid address '{}'", tail[0])), Ok)?
+            }
+            "--size" => {
+                (rows, columns) = parse_size(value()?).map_or_else(|| usage(format!("invalid size '{}'", tail[0])), Ok)?
+            }
+            "--simulate" => {
+                simulate = true;
+                rest = tail;
+                continue;
+            }
+            _ => break,
+        }
+        rest = &tail[1..];
+    }
+    let command = match rest {
+        [flag] if is_help(flag) => Command::Help,
+        _ => parse_command(rest)?,
+    };
+    Ok(Options { bus, address, rows, columns, simulate, command })
+}
+
+/// Converts text to panel bytes, turning `\0`-`\7` into glyph indices
+fn encode(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(d @ '0'..='7') => bytes.push(d as u8 - b'0'),
+                Some(other) => bytes.push(other as u8),
+                None => bytes.push(b'\\'),
+            },
+            c if (0x20..=0x7E).contains(&(c as u32)) || (0x80..=0xFF).contains(&(c as u32)) => bytes.push(c as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
+
+fn execute<T: Transport>(screen: &mut Screen<T>, command: &Command, columns: u8) -> Result<(), QwiicLcdError> {
+    match command {
+        Command::Print { text, row, col, align, clear } => {
+            if *clear {
+                screen.clear()?;
+            }
+            let mut bytes = encode(text);
+            let columns = columns as usize;
+            let col = match align {
+                None => *col,
+                Some(align) => {
+                    bytes.truncate(columns);
+                    let start = match align {
+                        Align::Left => 0,
+                        Align::Center => (columns - bytes.len()) / 2,
+                        Align::Right => columns - bytes.len(),
+                    };
+                    let mut line = vec![b' '; columns];
+                    line[start..start + bytes.len()].copy_from_slice(&bytes);
+                    bytes = line;
+                    0
+                }
+            };
+            screen.move_cursor(*row, col)?;
+            bytes.truncate(columns.saturating_sub(col));
+            screen.print_bytes(&bytes)
+        }
+        Command::Clear => screen.clear(),
+        Command::Backlight(r, g, b) => screen.change_backlight(*r, *g, *b),
+        Command::Contrast(contrast) => screen.set_contrast(*contrast),
+        Command::Cursor(on) => screen.enable_cursor(*on),
+        Command::Blink(on) => screen.enable_blink(*on),
+        Command::DefineGlyph { index, rows } => screen.create_character(*index, *rows),
+        Command::SetAddress(address) => screen.set_address(*address),
+        Command::Splash(SplashAction::Enable) => screen.enable_splash(true),
+        Command::Splash(SplashAction::Disable) => screen.enable_splash(false),
+        Command::Splash(SplashAction::Save) => screen.save_splash(),
+        // Handled before a screen is opened
+        Command::Help | Command::Scan => Ok(()),
+    }
+}
+
+fn run(args: &[String]) -> Result<(), CliError> {
+    let options = parse_args(args)?;
+    let config = ScreenConfig::new(options.rows, options.columns);
+    match &options.command {
+        Command::Help => println!("{}", USAGE),
+        Command::Scan if options.simulate => return usage("scan probes a real bus and cannot be simulated".to_string()),
+        Command::Scan => {
+            for address in LinuxTransport::scan(&options.bus).map_err(QwiicLcdError::from)? {
+                println!("0x{:02X}", address);
+            }
+        }
+        command if options.simulate => {
+            let mut screen = Screen::simulated(config);
+            execute(&mut screen, command, options.columns)?;
+        }
+        command => {
+            let mut screen = Screen::new(config, &options.bus, options.address)?;
+            execute(&mut screen, command, options.columns)?;
+            if let Command::SetAddress(address) = command {
+                println!("address changed to 0x{:02X}; use --address 0x{:02X} from now on", address, address);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => {}
+        Err(CliError::Usage(message)) => {
+            eprintln!("qwiic-lcd: {}\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+        Err(CliError::Lcd(e)) => {
+            eprintln!("qwiic-lcd: {}", e);
+            process::exit(exit_code(&e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn command(line: &str) -> Command {
+        match parse_args(&args(line)) {
+            Ok(options) => options.command,
+            Err(CliError::Usage(message)) => panic!("{}: {}", line, message),
+            Err(CliError::Lcd(e)) => panic!("{}: {}", line, e),
+        }
+    }
+
+    fn is_usage_error(line: &str) -> bool {
+        matches!(parse_args(&args(line)), Err(CliError::Usage(_)))
+    }
+
+    #[test]
+    fn test_parse_global_flags() {
+        let options = match parse_args(&args("--bus /dev/i2c-3 --address 0x30 --size 16x2 --simulate clear")) {
+            Ok(options) => options,
+            Err(_) => panic!("valid arguments rejected"),
+        };
+        assert_eq!((options.bus.as_str(), options.address), ("/dev/i2c-3", 0x30));
+        assert_eq!((options.rows, options.columns, options.simulate), (2, 16, true));
+        assert_eq!(options.command, Command::Clear);
+        assert!(is_usage_error("--size huge clear"));
+        assert!(is_usage_error("--bus"));
+        assert!(is_usage_error(""));
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            command("print Hi --row 1 --align center"),
+            Command::Print { text: "Hi".into(), row: 1, col: 0, align: Some(Align::Center), clear: false }
+        );
+        assert_eq!(command("backlight 10,20,30"), Command::Backlight(10, 20, 30));
+        assert_eq!(command("contrast 40"), Command::Contrast(40));
+        assert_eq!(command("cursor off"), Command::Cursor(false));
+        assert_eq!(command("blink on"), Command::Blink(true));
+        assert_eq!(command("set-address 0x40"), Command::SetAddress(0x40));
+        assert_eq!(command("splash save"), Command::Splash(SplashAction::Save));
+        assert_eq!(
+            command("glyph define 2 ..... .#.#. ##### ##### .###. ..#.. 0 0x00"),
+            Command::DefineGlyph { index: 2, rows: [0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00] }
+        );
+        assert!(is_usage_error("glyph define 2 1,2,3"));
+        assert!(is_usage_error("contrast 300"));
+        assert!(is_usage_error("blink maybe"));
+        assert!(is_usage_error("scan now"));
+        assert!(is_usage_error("dance"));
+        assert!(is_usage_error("print Hi --col 2 --align right"));
+    }
+
+    #[test]
+    fn test_help_only_before_text() {
+        assert_eq!(command("--help"), Command::Help);
+        assert_eq!(command("--bus /dev/i2c-3 -h print Hi"), Command::Help);
+        assert_eq!(command("print Hi --help"), Command::Help);
+        assert_eq!(
+            command("print --row 1 -- --help"),
+            Command::Print { text: "--help".into(), row: 1, col: 0, align: None, clear: false }
+        );
+        assert!(is_usage_error("print -- a b"));
+        assert!(matches!(run(&args("--simulate scan")), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn test_execute_on_emulator() {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        execute(&mut screen, &command("print Hi --row 1 --align right"), 16).unwrap();
+        assert_eq!(screen.transport().row_text(1), "              Hi");
+        execute(&mut screen, &command("glyph define 0 1 2 3 4 5 6 7 8"), 16).unwrap();
+        execute(&mut screen, &command("print a\\0b --col 3"), 16).unwrap();
+        assert_eq!(screen.transport().row(0)[3..6], [b'a', 0, b'b']);
+        execute(&mut screen, &command("backlight purple"), 16).unwrap();
+        assert_eq!(screen.transport().backlight(), (128, 0, 255));
+
+        let error = execute(&mut screen, &command("print x --row 5"), 16).unwrap_err();
+        assert_eq!(exit_code(&error), 8);
+        let error = execute(&mut screen, &command("glyph define 9 0 0 0 0 0 0 0 0"), 16).unwrap_err();
+        assert_eq!(exit_code(&error), 10);
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let bus_error = |errno| QwiicLcdError::I2CError(i2cdev::linux::LinuxI2CError::Errno(errno));
+        let errors = [
+            QwiicLcdError::InitializationFailed("gone".into()),
+            bus_error(5),
+            bus_error(19),
+            QwiicLcdError::CommunicationTimeout,
+            QwiicLcdError::CircuitOpen { retry_after: std::time::Duration::ZERO },
+            QwiicLcdError::InvalidPosition { row: 9, col: 0, max_rows: 4, max_columns: 20 },
+            QwiicLcdError::InvalidCharacter('é'),
+            QwiicLcdError::InvalidCustomCharIndex(8),
+            QwiicLcdError::InvalidAddress(0x80),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(exit_code).collect();
+        codes.push(EXIT_USAGE);
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len() + 1);
+        assert!(!codes.contains(&0) && !codes.contains(&1));
+    }
+}
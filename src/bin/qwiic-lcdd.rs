@@ -0,0 +1,101 @@
+// Display daemon sharing one Qwiic SerLCD panel between several programs.
+//
+//     qwiic-lcdd [--bus /dev/i2c-1] [--address 0x72] [--size 20x4]
+//                [--socket /tmp/qwiic-lcdd.sock] [--simulate]
+//
+// Clients connect to the Unix socket and send one JSON request per line,
+// e.g. `{"op":"claim","row":0,"col":0,"rows":1,"columns":20}`.
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::process;
+
+use qwiic_lcd_rs::*;
+
+#[allow(dead_code)]
+mod common;
+
+use common::{parse_address, parse_size};
+
+const USAGE: &str = "usage: qwiic-lcdd [--bus PATH] [--address ADDR] [--size COLSxROWS] \
+                     [--socket PATH] [--simulate]";
+
+struct Options {
+    bus: String,
+    address: u16,
+    rows: u8,
+    columns: u8,
+    socket: String,
+    simulate: bool,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        bus: "/dev/i2c-1".to_string(),
+        address: 0x72,
+        rows: 4,
+        columns: 20,
+        socket: "/tmp/qwiic-lcdd.sock".to_string(),
+        simulate: false,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--bus" => options.bus = value()?,
+            "--address" => {
+                let value = value()?;
+                options.address = parse_address(&value).ok_or(format!("invalid address '{}'", value))?;
+            }
+            "--size" => {
+                let value = value()?;
+                (options.rows, options.columns) = parse_size(&value).ok_or(format!("invalid size '{}'", value))?;
+            }
+            "--socket" => options.socket = value()?,
+            "--simulate" => options.simulate = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+    Ok(options)
+}
+
+fn bind(path: &str) -> Result<UnixListener, String> {
+    // A socket file left behind by a previous run would make bind fail;
+    // anything else at that path is left alone
+    let stale_socket = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket());
+    if stale_socket && UnixStream::connect(path).is_err() {
+        fs::remove_file(path).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    UnixListener::bind(path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn serve<T: Transport + Send + 'static>(mut screen: Screen<T>, options: &Options) -> Result<(), String> {
+    screen.init().map_err(|e| e.to_string())?;
+    let listener = bind(&options.socket)?;
+    LcdDaemon::new(screen).serve(listener).map_err(|e| e.to_string())
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_args()?;
+    let config = ScreenConfig::new(options.rows, options.columns);
+    if options.simulate {
+        serve(Screen::simulated(config), &options)
+    } else {
+        let screen = Screen::new(config, &options.bus, options.address).map_err(|e| e.to_string())?;
+        serve(screen, &options)
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("qwiic-lcdd: {}", e);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,97 @@
+// LCDproc-compatible server for Qwiic SerLCD panels.
+//
+//     qwiic-lcdproc [--bus /dev/i2c-1] [--address 0x72] [--size 20x4]
+//                   [--listen 127.0.0.1:13666] [--backlight ffffff] [--simulate]
+//
+// Point lcdproc, lcdexec or any other LCDproc client at the listen address.
+
+use std::env;
+use std::net::TcpListener;
+use std::process;
+
+use qwiic_lcd_rs::*;
+
+#[allow(dead_code)]
+mod common;
+
+use common::{parse_address, parse_color, parse_size};
+
+const USAGE: &str = "usage: qwiic-lcdproc [--bus PATH] [--address ADDR] [--size COLSxROWS] \
+                     [--listen HOST:PORT] [--backlight RRGGBB] [--simulate]";
+
+struct Options {
+    bus: String,
+    address: u16,
+    rows: u8,
+    columns: u8,
+    listen: String,
+    backlight: (u8, u8, u8),
+    simulate: bool,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        bus: "/dev/i2c-1".to_string(),
+        address: 0x72,
+        rows: 4,
+        columns: 20,
+        listen: "127.0.0.1:13666".to_string(),
+        backlight: (255, 255, 255),
+        simulate: false,
+    };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--bus" => options.bus = value()?,
+            "--address" => {
+                let value = value()?;
+                options.address = parse_address(&value).ok_or(format!("invalid address '{}'", value))?;
+            }
+            "--size" => {
+                let value = value()?;
+                (options.rows, options.columns) = parse_size(&value).ok_or(format!("invalid size '{}'", value))?;
+            }
+            "--listen" => options.listen = value()?,
+            "--backlight" => {
+                let value = value()?;
+                options.backlight = parse_color(&value).ok_or(format!("invalid color '{}'", value))?;
+            }
+            "--simulate" => options.simulate = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            other => return Err(format!("unknown argument '{}'", other)),
+        }
+    }
+    Ok(options)
+}
+
+fn serve<T: Transport + Send + 'static>(mut screen: Screen<T>, options: &Options) -> Result<(), String> {
+    screen.init().map_err(|e| e.to_string())?;
+    let mut server = LcdProcServer::new(screen);
+    let (r, g, b) = options.backlight;
+    server.set_backlight_color(r, g, b);
+    let listener = TcpListener::bind(&options.listen).map_err(|e| format!("{}: {}", options.listen, e))?;
+    server.serve(listener).map_err(|e| e.to_string())
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_args()?;
+    let config = ScreenConfig::new(options.rows, options.columns);
+    if options.simulate {
+        serve(Screen::simulated(config), &options)
+    } else {
+        let screen = Screen::new(config, &options.bus, options.address).map_err(|e| e.to_string())?;
+        serve(screen, &options)
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("qwiic-lcdproc: {}", e);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,426 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use i2cdev::linux::LinuxI2CError;
+
+use crate::{Emulator, ImageTooLarge, PanelImage, RenderOptions, Screen, ScreenConfig, Transport};
+
+/// Time the HD44780 spends in each phase of the blinking cursor
+const BLINK_PHASE: Duration = Duration::from_micros(409_600);
+
+/// Shortest frame delay viewers honor, in GIF centiseconds
+const MIN_DELAY_CS: u64 = 2;
+
+/// Default number of state changes kept before the oldest are dropped
+const DEFAULT_MAX_CHANGES: usize = 10_000;
+
+/// How a `GifCapture` turns the recorded session into frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameSampling {
+    /// One frame per visible state change, plus the cursor blink phases
+    OnChange,
+    /// Frames sampled at a fixed rate (frames per second)
+    Fps(u32),
+}
+
+/// One frame of the animation: an image and how long it is shown
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedFrame {
+    /// The rendered panel
+    pub image: PanelImage,
+    /// Display time in GIF centiseconds
+    pub delay_cs: u16,
+}
+
+/// Transport that records an emulated session as an animated GIF
+///
+/// Every SMBus call is decoded by an `Emulator` and a snapshot of the
+/// panel is stored with the time it was received. When the GIF is written
+/// the snapshots are rendered with `Emulator::render_image`, so clips look
+/// exactly like the PNG exports, and marquees, backlight fades and the
+/// blinking cursor keep their real timing.
+///
+/// Only the last 10 000 changes are kept by default (see
+/// `set_max_changes`), so a long session turns into a clip of its end
+/// instead of growing without limit.
+///
+/// # Example
+/// ```no_run
+/// # use qwiic_lcd_rs::*;
+/// let mut screen = Screen::captured(ScreenConfig::new(2, 16));
+/// screen.print("Recording").unwrap();
+/// screen.enable_blink(true).unwrap();
+/// std::thread::sleep(std::time::Duration::from_secs(2));
+/// screen.transport().save_gif("session.gif").unwrap();
+/// ```
+pub struct GifCapture {
+    emulator: Emulator,
+    started: Instant,
+    snapshots: VecDeque<(Duration, Emulator)>,
+    max_changes: usize,
+    sampling: FrameSampling,
+    options: RenderOptions,
+}
+
+impl GifCapture {
+    /// Starts capturing a panel with the given dimensions
+    pub fn new(rows: u8, columns: u8) -> GifCapture {
+        let emulator = Emulator::new(rows, columns);
+        GifCapture {
+            snapshots: VecDeque::from([(Duration::ZERO, emulator.clone())]),
+            max_changes: DEFAULT_MAX_CHANGES,
+            emulator,
+            started: Instant::now(),
+            sampling: FrameSampling::OnChange,
+            options: RenderOptions::default(),
+        }
+    }
+
+    /// The emulated panel state
+    pub fn emulator(&self) -> &Emulator {
+        &self.emulator
+    }
+
+    /// Time since the capture started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Number of state changes recorded so far
+    pub fn changes(&self) -> usize {
+        self.snapshots.len() - 1
+    }
+
+    /// Limits how many changes are kept; older ones are dropped first
+    pub fn set_max_changes(&mut self, max_changes: usize) {
+        self.max_changes = max_changes.max(1);
+        self.trim();
+    }
+
+    /// Chooses how frames are sampled (default: on every change)
+    pub fn set_sampling(&mut self, sampling: FrameSampling) {
+        self.sampling = sampling;
+    }
+
+    /// Sets the image layout used for every frame
+    pub fn set_render_options(&mut self, options: RenderOptions) {
+        self.options = options;
+    }
+
+    /// Drops everything recorded so far and restarts the clock
+    ///
+    /// The current panel state becomes the first frame.
+    pub fn restart(&mut self) {
+        self.started = Instant::now();
+        self.snapshots = VecDeque::from([(Duration::ZERO, self.emulator.clone())]);
+    }
+
+    /// Renders the session up to now into frames
+    pub fn frames(&self) -> Result<Vec<CapturedFrame>, ImageTooLarge> {
+        self.frames_until(self.elapsed())
+    }
+
+    /// Encodes the session up to now as a looping GIF
+    pub fn write_gif<W: Write>(&self, out: W) -> io::Result<()> {
+        let frames = self.frames().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let (width, height) = frames
+            .first()
+            .map_or((0, 0), |frame| (frame.image.width(), frame.image.height()));
+        let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "image too large for a GIF");
+        let width = u16::try_from(width).map_err(|_| too_large())?;
+        let height = u16::try_from(height).map_err(|_| too_large())?;
+
+        let mut encoder = gif::Encoder::new(out, width, height, &[]).map_err(gif_error)?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(gif_error)?;
+        for frame in &frames {
+            let (palette, indices) = index_colors(&frame.image);
+            let gif_frame = gif::Frame {
+                width,
+                height,
+                delay: frame.delay_cs,
+                palette: Some(palette),
+                buffer: indices.into(),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&gif_frame).map_err(gif_error)?;
+        }
+        encoder.into_inner().map(|_| ())
+    }
+
+    /// Writes the session up to now to a GIF file
+    pub fn save_gif<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_gif(&mut out)?;
+        out.flush()
+    }
+
+    fn record(&mut self) {
+        self.record_at(self.elapsed());
+    }
+
+    /// Stores a snapshot unless the call left the panel unchanged
+    fn record_at(&mut self, at: Duration) {
+        if self.snapshots.back().is_some_and(|(_, last)| last.same_state(&self.emulator)) {
+            return;
+        }
+        self.snapshots.push_back((at, self.emulator.clone()));
+        self.trim();
+    }
+
+    /// Drops the oldest snapshots beyond the limit; the clip then starts
+    /// at the oldest one left
+    fn trim(&mut self) {
+        while self.snapshots.len() > self.max_changes + 1 {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Time of the first frame
+    fn origin(&self) -> Duration {
+        self.snapshots.front().map_or(Duration::ZERO, |(at, _)| *at)
+    }
+
+    /// Start times of the frames between the oldest snapshot and `end`
+    fn frame_starts(&self, end: Duration) -> Vec<Duration> {
+        let mut starts = Vec::new();
+        match self.sampling {
+            FrameSampling::Fps(fps) => {
+                let step = Duration::from_secs(1) / fps.max(1);
+                let mut at = self.origin();
+                while at < end {
+                    starts.push(at);
+                    at += step;
+                }
+            }
+            FrameSampling::OnChange => {
+                for (i, (at, lcd)) in self.snapshots.iter().enumerate() {
+                    starts.push(*at);
+                    if !(lcd.blink() && lcd.display_on()) {
+                        continue;
+                    }
+                    // Add a frame at every blink toggle while this state lasts
+                    let until = self.snapshots.get(i + 1).map_or(end, |(next, _)| *next);
+                    let mut toggle = BLINK_PHASE * (at.as_micros() / BLINK_PHASE.as_micros() + 1) as u32;
+                    while toggle < until {
+                        starts.push(toggle);
+                        toggle += BLINK_PHASE;
+                    }
+                }
+            }
+        }
+        starts
+    }
+
+    /// Panel state shown at the given time
+    fn state_at(&self, at: Duration) -> &Emulator {
+        let index = self.snapshots.partition_point(|(time, _)| *time <= at);
+        &self.snapshots[index.saturating_sub(1)].1
+    }
+
+    fn frames_until(&self, end: Duration) -> Result<Vec<CapturedFrame>, ImageTooLarge> {
+        let starts = self.frame_starts(end);
+        let mut frames: Vec<CapturedFrame> = Vec::new();
+        // Frame boundaries in centiseconds, rounded from the absolute time
+        // so rounding errors do not accumulate
+        let mut shown_from = (self.origin().as_millis() as u64 + 5) / 10;
+        for (i, start) in starts.iter().enumerate() {
+            let next = starts.get(i + 1).copied().unwrap_or(end).max(*start);
+            let next_cs = (next.as_millis() as u64 + 5) / 10;
+            let only = i + 1 == starts.len() && frames.is_empty();
+            if next_cs < shown_from + MIN_DELAY_CS && !only {
+                // Too short to be seen; the next frame takes over its time
+                continue;
+            }
+            let delay = next_cs.saturating_sub(shown_from).max(MIN_DELAY_CS);
+            let blink_phase = (start.as_micros() / BLINK_PHASE.as_micros())% 2 == 0;
+            let options = RenderOptions { blink_phase, ..self.options };
+            let image = self.state_at(*start).render_image(&options)?;
+            shown_from = next_cs.max(shown_from + delay);
+            match frames.last_mut() {
+                Some(previous) if previous.image == image => {
+                    previous.delay_cs = (previous.delay_cs as u64 + delay).min(u16::MAX as u64) as u16;
+                }
+                _ => frames.push(CapturedFrame { image, delay_cs: delay.min(u16::MAX as u64) as u16 }),
+            }
+        }
+        Ok(frames)
+    }
+}
+
+/// Builds a palette of the colors in an image and the matching indices
+fn index_colors(image: &PanelImage) -> (Vec<u8>, Vec<u8>) {
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let indices = image
+        .pixels()
+        .chunks(3)
+        .map(|rgb| {
+            let rgb = [rgb[0], rgb[1], rgb[2]];
+            match palette.iter().position(|color| *color == rgb) {
+                Some(index) => index as u8,
+                None if palette.len() < 256 => {
+                    palette.push(rgb);
+                    (palette.len() - 1) as u8
+                }
+                // A panel frame never has more than three colors
+                None => 0,
+            }
+        })
+        .collect();
+    (palette.concat(), indices)
+}
+
+fn gif_error(error: gif::EncodingError) -> io::Error {
+    match error {
+        gif::EncodingError::Io(e) => e,
+        other => io::Error::new(io::ErrorKind::InvalidData, other),
+    }
+}
+
+impl Transport for GifCapture {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.emulator.write_byte(value)?;
+        self.record();
+        Ok(())
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.emulator.write_byte_data(register, value)?;
+        self.record();
+        Ok(())
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        self.emulator.write_block(register, data)?;
+        self.record();
+        Ok(())
+    }
+
+    fn delay(&mut self, _duration: Duration) {}
+}
+
+impl Screen<GifCapture> {
+    /// Creates a Screen whose session is recorded for GIF export
+    pub fn captured(config: ScreenConfig) -> Screen<GifCapture> {
+        let capture = GifCapture::new(config.max_rows(), config.max_columns());
+        Screen::with_transport(config, capture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Applies bytes to the capture as if they arrived at the given time
+    fn feed_at(capture: &mut GifCapture, at: Duration, bytes: &[u8]) {
+        capture.emulator.feed(bytes);
+        capture.record_at(at);
+    }
+
+    #[test]
+    fn test_frames_follow_changes() {
+        let mut capture = GifCapture::new(1, 8);
+        feed_at(&mut capture, ms(100), b"A");
+        // Same-instant updates collapse into a single frame
+        feed_at(&mut capture, ms(500), b"B");
+        feed_at(&mut capture, ms(501), b"C");
+        // Backlight fade steps
+        feed_at(&mut capture, ms(800), &[0x7C, 0x2B, 200, 200, 200]);
+        feed_at(&mut capture, ms(900), &[0x7C, 0x2B, 100, 100, 100]);
+
+        let frames = capture.frames_until(ms(1500)).unwrap();
+        let delays: Vec<u16> = frames.iter().map(|f| f.delay_cs).collect();
+        assert_eq!(delays, vec![10, 40, 30, 10, 60]);
+        assert_eq!(delays.iter().map(|d| *d as u32).sum::<u32>(), 150);
+        assert_eq!(frames[2].image, {
+            let mut lcd = Emulator::new(1, 8);
+            lcd.feed(b"ABC");
+            lcd.render_image(&RenderOptions::default()).unwrap()
+        });
+    }
+
+    #[test]
+    fn test_blink_and_fixed_rate() {
+        let mut capture = GifCapture::new(1, 8);
+        feed_at(&mut capture, ms(0), &[0xFE, 0x0D]);
+        let frames = capture.frames_until(ms(1229)).unwrap();
+        // The cursor block alternates every 409.6ms
+        assert_eq!(frames.len(), 3);
+        assert_ne!(frames[0].image, frames[1].image);
+        assert_eq!(frames[0].image, frames[2].image);
+        assert_eq!(frames[0].delay_cs, 41);
+
+        capture.set_sampling(FrameSampling::Fps(10));
+        let frames = capture.frames_until(ms(1000)).unwrap();
+        assert_eq!(frames.iter().map(|f| f.delay_cs as u32).sum::<u32>(), 100);
+        assert!(frames.len() >= 3);
+    }
+
+    #[test]
+    fn test_write_gif() {
+        let mut screen = Screen::captured(ScreenConfig::new(2, 16));
+        screen.print("Hello").unwrap();
+        screen.change_backlight(0, 0, 255).unwrap();
+        assert!(screen.transport().changes() >= 2);
+
+        let mut bytes = Vec::new();
+        screen.transport().write_gif(&mut bytes).unwrap();
+        assert!(bytes.starts_with(b"GIF89a"));
+        assert_eq!(bytes.last(), Some(&0x3B));
+
+        // Writes that leave the panel as it was aren't stored again
+        let changes = screen.transport().changes();
+        screen.change_backlight(0, 0, 255).unwrap();
+        screen.move_cursor(1, 0).unwrap();
+        screen.move_cursor(1, 0).unwrap();
+        assert_eq!(screen.transport().changes(), changes + 1);
+
+        screen.transport_mut().restart();
+        assert_eq!(screen.transport().changes(), 0);
+        assert_eq!(screen.transport().frames().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_max_changes_drops_oldest() {
+        let mut capture = GifCapture::new(1, 8);
+        capture.set_max_changes(2);
+        feed_at(&mut capture, ms(100), b"A");
+        feed_at(&mut capture, ms(200), b"B");
+        feed_at(&mut capture, ms(300), b"C");
+        assert_eq!(capture.changes(), 2);
+
+        // The clip now starts at "A" and runs to the end
+        let frames = capture.frames_until(ms(500)).unwrap();
+        let delays: Vec<u16> = frames.iter().map(|f| f.delay_cs).collect();
+        assert_eq!(delays, vec![10, 10, 20]);
+        assert_eq!(frames[0].image, {
+            let mut lcd = Emulator::new(1, 8);
+            lcd.feed(b"A");
+            lcd.render_image(&RenderOptions::default()).unwrap()
+        });
+
+        capture.set_sampling(FrameSampling::Fps(10));
+        let frames = capture.frames_until(ms(500)).unwrap();
+        assert_eq!(frames.iter().map(|f| f.delay_cs as u32).sum::<u32>(), 40);
+    }
+}
@@ -0,0 +1,371 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant};
+
+use crate::{FrameBuffer, MoveDirection, QwiicLcdError, Screen, Transport};
+
+type ScreenResult = Result<(), QwiicLcdError>;
+
+/// Characters per DDRAM line of the HD44780
+const DDRAM_LINE: usize = 40;
+
+/// Content shown by a `Carousel`
+///
+/// `render` draws into a blank framebuffer the size of the panel and
+/// returns `false` when the page has nothing to show, so the carousel
+/// skips it. Closures `FnMut(&mut FrameBuffer) -> bool` are pages.
+pub trait Page: Send {
+    fn render(&mut self, frame: &mut FrameBuffer) -> bool;
+}
+
+impl<F> Page for F
+where
+    F: FnMut(&mut FrameBuffer) -> bool + Send,
+{
+    fn render(&mut self, frame: &mut FrameBuffer) -> bool {
+        self(frame)
+    }
+}
+
+/// How a `Carousel` changes pages
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transition {
+    /// Replace the page at once
+    Cut,
+    /// Scroll the next page in from the right with `shift_display`
+    ///
+    /// The panel must leave room in its display RAM to shift through, which
+    /// only panels of up to 2 rows and 20 columns do; larger panels fall
+    /// back to `Cut`.
+    Slide,
+    /// Reveal the next page one column at a time, left to right
+    Wipe,
+}
+
+struct Entry {
+    name: String,
+    page: Box<dyn Page>,
+    dwell: Duration,
+    priority: i32,
+    sticky: bool,
+}
+
+/// Rotates through pages, each shown for its own dwell time
+///
+/// Pages rotate from the highest priority down, in insertion order for
+/// equal priorities. While any page is sticky, only sticky pages are
+/// shown, so an urgent alert preempts the rotation until it is unstuck.
+/// `tick` is meant to be called from the main loop: it re-renders the
+/// current page (sending only changed cells) and moves on once the dwell
+/// time has passed or the page has no data.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// # use std::time::{Duration, Instant};
+/// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+/// let mut carousel = Carousel::new();
+/// carousel.add("net", Duration::from_secs(5), |frame: &mut FrameBuffer| {
+///     frame.write_str("eth0 10.0.0.7");
+///     true
+/// });
+/// carousel.add("disk", Duration::from_secs(5), |frame: &mut FrameBuffer| {
+///     frame.write_str("/ 42% used");
+///     true
+/// });
+///
+/// let start = Instant::now();
+/// carousel.tick(&mut screen, start).unwrap();
+/// assert_eq!(screen.transport().row_text(0), "eth0 10.0.0.7   ");
+/// carousel.tick(&mut screen, start + Duration::from_secs(5)).unwrap();
+/// assert_eq!(carousel.current(), Some("disk"));
+/// ```
+pub struct Carousel {
+    pages: Vec<Entry>,
+    current: Option<usize>,
+    since: Option<Instant>,
+    transition: Transition,
+    step_delay: Duration,
+}
+
+impl Default for Carousel {
+    fn default() -> Self {
+        Carousel::new()
+    }
+}
+
+impl Carousel {
+    /// Creates an empty carousel using `Transition::Cut`
+    pub fn new() -> Carousel {
+        Carousel {
+            pages: Vec::new(),
+            current: None,
+            since: None,
+            transition: Transition::Cut,
+            step_delay: Duration::from_millis(30),
+        }
+    }
+
+    /// Adds a page shown for `dwell` each time it comes up
+    pub fn add<P: Page + 'static>(&mut self, name: &str, dwell: Duration, page: P) {
+        self.pages.push(Entry { name: name.to_string(), page: Box::new(page), dwell, priority: 0, sticky: false });
+    }
+
+    /// Removes a page; returns whether it existed
+    pub fn remove(&mut self, name: &str) -> bool {
+        let Some(index) = self.index(name) else { return false };
+        self.pages.remove(index);
+        self.current = match self.current {
+            Some(current) if current == index => None,
+            Some(current) if current > index => Some(current - 1),
+            current => current,
+        };
+        true
+    }
+
+    /// Sets a page's priority (default 0, higher first); returns whether it exists
+    pub fn set_priority(&mut self, name: &str, priority: i32) -> bool {
+        self.entry(name).map(|entry| entry.priority = priority).is_some()
+    }
+
+    /// Marks a page as urgent so it preempts the rotation; returns whether it exists
+    pub fn set_sticky(&mut self, name: &str, sticky: bool) -> bool {
+        self.entry(name).map(|entry| entry.sticky = sticky).is_some()
+    }
+
+    /// Changes how pages replace each other
+    pub fn set_transition(&mut self, transition: Transition) {
+        self.transition = transition;
+    }
+
+    /// Pause between the steps of a slide or wipe (30ms by default)
+    pub fn set_step_delay(&mut self, delay: Duration) {
+        self.step_delay = delay;
+    }
+
+    /// Name of the page on screen
+    pub fn current(&self) -> Option<&str> {
+        self.current.map(|index| self.pages[index].name.as_str())
+    }
+
+    /// Shows the current page, moving to the next one when it is due
+    pub fn tick<T: Transport>(&mut self, screen: &mut Screen<T>, now: Instant) -> ScreenResult {
+        let blank = FrameBuffer::new(screen.framebuffer().rows(), screen.framebuffer().columns());
+        let sticky = self.pages.iter().any(|entry| entry.sticky);
+
+        if let Some(index) = self.current {
+            let entry = &mut self.pages[index];
+            let due = self.since.is_some_and(|since| now.duration_since(since) >= entry.dwell);
+            if (entry.sticky || !sticky) && !due {
+                let mut frame = blank.clone();
+                if entry.page.render(&mut frame) {
+                    return screen.present(&frame);
+                }
+            }
+        }
+        self.advance(screen, now, blank)
+    }
+
+    /// Moves to the next page with data now, regardless of dwell times
+    pub fn next<T: Transport>(&mut self, screen: &mut Screen<T>, now: Instant) -> ScreenResult {
+        let blank = FrameBuffer::new(screen.framebuffer().rows(), screen.framebuffer().columns());
+        self.advance(screen, now, blank)
+    }
+
+    fn advance<T: Transport>(&mut self, screen: &mut Screen<T>, now: Instant, blank: FrameBuffer) -> ScreenResult {
+        let order = self.rotation();
+        let start = self
+            .current
+            .and_then(|current| order.iter().position(|index| *index == current))
+            .map_or(0, |position| position + 1);
+
+        for offset in 0..order.len() {
+            let index = order[(start + offset) % order.len()];
+            let mut frame = blank.clone();
+            if !self.pages[index].page.render(&mut frame) {
+                continue;
+            }
+            let changed = self.current != Some(index);
+            self.current = Some(index);
+            self.since = Some(now);
+            #[cfg(feature = "log")]
+            log::debug!("carousel showing {}", self.pages[index].name);
+            return match self.transition {
+                Transition::Slide if changed => self.slide(screen, &frame),
+                Transition::Wipe if changed => self.wipe(screen, &frame),
+                _ => screen.present(&frame),
+            };
+        }
+
+        self.current = None;
+        self.since = None;
+        Ok(())
+    }
+
+    /// Indices of the pages in rotation, highest priority first
+    fn rotation(&self) -> Vec<usize> {
+        let sticky = self.pages.iter().any(|entry| entry.sticky);
+        let mut order: Vec<usize> = (0..self.pages.len()).filter(|i| self.pages[*i].sticky || !sticky).collect();
+        order.sort_by_key(|i| -(self.pages[*i].priority as i64));
+        order
+    }
+
+    /// Scrolls the old page out to the left and `frame` in from the right
+    ///
+    /// Each display RAM line is 40 characters long and the display shift
+    /// wraps around it. Once the window has moved past the visible
+    /// addresses, `frame` is written there; shifting on until the window
+    /// wraps back to the start brings it in from the right.
+    fn slide<T: Transport>(&self, screen: &mut Screen<T>, frame: &FrameBuffer) -> ScreenResult {
+        let (rows, columns) = (frame.rows() as usize, frame.columns() as usize);
+        if rows > 2 || columns * 2 > DDRAM_LINE || screen.display_shift != 0 {
+            return screen.present(frame);
+        }
+        for step in 1..=DDRAM_LINE {
+            screen.shift_display(MoveDirection::Left)?;
+            if step == columns {
+                screen.present(frame)?;
+            }
+            screen.transport.delay(self.step_delay);
+        }
+        screen.home()
+    }
+
+    /// Replaces the display one column at a time
+    fn wipe<T: Transport>(&self, screen: &mut Screen<T>, frame: &FrameBuffer) -> ScreenResult {
+        let mut shown = screen.framebuffer().clone();
+        for col in 0..frame.columns() as usize {
+            for row in 0..frame.rows() as usize {
+                shown.set(row, col, frame.get(row, col).unwrap_or(b' '));
+            }
+            screen.present(&shown)?;
+            screen.transport.delay(self.step_delay);
+        }
+        Ok(())
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.pages.iter().position(|entry| entry.name == name)
+    }
+
+    fn entry(&mut self, name: &str) -> Option<&mut Entry> {
+        self.pages.iter_mut().find(|entry| entry.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+    use std::sync::{Arc, Mutex};
+
+    fn text(s: &'static str) -> impl Page {
+        move |frame: &mut FrameBuffer| {
+            frame.write_str(s);
+            true
+        }
+    }
+
+    fn screen() -> Screen<Emulator> {
+        Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16))
+    }
+
+    #[test]
+    fn test_rotation_skips_pages_without_data() {
+        let alerts = Arc::new(Mutex::new(None::<&'static str>));
+        let pending = Arc::clone(&alerts);
+        let mut carousel = Carousel::new();
+        carousel.add("net", Duration::from_secs(5), text("net"));
+        carousel.add("alerts", Duration::from_secs(2), move |frame: &mut FrameBuffer| match *pending.lock().unwrap() {
+            Some(alert) => {
+                frame.write_str(alert);
+                true
+            }
+            None => false,
+        });
+        carousel.add("disk", Duration::from_secs(5), text("disk"));
+
+        let mut screen = screen();
+        let start = Instant::now();
+        let shown = |carousel: &mut Carousel, screen: &mut Screen<Emulator>, secs| {
+            carousel.tick(screen, start + Duration::from_secs(secs)).unwrap();
+            carousel.current().map(str::to_string)
+        };
+        assert_eq!(shown(&mut carousel, &mut screen, 0).as_deref(), Some("net"));
+        assert_eq!(shown(&mut carousel, &mut screen, 4).as_deref(), Some("net"));
+        assert_eq!(shown(&mut carousel, &mut screen, 5).as_deref(), Some("disk"));
+
+        *alerts.lock().unwrap() = Some("fan failed");
+        assert_eq!(shown(&mut carousel, &mut screen, 10).as_deref(), Some("net"));
+        assert_eq!(shown(&mut carousel, &mut screen, 15).as_deref(), Some("alerts"));
+        assert_eq!(screen.transport().row_text(0), "fan failed      ");
+
+        // The page runs out of data before its dwell time is over
+        *alerts.lock().unwrap() = None;
+        assert_eq!(shown(&mut carousel, &mut screen, 16).as_deref(), Some("disk"));
+    }
+
+    #[test]
+    fn test_sticky_pages_preempt_by_priority() {
+        let mut carousel = Carousel::new();
+        carousel.add("net", Duration::from_secs(5), text("net"));
+        carousel.add("temp", Duration::from_secs(5), text("temp"));
+        carousel.add("overheat", Duration::from_secs(1), text("OVERHEAT"));
+        carousel.add("intrusion", Duration::from_secs(1), text("INTRUSION"));
+        carousel.set_priority("temp", 1);
+
+        let mut screen = screen();
+        let start = Instant::now();
+        carousel.tick(&mut screen, start).unwrap();
+        assert_eq!(carousel.current(), Some("temp"));
+
+        carousel.set_sticky("overheat", true);
+        carousel.set_sticky("intrusion", true);
+        carousel.set_priority("intrusion", 9);
+        for secs in [1, 2, 3] {
+            carousel.tick(&mut screen, start + Duration::from_secs(secs)).unwrap();
+            let expected = if secs % 2 == 1 { "intrusion" } else { "overheat" };
+            assert_eq!(carousel.current(), Some(expected));
+        }
+
+        carousel.set_sticky("overheat", false);
+        carousel.set_sticky("intrusion", false);
+        carousel.tick(&mut screen, start + Duration::from_millis(3500)).unwrap();
+        assert_eq!(carousel.current(), Some("intrusion"));
+        carousel.tick(&mut screen, start + Duration::from_secs(4)).unwrap();
+        assert_eq!(carousel.current(), Some("temp"));
+        assert!(carousel.remove("intrusion"));
+        assert_eq!(carousel.current(), Some("temp"));
+    }
+
+    #[test]
+    fn test_slide_and_wipe_transitions() {
+        let mut screen = screen();
+        let mut carousel = Carousel::new();
+        carousel.add("a", Duration::from_secs(1), text("first page"));
+        carousel.add("b", Duration::from_secs(1), text("second page"));
+        carousel.set_transition(Transition::Slide);
+        let start = Instant::now();
+        carousel.tick(&mut screen, start).unwrap();
+        carousel.tick(&mut screen, start + Duration::from_secs(1)).unwrap();
+        assert_eq!(screen.transport().row_text(0), "second page     ");
+        assert_eq!(screen.transport().display_shift(), 0);
+        assert_eq!(screen.framebuffer().row_text(0), "second page     ");
+
+        carousel.set_transition(Transition::Wipe);
+        carousel.next(&mut screen, start + Duration::from_secs(2)).unwrap();
+        assert_eq!(screen.transport().row_text(0), "first page      ");
+    }
+}
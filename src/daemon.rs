@@ -0,0 +1,703 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{FrameBuffer, Screen, Transport};
+
+/// How often the daemon composites and updates the panel
+pub const REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Longest request line a client may send, in bytes; longer lines get an
+/// error reply and the connection is closed
+pub const MAX_LINE: usize = 64 * 1024;
+
+fn default_notification_ms() -> u64 {
+    3000
+}
+
+/// One JSON-line request sent to `qwiic-lcdd`
+///
+/// Requests are tagged by `op`, for example
+/// `{"op":"claim","row":0,"col":0,"rows":1,"columns":20}` or
+/// `{"op":"text","text":"CPU 12%"}`. Text coordinates are relative to the
+/// client's claimed region; the characters `\u0000`-`\u0007` refer to the
+/// client's own custom glyphs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Request {
+    /// Claims (or moves) the client's region; higher layers cover lower ones
+    Claim {
+        #[serde(default)]
+        name: Option<String>,
+        row: usize,
+        col: usize,
+        rows: u8,
+        columns: u8,
+        #[serde(default)]
+        layer: i32,
+    },
+    /// Writes text into the region
+    Text {
+        #[serde(default)]
+        row: usize,
+        #[serde(default)]
+        col: usize,
+        text: String,
+        /// Blank the region first
+        #[serde(default)]
+        clear: bool,
+    },
+    /// Blanks the region
+    Clear,
+    /// Sets the backlight color (last request wins)
+    Backlight { r: u8, g: u8, b: u8 },
+    /// Defines the client's custom glyph 0-7
+    Glyph { index: u8, rows: [u8; 8] },
+    /// Shows a message over every region for a while
+    Notify {
+        text: String,
+        #[serde(default = "default_notification_ms")]
+        duration_ms: u64,
+    },
+    /// Gives up the region
+    Release,
+    /// Returns the composited panel contents
+    Read,
+}
+
+/// Reply to a `Request`, one JSON line per request
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Panel rows, for `read`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows: Option<Vec<String>>,
+}
+
+impl Response {
+    fn success() -> Response {
+        Response { ok: true, ..Response::default() }
+    }
+
+    fn failure(error: impl Into<String>) -> Response {
+        Response { ok: false, error: Some(error.into()), rows: None }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Layer {
+    name: Option<String>,
+    row: usize,
+    col: usize,
+    layer: i32,
+    cells: FrameBuffer,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Client {
+    layer: Option<Layer>,
+    /// Physical CGRAM slot behind each of the client's glyphs
+    glyphs: [Option<usize>; 8],
+}
+
+/// Shared CGRAM slot: bitmap, number of client glyphs using it, and
+/// whether the panel still has to be updated
+#[derive(Clone, Copy, Debug)]
+struct Slot {
+    rows: [u8; 8],
+    users: usize,
+    dirty: bool,
+}
+
+/// Composites the regions of several clients into one panel
+///
+/// Each client claims a rectangular region on a layer; when regions
+/// overlap, the higher layer (then the later claim) wins. Custom glyphs are
+/// virtualized: every client numbers its glyphs 0-7 and the compositor maps
+/// them onto the 8 CGRAM slots, sharing slots between identical bitmaps.
+/// Notifications temporarily cover the whole panel, one after the other.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// # use std::time::Instant;
+/// let mut compositor = Compositor::new(2, 16);
+/// let health = compositor.connect();
+/// let net = compositor.connect();
+/// compositor.handle(health, Request::Claim { name: None, row: 0, col: 0, rows: 1, columns: 16, layer: 0 });
+/// compositor.handle(net, Request::Claim { name: None, row: 1, col: 0, rows: 1, columns: 16, layer: 0 });
+/// compositor.handle(health, Request::Text { row: 0, col: 0, text: "CPU 12%".into(), clear: true });
+/// compositor.handle(net, Request::Text { row: 0, col: 0, text: "wlan0 up".into(), clear: true });
+/// let frame = compositor.compose(Instant::now());
+/// assert_eq!(frame.row_text(0), "CPU 12%         ");
+/// assert_eq!(frame.row_text(1), "wlan0 up        ");
+/// ```
+#[derive(Debug)]
+pub struct Compositor {
+    rows: u8,
+    columns: u8,
+    clients: BTreeMap<u32, Client>,
+    next_client: u32,
+    slots: [Option<Slot>; 8],
+    backlight: Option<(u8, u8, u8)>,
+    notifications: VecDeque<(String, Duration)>,
+    notification: Option<(String, Instant)>,
+    frame: FrameBuffer,
+}
+
+impl Compositor {
+    /// Creates a compositor for a panel of the given size
+    pub fn new(rows: u8, columns: u8) -> Compositor {
+        Compositor {
+            rows,
+            columns,
+            clients: BTreeMap::new(),
+            next_client: 1,
+            slots: [None; 8],
+            backlight: None,
+            notifications: VecDeque::new(),
+            notification: None,
+            frame: FrameBuffer::new(rows, columns),
+        }
+    }
+
+    /// Registers a new client and returns its id
+    pub fn connect(&mut self) -> u32 {
+        let id = self.next_client;
+        self.next_client += 1;
+        self.clients.insert(id, Client::default());
+        id
+    }
+
+    /// Drops a client, its region and its glyphs
+    pub fn disconnect(&mut self, client: u32) {
+        if let Some(removed) = self.clients.remove(&client) {
+            for slot in removed.glyphs.iter().flatten() {
+                self.release_slot(*slot);
+            }
+        }
+    }
+
+    /// Name given in the client's claim, if any
+    pub fn client_name(&self, client: u32) -> Option<&str> {
+        self.clients.get(&client)?.layer.as_ref()?.name.as_deref()
+    }
+
+    /// Backlight color last requested by any client
+    pub fn backlight(&self) -> Option<(u8, u8, u8)> {
+        self.backlight
+    }
+
+    /// Takes the CGRAM slots whose bitmap changed since the last call
+    pub fn take_glyph_updates(&mut self) -> Vec<(u8, [u8; 8])> {
+        let mut updates = Vec::new();
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(slot) = slot.as_mut().filter(|s| s.dirty) {
+                slot.dirty = false;
+                updates.push((index as u8, slot.rows));
+            }
+        }
+        updates
+    }
+
+    /// Handles one request from a client
+    pub fn handle(&mut self, client: u32, request: Request) -> Response {
+        let result = match request {
+            Request::Claim { name, row, col, rows, columns, layer } => self.claim(client, name, row, col, rows, columns, layer),
+            Request::Text { row, col, text, clear } => self.text(client, row, col, &text, clear),
+            Request::Clear => self.layer(client).map(|layer| layer.cells.clear()),
+            Request::Backlight { r, g, b } => {
+                self.backlight = Some((r, g, b));
+                Ok(())
+            }
+            Request::Glyph { index, rows } => self.define_glyph(client, index, rows),
+            Request::Notify { text, duration_ms } => {
+                // Capped so the deadline can't overflow `Instant`
+                let duration_ms = duration_ms.min(u32::MAX as u64);
+                self.notifications.push_back((text, Duration::from_millis(duration_ms)));
+                Ok(())
+            }
+            Request::Release => {
+                self.clients.entry(client).or_default().layer = None;
+                Ok(())
+            }
+            Request::Read => {
+                let rows = (0..self.rows as usize).map(|row| self.frame.row_text(row)).collect();
+                return Response { ok: true, error: None, rows: Some(rows) };
+            }
+        };
+        match result {
+            Ok(()) => Response::success(),
+            Err(e) => Response::failure(e),
+        }
+    }
+
+    /// Handles one JSON line and returns the JSON reply
+    pub fn handle_line(&mut self, client: u32, line: &str) -> String {
+        let response = match serde_json::from_str::<Request>(line) {
+            Ok(request) => self.handle(client, request),
+            Err(e) => Response::failure(format!("invalid request: {}", e)),
+        };
+        serde_json::to_string(&response).unwrap_or_else(|_| r#"{"ok":false}"#.to_string())
+    }
+
+    /// Composites every region and the current notification
+    pub fn compose(&mut self, now: Instant) -> FrameBuffer {
+        let mut frame = FrameBuffer::new(self.rows, self.columns);
+        let mut layers: Vec<(&Client, &Layer)> = self
+            .clients
+            .values()
+            .filter_map(|client| client.layer.as_ref().map(|layer| (client, layer)))
+            .collect();
+        // Stable sort keeps claim order within a layer
+        layers.sort_by_key(|(_, layer)| layer.layer);
+        for (client, layer) in layers {
+            for row in 0..layer.cells.rows() as usize {
+                for (col, byte) in layer.cells.row(row).iter().enumerate() {
+                    let byte = match byte {
+                        0..=7 => client.glyphs[*byte as usize].map_or(b'?', |slot| slot as u8),
+                        b => *b,
+                    };
+                    frame.set(layer.row + row, layer.col + col, byte);
+                }
+            }
+        }
+
+        if self.notification.as_ref().is_some_and(|(_, until)| *until <= now) {
+            self.notification = None;
+        }
+        if self.notification.is_none() {
+            if let Some((text, duration)) = self.notifications.pop_front() {
+                self.notification = Some((text, now + duration));
+            }
+        }
+        if let Some((text, _)) = &self.notification {
+            draw_notification(&mut frame, text);
+        }
+        self.frame = frame.clone();
+        frame
+    }
+
+    fn layer(&mut self, client: u32) -> Result<&mut Layer, String> {
+        self.clients
+            .entry(client)
+            .or_default()
+            .layer
+            .as_mut()
+            .ok_or_else(|| "claim a region first".to_string())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn claim(
+        &mut self,
+        client: u32,
+        name: Option<String>,
+        row: usize,
+        col: usize,
+        rows: u8,
+        columns: u8,
+        layer: i32,
+    ) -> Result<(), String> {
+        let fits = |start: usize, len: u8, max: u8| start.checked_add(len as usize).is_some_and(|end| end <= max as usize);
+        if rows == 0 || columns == 0 || !fits(row, rows, self.rows) || !fits(col, columns, self.columns) {
+            return Err(format!(
+                "region {}x{} at ({}, {}) does not fit the {}x{} panel",
+                columns, rows, row, col, self.columns, self.rows
+            ));
+        }
+        self.clients.entry(client).or_default().layer = Some(Layer {
+            name,
+            row,
+            col,
+            layer,
+            cells: FrameBuffer::new(rows, columns),
+        });
+        Ok(())
+    }
+
+    fn text(&mut self, client: u32, row: usize, col: usize, text: &str, clear: bool) -> Result<(), String> {
+        let cells = &mut self.layer(client)?.cells;
+        if row >= cells.rows() as usize || col >= cells.columns() as usize {
+            return Err(format!("position ({}, {}) is outside the region", row, col));
+        }
+        if clear {
+            cells.clear();
+        }
+        for (i, byte) in encode(text).into_iter().enumerate() {
+            cells.set(row, col + i, byte);
+        }
+        Ok(())
+    }
+
+    fn define_glyph(&mut self, client: u32, index: u8, rows: [u8; 8]) -> Result<(), String> {
+        if index > 7 {
+            return Err(format!("invalid glyph index {}, must be 0-7", index));
+        }
+        let rows = rows.map(|row| row & 0x1F);
+        let previous = self.clients.entry(client).or_default().glyphs[index as usize];
+        if let Some(slot) = previous {
+            if self.slots[slot].is_some_and(|s| s.rows == rows) {
+                return Ok(());
+            }
+            self.release_slot(slot);
+        }
+        let slot = match self.slots.iter().position(|s| s.is_some_and(|s| s.rows == rows)) {
+            Some(shared) => shared,
+            None => self.slots.iter().position(Option::is_none).ok_or("all 8 glyph slots are in use")?,
+        };
+        let entry = self.slots[slot].get_or_insert(Slot { rows, users: 0, dirty: true });
+        entry.users += 1;
+        self.clients.entry(client).or_default().glyphs[index as usize] = Some(slot);
+        Ok(())
+    }
+
+    fn release_slot(&mut self, slot: usize) {
+        if let Some(entry) = self.slots[slot].as_mut() {
+            entry.users -= 1;
+            if entry.users == 0 {
+                self.slots[slot] = None;
+            }
+        }
+    }
+}
+
+/// Draws a notification centered on the panel, wrapped to its width
+fn draw_notification(frame: &mut FrameBuffer, text: &str) {
+    let columns = frame.columns() as usize;
+    let rows = frame.rows() as usize;
+    if columns == 0 {
+        return;
+    }
+    let bytes = encode(text);
+    let lines: Vec<&[u8]> = bytes.chunks(columns).take(rows).collect();
+    let top = (rows - lines.len()) / 2;
+    for (i, line) in lines.iter().enumerate() {
+        let start = (columns - line.len()) / 2;
+        for col in 0..columns {
+            let byte = col.checked_sub(start).and_then(|c| line.get(c)).copied().unwrap_or(b' ');
+            frame.set(top + i, col, byte);
+        }
+    }
+}
+
+/// Converts request text to panel bytes; `\u0000`-`\u0007` stay glyph
+/// references and anything outside Latin-1 becomes '?'
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            code @ (0x00..=0x07 | 0x20..=0x7E | 0xA0..=0xFF) => code as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Daemon that owns a `Screen` and serves the JSON-lines API on a Unix socket
+///
+/// Every connection is a client of the shared `Compositor`. A refresh
+/// thread composites the regions every `REFRESH_INTERVAL`, loads changed
+/// glyph slots and sends only the changed cells with `Screen::present`.
+///
+/// # Example
+/// ```no_run
+/// # use qwiic_lcd_rs::*;
+/// # use std::os::unix::net::UnixListener;
+/// let screen = Screen::new(ScreenConfig::default(), "/dev/i2c-1", 0x72).unwrap();
+/// let daemon = LcdDaemon::new(screen);
+/// daemon.serve(UnixListener::bind("/run/qwiic-lcdd.sock").unwrap()).unwrap();
+/// ```
+pub struct LcdDaemon<T: Transport + Send + 'static> {
+    compositor: Arc<Mutex<Compositor>>,
+    screen: Arc<Mutex<Screen<T>>>,
+}
+
+impl<T: Transport + Send + 'static> LcdDaemon<T> {
+    /// Creates a daemon that renders to the given screen
+    pub fn new(screen: Screen<T>) -> LcdDaemon<T> {
+        let framebuffer = screen.framebuffer();
+        LcdDaemon {
+            compositor: Arc::new(Mutex::new(Compositor::new(framebuffer.rows(), framebuffer.columns()))),
+            screen: Arc::new(Mutex::new(screen)),
+        }
+    }
+
+    /// Shared handle to the compositor
+    pub fn compositor(&self) -> Arc<Mutex<Compositor>> {
+        Arc::clone(&self.compositor)
+    }
+
+    /// Shared handle to the screen being driven
+    pub fn screen(&self) -> Arc<Mutex<Screen<T>>> {
+        Arc::clone(&self.screen)
+    }
+
+    /// Accepts clients forever; only returns on a listener error
+    pub fn serve(&self, listener: UnixListener) -> io::Result<()> {
+        let compositor = Arc::clone(&self.compositor);
+        let screen = Arc::clone(&self.screen);
+        thread::spawn(move || refresh_loop(compositor, screen));
+
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let compositor = Arc::clone(&self.compositor);
+            thread::spawn(move || {
+                let _ = handle_client(stream, compositor);
+            });
+        }
+        Ok(())
+    }
+}
+
+fn handle_client(stream: UnixStream, compositor: Arc<Mutex<Compositor>>) -> io::Result<()> {
+    let client = compositor.lock().unwrap_or_else(|e| e.into_inner()).connect();
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut result = Ok(());
+    let mut bytes = Vec::new();
+    loop {
+        bytes.clear();
+        // Reads at most one byte past the limit, so a client can't make
+        // the daemon buffer an endless line
+        match (&mut reader).take(MAX_LINE as u64 + 1).read_until(b'\n', &mut bytes) {
+            Ok(0) => break,
+            Ok(_) if bytes.len() > MAX_LINE && !bytes.ends_with(b"\n") => {
+                let error = Response::failure(format!("request longer than {} bytes", MAX_LINE));
+                let _ = writeln!(writer, "{}", serde_json::to_string(&error).unwrap_or_default());
+                break;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+        let line = match std::str::from_utf8(&bytes) {
+            Ok(line) if line.trim().is_empty() => continue,
+            Ok(line) => line.trim_end_matches(['\n', '\r']),
+            Err(e) => {
+                result = Err(io::Error::new(io::ErrorKind::InvalidData, e));
+                break;
+            }
+        };
+        let reply = compositor.lock().unwrap_or_else(|e| e.into_inner()).handle_line(client, line);
+        if let Err(e) = writeln!(writer, "{}", reply) {
+            result = Err(e);
+            break;
+        }
+    }
+    compositor.lock().unwrap_or_else(|e| e.into_inner()).disconnect(client);
+    result
+}
+
+fn refresh_loop<T: Transport>(compositor: Arc<Mutex<Compositor>>, screen: Arc<Mutex<Screen<T>>>) {
+    let mut backlight = None;
+    let mut pending_glyphs = Vec::new();
+    loop {
+        let (frame, wanted_backlight) = {
+            let mut compositor = compositor.lock().unwrap_or_else(|e| e.into_inner());
+            pending_glyphs.extend(compositor.take_glyph_updates());
+            (compositor.compose(Instant::now()), compositor.backlight())
+        };
+
+        let mut screen = screen.lock().unwrap_or_else(|e| e.into_inner());
+        // Failed glyph loads stay pending and are retried on the next pass
+        pending_glyphs.retain(|(index, rows)| screen.create_character(*index, *rows).is_err());
+        if wanted_backlight.is_some() && wanted_backlight != backlight {
+            let (r, g, b) = wanted_backlight.unwrap_or((255, 255, 255));
+            if screen.change_backlight(r, g, b).is_ok() {
+                backlight = wanted_backlight;
+            }
+        }
+        let _ = screen.present(&frame);
+        drop(screen);
+        thread::sleep(REFRESH_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+
+    fn claim(row: usize, col: usize, rows: u8, columns: u8, layer: i32) -> Request {
+        Request::Claim { name: None, row, col, rows, columns, layer }
+    }
+
+    fn text(row: usize, col: usize, text: &str) -> Request {
+        Request::Text { row, col, text: text.to_string(), clear: false }
+    }
+
+    #[test]
+    fn test_regions_and_layers() {
+        let mut compositor = Compositor::new(2, 16);
+        let a = compositor.connect();
+        let b = compositor.connect();
+        assert!(compositor.handle(a, claim(0, 0, 2, 16, 0)).ok);
+        assert!(compositor.handle(a, text(0, 0, "background row")).ok);
+        assert!(compositor.handle(a, text(1, 0, "second")).ok);
+        assert!(compositor.handle(b, claim(1, 8, 1, 8, 1)).ok);
+        assert!(compositor.handle(b, text(0, 0, "overlay")).ok);
+
+        let frame = compositor.compose(Instant::now());
+        assert_eq!(frame.row_text(0), "background row  ");
+        assert_eq!(frame.row_text(1), "second  overlay ");
+
+        // Out of bounds claims and writes are rejected
+        assert!(!compositor.handle(b, claim(1, 10, 1, 8, 1)).ok);
+        assert!(!compositor.handle(b, text(1, 0, "x")).ok);
+        // A rejected write leaves the region as it was
+        assert!(!compositor.handle(a, Request::Text { row: 5, col: 0, text: "x".into(), clear: true }).ok);
+        assert_eq!(compositor.compose(Instant::now()).row_text(0), "background row  ");
+        let c = compositor.connect();
+        assert_eq!(compositor.handle(c, text(0, 0, "x")).error.as_deref(), Some("claim a region first"));
+
+        compositor.disconnect(b);
+        assert_eq!(compositor.compose(Instant::now()).row_text(1), "second          ");
+        assert!(compositor.handle(a, Request::Clear).ok);
+        assert_eq!(compositor.compose(Instant::now()).row_text(0), " ".repeat(16));
+    }
+
+    #[test]
+    fn test_glyph_slots_are_virtualized() {
+        let mut compositor = Compositor::new(1, 8);
+        let a = compositor.connect();
+        let b = compositor.connect();
+        let heart = [0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00];
+        let bell = [0x04, 0x0E, 0x0E, 0x0E, 0x1F, 0x00, 0x04, 0x00];
+        compositor.handle(a, Request::Glyph { index: 0, rows: heart });
+        compositor.handle(b, Request::Glyph { index: 0, rows: bell });
+        compositor.handle(b, Request::Glyph { index: 5, rows: heart });
+        assert_eq!(compositor.take_glyph_updates(), vec![(0, heart), (1, bell)]);
+        assert!(compositor.take_glyph_updates().is_empty());
+
+        compositor.handle(a, claim(0, 0, 1, 4, 0));
+        compositor.handle(b, claim(0, 4, 1, 4, 0));
+        compositor.handle(a, text(0, 0, "a\u{0}"));
+        compositor.handle(b, text(0, 0, "\u{0}\u{5}\u{3}"));
+        let frame = compositor.compose(Instant::now());
+        assert_eq!(frame.row(0), [b'a', 0, b' ', b' ', 1, 0, b'?', b' ']);
+
+        for index in 1..7 {
+            let rows = [index; 8];
+            assert!(compositor.handle(a, Request::Glyph { index, rows }).ok);
+        }
+        let error = compositor.handle(a, Request::Glyph { index: 7, rows: [0x1F; 8] });
+        assert_eq!(error.error.as_deref(), Some("all 8 glyph slots are in use"));
+        // Disconnecting frees the client's slots
+        compositor.disconnect(a);
+        assert!(compositor.handle(b, Request::Glyph { index: 7, rows: [0x1F; 8] }).ok);
+    }
+
+    #[test]
+    fn test_notifications_take_turns() {
+        let mut compositor = Compositor::new(2, 8);
+        let client = compositor.connect();
+        compositor.handle(client, claim(0, 0, 2, 8, 0));
+        compositor.handle(client, text(0, 0, "status"));
+        compositor.handle(client, Request::Notify { text: "hi".into(), duration_ms: 100 });
+        compositor.handle(client, Request::Notify { text: "ten chars!".into(), duration_ms: 100 });
+
+        let start = Instant::now();
+        let frame = compositor.compose(start);
+        assert_eq!(frame.row_text(0), "   hi   ");
+        let frame = compositor.compose(start + Duration::from_millis(150));
+        assert_eq!(frame.row_text(0), "ten char");
+        assert_eq!(frame.row_text(1), "   s!   ");
+        let frame = compositor.compose(start + Duration::from_millis(300));
+        assert_eq!(frame.row_text(0), "status  ");
+
+        // A panel without columns has nowhere to draw, but must not panic
+        let mut empty = Compositor::new(1, 0);
+        let client = empty.connect();
+        empty.handle(client, Request::Notify { text: "hi".into(), duration_ms: 100 });
+        empty.compose(Instant::now());
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let mut compositor = Compositor::new(1, 8);
+        let client = compositor.connect();
+        let reply = compositor.handle_line(client, r#"{"op":"claim","name":"app","row":0,"col":0,"rows":1,"columns":8}"#);
+        assert_eq!(reply, r#"{"ok":true}"#);
+        assert_eq!(compositor.client_name(client), Some("app"));
+        compositor.handle_line(client, r#"{"op":"text","text":"json"}"#);
+        compositor.handle_line(client, r#"{"op":"backlight","r":1,"g":2,"b":3}"#);
+        assert_eq!(compositor.backlight(), Some((1, 2, 3)));
+        compositor.compose(Instant::now());
+        assert_eq!(compositor.handle_line(client, r#"{"op":"read"}"#), r#"{"ok":true,"rows":["json    "]}"#);
+        assert!(compositor.handle_line(client, r#"{"op":"dance"}"#).starts_with(r#"{"ok":false,"error":"invalid request"#));
+
+        let forever = r#"{"op":"notify","text":"x","duration_ms":18446744073709551615}"#;
+        assert_eq!(compositor.handle_line(client, forever), r#"{"ok":true}"#);
+        compositor.compose(Instant::now());
+
+        let huge = r#"{"op":"claim","row":18446744073709551615,"col":0,"rows":1,"columns":8}"#;
+        assert!(compositor.handle_line(client, huge).starts_with(r#"{"ok":false,"error":"region 8x1"#));
+    }
+
+    #[test]
+    fn test_unix_socket_daemon() {
+        let path = std::env::temp_dir().join(format!("qwiic-lcdd-test-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let daemon = LcdDaemon::new(Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16)));
+        let screen = daemon.screen();
+        thread::spawn(move || daemon.serve(listener));
+
+        let mut clients: Vec<UnixStream> = (0..2).map(|_| UnixStream::connect(&path).unwrap()).collect();
+        let requests = [
+            [r#"{"op":"claim","row":0,"col":0,"rows":1,"columns":16}"#, r#"{"op":"text","text":"health: ok"}"#],
+            [r#"{"op":"claim","row":1,"col":0,"rows":1,"columns":16}"#, r#"{"op":"text","text":"eth0 10.0.0.2"}"#],
+        ];
+        for (stream, lines) in clients.iter_mut().zip(requests) {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            for line in lines {
+                writeln!(stream, "{}", line).unwrap();
+                let mut reply = String::new();
+                reader.read_line(&mut reply).unwrap();
+                assert_eq!(reply.trim_end(), r#"{"ok":true}"#);
+            }
+        }
+
+        // Wait for the refresh thread to present both regions
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let shown = |screen: &Screen<Emulator>| {
+            screen.transport().row_text(0) == "health: ok      " && screen.transport().row_text(1) == "eth0 10.0.0.2   "
+        };
+        while !shown(&screen.lock().unwrap()) {
+            assert!(Instant::now() < deadline, "panel never showed both regions");
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        // An endless line is cut off instead of being buffered
+        let mut stream = UnixStream::connect(&path).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        stream.write_all(&vec![b'x'; MAX_LINE + 1]).unwrap();
+        let mut reply = String::new();
+        reader.read_line(&mut reply).unwrap();
+        assert!(reply.starts_with(r#"{"ok":false,"error":"request longer than"#));
+        reply.clear();
+        assert_eq!(reader.read_line(&mut reply).unwrap(), 0);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,520 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use i2cdev::linux::LinuxI2CError;
+
+use crate::{Command, ROW_OFFSETS};
+
+/// Length of one HD44780 DDRAM line
+const LINE_LENGTH: usize = 40;
+
+/// What the decoder expects next in the byte stream
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Decode {
+    /// Characters, or a 0x7C / 0xFE command prefix
+    Data,
+    /// Byte following the setting command prefix (0x7C)
+    Setting,
+    /// Byte following the special command prefix (0xFE)
+    Special,
+    /// Arguments of a setting command still to be read
+    Arguments { command: u8, needed: usize },
+}
+
+/// Software model of a Qwiic SerLCD (OpenLCD firmware + HD44780)
+///
+/// The emulator decodes the same byte stream the firmware receives over
+/// I2C and keeps the resulting panel state: characters, cursor, display
+/// flags, backlight, contrast and custom glyphs. It implements `Transport`,
+/// so a `Screen<Emulator>` runs the real command encoding without hardware,
+/// and it is the shared state behind the terminal simulator and the image
+/// exporters.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+/// screen.move_cursor(1, 3).unwrap();
+/// screen.print("Hello").unwrap();
+/// assert_eq!(screen.transport().row_text(1), "   Hello        ");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Emulator {
+    rows: u8,
+    columns: u8,
+    lines: Vec<Vec<u8>>,
+    cursor: (usize, usize),
+    cgram: [[u8; 8]; 8],
+    cgram_address: Option<usize>,
+    display_on: bool,
+    cursor_on: bool,
+    blink_on: bool,
+    increment: bool,
+    shift_on_write: bool,
+    display_shift: i32,
+    backlight: (u8, u8, u8),
+    contrast: u8,
+    address: u8,
+    splash: bool,
+    decode: Decode,
+    arguments: Vec<u8>,
+    generation: u64,
+}
+
+impl Emulator {
+    /// Creates a powered-up panel with the given dimensions
+    pub fn new(rows: u8, columns: u8) -> Emulator {
+        Emulator {
+            rows,
+            columns,
+            lines: vec![vec![b' '; LINE_LENGTH.max(columns as usize)]; rows as usize],
+            cursor: (0, 0),
+            cgram: [[0; 8]; 8],
+            cgram_address: None,
+            display_on: true,
+            cursor_on: false,
+            blink_on: false,
+            increment: true,
+            shift_on_write: false,
+            display_shift: 0,
+            backlight: (255, 255, 255),
+            contrast: 40,
+            address: 0x72,
+            splash: true,
+            decode: Decode::Data,
+            arguments: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    /// Number of rows
+    pub fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    /// Number of columns
+    pub fn columns(&self) -> u8 {
+        self.columns
+    }
+
+    /// Byte shown at a visible cell, taking the display shift into account
+    pub fn cell(&self, row: usize, col: usize) -> Option<u8> {
+        let line = self.lines.get(row)?;
+        if col >= self.columns as usize {
+            return None;
+        }
+        let len = line.len() as i64;
+        let index = (col as i64 - self.display_shift as i64).rem_euclid(len);
+        Some(line[index as usize])
+    }
+
+    /// Bytes of a visible row
+    pub fn row(&self, row: usize) -> Vec<u8> {
+        (0..self.columns as usize).filter_map(|col| self.cell(row, col)).collect()
+    }
+
+    /// A visible row as text; custom glyphs and non-ASCII bytes show as '?'
+    pub fn row_text(&self, row: usize) -> String {
+        self.row(row)
+            .iter()
+            .map(|b| if (0x20..=0x7E).contains(b) { *b as char } else { '?' })
+            .collect()
+    }
+
+    /// Cursor position as (row, column)
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// Whether the display is on
+    pub fn display_on(&self) -> bool {
+        self.display_on
+    }
+
+    /// Whether the underline cursor is shown
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_on
+    }
+
+    /// Whether the blinking block cursor is shown
+    pub fn blink(&self) -> bool {
+        self.blink_on
+    }
+
+    /// Backlight color as (red, green, blue)
+    pub fn backlight(&self) -> (u8, u8, u8) {
+        self.backlight
+    }
+
+    /// Contrast setting (0-255)
+    pub fn contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// Bitmap of a custom glyph (5 low bits of each of the 8 rows)
+    pub fn glyph(&self, index: usize) -> [u8; 8] {
+        self.cgram[index % 8]
+    }
+
+    /// Number of positions the display is shifted right (negative: left)
+    pub fn display_shift(&self) -> i32 {
+        self.display_shift
+    }
+
+    /// I2C address last configured on the panel
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Whether the power-up splash screen is enabled
+    pub fn splash(&self) -> bool {
+        self.splash
+    }
+
+    /// Counter bumped every time the panel receives bytes
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether `other` is in the same state, however often either was written to
+    #[cfg(feature = "gif")]
+    pub(crate) fn same_state(&self, other: &Emulator) -> bool {
+        Emulator { generation: other.generation, ..self.clone() } == *other
+    }
+
+    /// Feeds raw bytes as the firmware would receive them over I2C
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.generation += 1;
+        for byte in bytes {
+            self.decode_byte(*byte);
+        }
+    }
+
+    fn decode_byte(&mut self, byte: u8) {
+        match self.decode {
+            Decode::Data => match byte {
+                b if b == Command::SettingCommand as u8 => self.decode = Decode::Setting,
+                b if b == Command::SpecialCommand as u8 => self.decode = Decode::Special,
+                b => self.write_data(b),
+            },
+            Decode::Setting => {
+                self.decode = Decode::Data;
+                self.setting_command(byte);
+            }
+            Decode::Special => {
+                self.decode = Decode::Data;
+                self.special_command(byte);
+            }
+            Decode::Arguments { command, needed } => {
+                self.arguments.push(byte);
+                if self.arguments.len() < needed {
+                    return;
+                }
+                self.decode = Decode::Data;
+                let arguments = std::mem::take(&mut self.arguments);
+                self.setting_with_arguments(command, &arguments);
+            }
+        }
+    }
+
+    fn expect_arguments(&mut self, command: u8, needed: usize) {
+        self.arguments.clear();
+        self.decode = Decode::Arguments { command, needed };
+    }
+
+    fn setting_command(&mut self, command: u8) {
+        match command {
+            // '-': clear display
+            0x2D => self.clear(),
+            // '+': RGB backlight
+            0x2B => self.expect_arguments(command, 3),
+            // Ctrl+X: contrast
+            0x18 => self.expect_arguments(command, 1),
+            // Ctrl+Y: change I2C address
+            0x19 => self.expect_arguments(command, 1),
+            // '0' / '1': enable / disable the splash screen
+            0x30 => self.splash = true,
+            0x31 => self.splash = false,
+            // Ctrl+[ .. ": record custom character 0-7
+            0x1B..=0x22 => self.expect_arguments(command, 8),
+            // '#' .. '*': display custom character 0-7
+            0x23..=0x2A => self.write_char(command - 0x23),
+            // Primary backlight brightness, 30 steps per color
+            0x80..=0x9D => self.backlight.0 = scale_brightness(command - 0x80),
+            0x9E..=0xBB => self.backlight.1 = scale_brightness(command - 0x9E),
+            0xBC..=0xD9 => self.backlight.2 = scale_brightness(command - 0xBC),
+            _ => {}
+        }
+    }
+
+    fn setting_with_arguments(&mut self, command: u8, arguments: &[u8]) {
+        match command {
+            0x2B => self.backlight = (arguments[0], arguments[1], arguments[2]),
+            0x18 => self.contrast = arguments[0],
+            0x19 => self.address = arguments[0],
+            0x1B..=0x22 => {
+                let index = (command - 0x1B) as usize;
+                for (row, bits) in arguments.iter().enumerate() {
+                    self.cgram[index][row] = bits & 0x1F;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn special_command(&mut self, command: u8) {
+        match command {
+            0x01 => self.clear(),
+            0x02..=0x03 => self.home(),
+            0x04..=0x07 => {
+                self.increment = command & 0x02 != 0;
+                self.shift_on_write = command & 0x01 != 0;
+            }
+            0x08..=0x0F => {
+                self.display_on = command & 0x04 != 0;
+                self.cursor_on = command & 0x02 != 0;
+                self.blink_on = command & 0x01 != 0;
+            }
+            0x10..=0x1F => {
+                let right = command & 0x04 != 0;
+                if command & 0x08 != 0 {
+                    self.display_shift += if right { 1 } else { -1 };
+                } else if right {
+                    self.advance();
+                } else {
+                    self.retreat();
+                }
+            }
+            // Function set: bus width and font are fixed on this panel
+            0x20..=0x3F => {}
+            0x40..=0x7F => self.cgram_address = Some((command & 0x3F) as usize),
+            _ => self.set_ddram_address((command & 0x7F) as usize),
+        }
+    }
+
+    fn set_ddram_address(&mut self, address: usize) {
+        self.cgram_address = None;
+        let rows = (self.rows as usize).min(ROW_OFFSETS.len());
+        let row = (0..rows)
+            .filter(|row| address >= ROW_OFFSETS[*row])
+            .max_by_key(|row| ROW_OFFSETS[*row])
+            .unwrap_or(0);
+        let col = (address - ROW_OFFSETS[row]).min((self.columns as usize).saturating_sub(1));
+        self.cursor = (row, col);
+    }
+
+    fn write_data(&mut self, byte: u8) {
+        match self.cgram_address {
+            Some(address) => {
+                self.cgram[address / 8][address % 8] = byte & 0x1F;
+                self.cgram_address = Some((address + 1) % 64);
+            }
+            None => self.write_char(byte),
+        }
+    }
+
+    fn write_char(&mut self, byte: u8) {
+        let (row, col) = self.cursor;
+        if let Some(line) = self.lines.get_mut(row) {
+            line[col] = byte;
+        }
+        if self.increment {
+            self.advance();
+        } else {
+            self.retreat();
+        }
+        if self.shift_on_write {
+            self.display_shift += if self.increment { -1 } else { 1 };
+        }
+    }
+
+    fn advance(&mut self) {
+        let (mut row, mut col) = self.cursor;
+        col += 1;
+        if col >= self.columns as usize {
+            col = 0;
+            row = (row + 1) % (self.rows as usize).max(1);
+        }
+        self.cursor = (row, col);
+    }
+
+    fn retreat(&mut self) {
+        let (mut row, mut col) = self.cursor;
+        if col == 0 {
+            col = (self.columns as usize).saturating_sub(1);
+            row = if row == 0 { (self.rows as usize).saturating_sub(1) } else { row - 1 };
+        } else {
+            col -= 1;
+        }
+        self.cursor = (row, col);
+    }
+
+    fn clear(&mut self) {
+        for line in self.lines.iter_mut() {
+            line.iter_mut().for_each(|c| *c = b' ');
+        }
+        self.home();
+    }
+
+    fn home(&mut self) {
+        self.cgram_address = None;
+        self.cursor = (0, 0);
+        self.display_shift = 0;
+    }
+}
+
+/// Maps an OpenLCD brightness step (0-29) to 0-255
+fn scale_brightness(step: u8) -> u8 {
+    (step as u32 * 255 / 29) as u8
+}
+
+impl crate::Transport for Emulator {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.feed(&[value]);
+        Ok(())
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.feed(&[register, value]);
+        Ok(())
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        let mut bytes = Vec::with_capacity(data.len() + 1);
+        bytes.push(register);
+        bytes.extend_from_slice(data);
+        self.feed(&bytes);
+        Ok(())
+    }
+
+    fn delay(&mut self, _duration: Duration) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Backlight, MoveDirection, Screen, ScreenConfig};
+
+    fn screen() -> Screen<Emulator> {
+        Screen::with_transport(ScreenConfig::default(), Emulator::new(4, 20))
+    }
+
+    #[test]
+    fn test_print_and_move_cursor() {
+        let mut screen = screen();
+        screen.init().unwrap();
+        screen.print("Hello").unwrap();
+        screen.move_cursor(2, 18).unwrap();
+        screen.print("abc").unwrap();
+
+        let lcd = screen.transport();
+        assert_eq!(lcd.row_text(0), format!("{:<20}", "Hello"));
+        assert_eq!(lcd.row_text(2), format!("{:>20}", "ab"));
+        assert_eq!(lcd.row_text(3), format!("{:<20}", "c"));
+        assert_eq!(lcd.cursor(), (3, 1));
+        assert_eq!(lcd.cursor(), screen.framebuffer().cursor());
+        for row in 0..4 {
+            assert_eq!(lcd.row(row), screen.framebuffer().row(row));
+        }
+    }
+
+    #[test]
+    fn test_display_flags_and_clear() {
+        let mut screen = screen();
+        screen.init().unwrap();
+        assert!(screen.transport().display_on());
+        assert!(!screen.transport().cursor_visible());
+        assert!(!screen.transport().blink());
+
+        screen.enable_cursor(true).unwrap();
+        screen.enable_blink(true).unwrap();
+        screen.enable_display(false).unwrap();
+        let lcd = screen.transport();
+        assert!(lcd.cursor_visible() && lcd.blink() && !lcd.display_on());
+
+        screen.print("xyz").unwrap();
+        screen.clear().unwrap();
+        assert_eq!(screen.transport().row_text(0), " ".repeat(20));
+        assert_eq!(screen.transport().cursor(), (0, 0));
+    }
+
+    #[test]
+    fn test_backlight_contrast_and_glyphs() {
+        let mut screen = screen();
+        screen.change_backlight(10, 20, 30).unwrap();
+        assert_eq!(screen.transport().backlight(), (10, 20, 30));
+        screen.set_backlight_state(Backlight::Off).unwrap();
+        assert_eq!(screen.transport().backlight(), (0, 0, 0));
+
+        screen.set_contrast(0x7C).unwrap();
+        screen.print("A").unwrap();
+        assert_eq!(screen.transport().contrast(), 0x7C);
+        assert_eq!(screen.transport().row_text(0).trim_end(), "A");
+
+        let heart = [0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00];
+        screen.create_character(2, heart).unwrap();
+        screen.print("B").unwrap();
+        assert_eq!(screen.transport().glyph(2), heart);
+        // create_character homes the cursor
+        assert_eq!(screen.transport().row_text(0).trim_end(), "B");
+    }
+
+    #[test]
+    fn test_shifts() {
+        let mut screen = screen();
+        screen.print("abc").unwrap();
+        screen.shift_cursor(MoveDirection::Left).unwrap();
+        screen.print("X").unwrap();
+        assert_eq!(screen.transport().row_text(0).trim_end(), "abX");
+
+        screen.shift_display(MoveDirection::Right).unwrap();
+        assert_eq!(screen.transport().display_shift(), 1);
+        assert_eq!(screen.transport().row_text(0).trim_end(), " abX");
+        screen.home().unwrap();
+        assert_eq!(screen.transport().display_shift(), 0);
+    }
+
+    #[test]
+    fn test_address_and_splash() {
+        let mut screen = screen();
+        screen.set_address(0x40).unwrap();
+        assert_eq!(screen.transport().address(), 0x40);
+        assert!(matches!(screen.set_address(0x78), Err(crate::QwiicLcdError::InvalidAddress(0x78))));
+        assert_eq!(screen.transport().address(), 0x40);
+
+        screen.enable_splash(false).unwrap();
+        assert!(!screen.transport().splash());
+        screen.save_splash().unwrap();
+        screen.enable_splash(true).unwrap();
+        assert!(screen.transport().splash());
+    }
+
+    #[test]
+    fn test_raw_setting_commands() {
+        let mut lcd = Emulator::new(2, 16);
+        lcd.feed(&[0x7C, 0x1B, 1, 2, 3, 4, 5, 6, 7, 0xFF]);
+        assert_eq!(lcd.glyph(0), [1, 2, 3, 4, 5, 6, 7, 0x1F]);
+        lcd.feed(&[0x7C, 0x23]);
+        assert_eq!(lcd.cell(0, 0), Some(0));
+        lcd.feed(&[0x7C, 0x80, 0x7C, 0x9E + 29]);
+        assert_eq!(lcd.backlight(), (0, 255, 255));
+        lcd.feed(&[0x7C, 0x19, 0x30]);
+        assert_eq!(lcd.address(), 0x30);
+        lcd.feed(&[0x7C, 0x31]);
+        assert!(!lcd.splash());
+        assert_eq!(lcd.generation(), 5);
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// In-memory copy of the characters shown on the LCD
+///
+/// `Screen` keeps one of these in sync with every `print`, `clear` and
+/// cursor movement so the panel contents can be restored after the device
+/// is lost. Cells hold the raw byte sent to the LCD (ASCII, extended ROM
+/// characters or custom glyph indices 0-7).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawFrameBuffer")
+)]
+pub struct FrameBuffer {
+    rows: u8,
+    columns: u8,
+    cells: Vec<u8>,
+    cursor: (usize, usize),
+}
+
+/// Unchecked form of a deserialized `FrameBuffer`
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawFrameBuffer {
+    rows: u8,
+    columns: u8,
+    cells: Vec<u8>,
+    cursor: (usize, usize),
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawFrameBuffer> for FrameBuffer {
+    type Error = String;
+
+    fn try_from(raw: RawFrameBuffer) -> Result<FrameBuffer, String> {
+        if raw.cells.len() != raw.rows as usize * raw.columns as usize {
+            return Err(format!("{} cells don't fill {}x{}", raw.cells.len(), raw.rows, raw.columns));
+        }
+        let mut frame = FrameBuffer { rows: raw.rows, columns: raw.columns, cells: raw.cells, cursor: (0, 0) };
+        frame.set_cursor(raw.cursor.0, raw.cursor.1);
+        Ok(frame)
+    }
+}
+
+impl FrameBuffer {
+    /// Creates a blank framebuffer with the given dimensions
+    pub fn new(rows: u8, columns: u8) -> FrameBuffer {
+        FrameBuffer {
+            rows,
+            columns,
+            cells: vec![b' '; rows as usize * columns as usize],
+            cursor: (0, 0),
+        }
+    }
+
+    /// Number of rows
+    pub fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    /// Number of columns
+    pub fn columns(&self) -> u8 {
+        self.columns
+    }
+
+    /// Current cursor position as (row, column)
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    /// Moves the cursor, clamping it to the framebuffer dimensions
+    pub fn set_cursor(&mut self, row: usize, col: usize) {
+        self.cursor = (
+            row.min((self.rows as usize).saturating_sub(1)),
+            col.min((self.columns as usize).saturating_sub(1)),
+        );
+    }
+
+    /// Returns the byte at the given cell, or `None` if it is out of range
+    pub fn get(&self, row: usize, col: usize) -> Option<u8> {
+        self.index(row, col).map(|i| self.cells[i])
+    }
+
+    /// Sets the byte at the given cell without moving the cursor
+    ///
+    /// Out of range cells are ignored.
+    pub fn set(&mut self, row: usize, col: usize, byte: u8) {
+        if let Some(i) = self.index(row, col) {
+            self.cells[i] = byte;
+        }
+    }
+
+    /// Returns the bytes of a whole row
+    pub fn row(&self, row: usize) -> &[u8] {
+        let start = row.min(self.rows as usize) * self.columns as usize;
+        let end = (start + self.columns as usize).min(self.cells.len());
+        &self.cells[start..end]
+    }
+
+    /// Returns a row as text, with non-printable bytes shown as '?'
+    pub fn row_text(&self, row: usize) -> String {
+        self.row(row)
+            .iter()
+            .map(|b| if (0x20..=0x7E).contains(b) { *b as char } else { '?' })
+            .collect()
+    }
+
+    /// Writes a byte at the cursor and advances it
+    ///
+    /// Like the OpenLCD firmware, the cursor wraps to the start of the next
+    /// row at the end of a line and back to the top after the last row.
+    pub fn write(&mut self, byte: u8) {
+        let (row, col) = self.cursor;
+        self.set(row, col, byte);
+        self.advance();
+    }
+
+    /// Writes a string at the cursor like `write`
+    ///
+    /// Characters outside Latin-1 are written as '?'.
+    pub fn write_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.write(u8::try_from(c).unwrap_or(b'?'));
+        }
+    }
+
+    /// Moves the cursor one cell forward, wrapping like `write`
+    pub fn advance(&mut self) {
+        if self.rows == 0 || self.columns == 0 {
+            return;
+        }
+        let (mut row, mut col) = self.cursor;
+        col += 1;
+        if col >= self.columns as usize {
+            col = 0;
+            row = (row + 1) % self.rows as usize;
+        }
+        self.cursor = (row, col);
+    }
+
+    /// Moves the cursor one cell back, wrapping to the end of the previous row
+    pub fn retreat(&mut self) {
+        if self.rows == 0 || self.columns == 0 {
+            return;
+        }
+        let (mut row, mut col) = self.cursor;
+        if col == 0 {
+            col = self.columns as usize - 1;
+            row = if row == 0 { self.rows as usize - 1 } else { row - 1 };
+        } else {
+            col -= 1;
+        }
+        self.cursor = (row, col);
+    }
+
+    /// Blanks every cell and homes the cursor
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|c| *c = b' ');
+        self.home();
+    }
+
+    /// Moves the cursor to (0, 0)
+    pub fn home(&mut self) {
+        self.cursor = (0, 0);
+    }
+
+    fn index(&self, row: usize, col: usize) -> Option<usize> {
+        if row < self.rows as usize && col < self.columns as usize {
+            Some(row * self.columns as usize + col)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_framebuffer_is_blank() {
+        let fb = FrameBuffer::new(2, 16);
+        assert_eq!(fb.rows(), 2);
+        assert_eq!(fb.columns(), 16);
+        assert_eq!(fb.cursor(), (0, 0));
+        assert_eq!(fb.row(0), &[b' '; 16]);
+        assert_eq!(fb.row_text(1), " ".repeat(16));
+    }
+
+    #[test]
+    fn test_write_wraps_rows() {
+        let mut fb = FrameBuffer::new(2, 4);
+        for b in b"abcdefghij" {
+            fb.write(*b);
+        }
+        // "ij" wrapped past the last row back to the top
+        assert_eq!(fb.row_text(0), "ijcd");
+        assert_eq!(fb.row_text(1), "efgh");
+        assert_eq!(fb.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn test_write_str_replaces_unsupported_characters() {
+        let mut fb = FrameBuffer::new(1, 8);
+        fb.write_str("21°C ☀");
+        assert_eq!(fb.row(0), b"21\xB0C ?  ");
+        assert_eq!(fb.cursor(), (0, 6));
+    }
+
+    #[test]
+    fn test_retreat_wraps_backwards() {
+        let mut fb = FrameBuffer::new(2, 4);
+        fb.retreat();
+        assert_eq!(fb.cursor(), (1, 3));
+        fb.retreat();
+        assert_eq!(fb.cursor(), (1, 2));
+        fb.set_cursor(1, 0);
+        fb.retreat();
+        assert_eq!(fb.cursor(), (0, 3));
+    }
+
+    #[test]
+    fn test_set_cursor_and_clear() {
+        let mut fb = FrameBuffer::new(4, 20);
+        fb.set_cursor(2, 5);
+        fb.write(b'X');
+        assert_eq!(fb.get(2, 5), Some(b'X'));
+        assert_eq!(fb.cursor(), (2, 6));
+
+        fb.set_cursor(10, 30);
+        assert_eq!(fb.cursor(), (3, 19));
+
+        fb.clear();
+        assert_eq!(fb.get(2, 5), Some(b' '));
+        assert_eq!(fb.cursor(), (0, 0));
+        assert_eq!(fb.get(4, 0), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip_checks_dimensions() {
+        let mut fb = FrameBuffer::new(1, 4);
+        fb.write_str("ok");
+        let json = serde_json::to_string(&fb).unwrap();
+        assert_eq!(serde_json::from_str::<FrameBuffer>(&json).unwrap(), fb);
+
+        let short = r#"{"rows":2,"columns":4,"cells":[32,32],"cursor":[0,0]}"#;
+        assert!(serde_json::from_str::<FrameBuffer>(short).is_err());
+    }
+
+    #[test]
+    fn test_row_text_masks_non_printable() {
+        let mut fb = FrameBuffer::new(1, 3);
+        fb.write(0x00);
+        fb.write(b'A');
+        fb.write(0xDF);
+        assert_eq!(fb.row_text(0), "?A?");
+        assert_eq!(fb.row(0), &[0x00, b'A', 0xDF]);
+    }
+}
@@ -0,0 +1,418 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Instant;
+
+use crate::{LinuxTransport, QwiicLcdError, Screen, ScreenConfig, Transport};
+
+/// Consecutive failures after which a panel is reported as `Failed`
+pub const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+
+/// Overall condition of one panel in a `ScreenGroup`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PanelStatus {
+    /// The last operation succeeded
+    Healthy,
+    /// Recent operations failed, but fewer than the failure threshold
+    Degraded,
+    /// The panel failed the failure threshold's worth of operations in a row
+    Failed,
+}
+
+/// Health counters of one panel
+///
+/// `last_success` is an `Instant`, which has no meaning outside this
+/// process, so it is left out when serialized.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PanelHealth {
+    /// Current condition
+    pub status: PanelStatus,
+    /// Failures since the last success
+    pub consecutive_failures: u32,
+    /// Operations attempted on the panel
+    pub operations: u64,
+    /// Operations that failed
+    pub failures: u64,
+    /// Message of the most recent error
+    pub last_error: Option<String>,
+    /// When an operation last succeeded
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub last_success: Option<Instant>,
+}
+
+impl Default for PanelHealth {
+    fn default() -> Self {
+        PanelHealth {
+            status: PanelStatus::Healthy,
+            consecutive_failures: 0,
+            operations: 0,
+            failures: 0,
+            last_error: None,
+            last_success: None,
+        }
+    }
+}
+
+impl PanelHealth {
+    fn record<R>(&mut self, result: &Result<R, QwiicLcdError>, threshold: u32) {
+        self.operations += 1;
+        match result {
+            Ok(_) => {
+                self.consecutive_failures = 0;
+                self.last_success = Some(Instant::now());
+            }
+            Err(e) => {
+                self.failures += 1;
+                self.consecutive_failures += 1;
+                self.last_error = Some(e.to_string());
+            }
+        }
+        self.status = match self.consecutive_failures {
+            0 => PanelStatus::Healthy,
+            n if n < threshold => PanelStatus::Degraded,
+            _ => PanelStatus::Failed,
+        };
+    }
+}
+
+struct Panel<T: Transport> {
+    bus: String,
+    bus_lock: Arc<Mutex<()>>,
+    screen: Mutex<Screen<T>>,
+    health: Mutex<PanelHealth>,
+}
+
+fn lock<U>(mutex: &Mutex<U>) -> MutexGuard<'_, U> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Several screens managed together, keyed by name
+///
+/// Operations can target one panel (`with`) or every panel (`broadcast`,
+/// `clear`, `change_backlight`). Panels on the same bus are never driven at
+/// the same time, while broadcasts run the buses in parallel. Each panel
+/// keeps its own `PanelHealth`; a failing panel only shows up in its own
+/// result and health, the rest of the group keeps working.
+///
+/// The group can be shared between threads (`&self` methods).
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let mut group = ScreenGroup::new();
+/// group.add("lobby", "/dev/i2c-1", Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16)));
+/// group.add("desk", "/dev/i2c-1", Screen::with_transport(ScreenConfig::new(4, 20), Emulator::new(4, 20)));
+///
+/// for (name, result) in group.change_backlight(0, 0, 255) {
+///     result.unwrap_or_else(|e| eprintln!("{}: {}", name, e));
+/// }
+/// group.with("desk", |screen| screen.print("Desk only")).unwrap().unwrap();
+/// assert_eq!(group.health("lobby").unwrap().status, PanelStatus::Healthy);
+/// ```
+pub struct ScreenGroup<T: Transport = LinuxTransport> {
+    panels: BTreeMap<String, Panel<T>>,
+    buses: BTreeMap<String, Arc<Mutex<()>>>,
+    failure_threshold: u32,
+}
+
+impl<T: Transport> Default for ScreenGroup<T> {
+    fn default() -> Self {
+        ScreenGroup::new()
+    }
+}
+
+impl ScreenGroup<LinuxTransport> {
+    /// Opens a panel at `address` on `bus` and adds it as `name`
+    pub fn open(&mut self, name: &str, config: ScreenConfig, bus: &str, address: u16) -> Result<(), QwiicLcdError> {
+        let screen = Screen::new(config, bus, address)?;
+        self.add(name, bus, screen);
+        Ok(())
+    }
+}
+
+impl<T: Transport> ScreenGroup<T> {
+    /// Creates an empty group
+    pub fn new() -> ScreenGroup<T> {
+        ScreenGroup {
+            panels: BTreeMap::new(),
+            buses: BTreeMap::new(),
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+        }
+    }
+
+    /// Adds a panel on `bus`, returning the screen it replaces, if any
+    ///
+    /// `bus` only identifies which panels share a bus (e.g. "/dev/i2c-1").
+    pub fn add(&mut self, name: &str, bus: &str, screen: Screen<T>) -> Option<Screen<T>> {
+        let bus_lock = Arc::clone(self.buses.entry(bus.to_string()).or_default());
+        let panel = Panel {
+            bus: bus.to_string(),
+            bus_lock,
+            screen: Mutex::new(screen),
+            health: Mutex::new(PanelHealth::default()),
+        };
+        let replaced = self.panels.insert(name.to_string(), panel);
+        replaced.map(|panel| panel.screen.into_inner().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Removes a panel and hands its screen back
+    pub fn remove(&mut self, name: &str) -> Option<Screen<T>> {
+        let panel = self.panels.remove(name)?;
+        if !self.panels.values().any(|other| other.bus == panel.bus) {
+            self.buses.remove(&panel.bus);
+        }
+        Some(panel.screen.into_inner().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Names of the panels, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.panels.keys().map(String::as_str)
+    }
+
+    /// Number of panels
+    pub fn len(&self) -> usize {
+        self.panels.len()
+    }
+
+    /// Returns true if the group has no panels
+    pub fn is_empty(&self) -> bool {
+        self.panels.is_empty()
+    }
+
+    /// Bus the named panel is on
+    pub fn bus(&self, name: &str) -> Option<&str> {
+        self.panels.get(name).map(|panel| panel.bus.as_str())
+    }
+
+    /// Sets how many consecutive failures mark a panel as `Failed`
+    pub fn set_failure_threshold(&mut self, threshold: u32) {
+        self.failure_threshold = threshold.max(1);
+    }
+
+    /// Runs `f` on one panel while holding its bus
+    ///
+    /// Returns `None` if there is no panel with that name. The result is
+    /// recorded in the panel's health.
+    pub fn with<R, F>(&self, name: &str, f: F) -> Option<Result<R, QwiicLcdError>>
+    where
+        F: FnOnce(&mut Screen<T>) -> Result<R, QwiicLcdError>,
+    {
+        let panel = self.panels.get(name)?;
+        Some(self.run(panel, f))
+    }
+
+    /// Runs `f` on every panel and returns each panel's result
+    ///
+    /// Buses are driven in parallel; panels sharing a bus take turns.
+    pub fn broadcast<R, F>(&self, f: F) -> BTreeMap<String, Result<R, QwiicLcdError>>
+    where
+        R: Send,
+        F: Fn(&mut Screen<T>) -> Result<R, QwiicLcdError> + Sync,
+    {
+        let mut by_bus: BTreeMap<&str, Vec<(&String, &Panel<T>)>> = BTreeMap::new();
+        for (name, panel) in &self.panels {
+            by_bus.entry(panel.bus.as_str()).or_default().push((name, panel));
+        }
+
+        let f = &f;
+        thread::scope(|scope| {
+            let workers: Vec<_> = by_bus
+                .into_values()
+                .map(|panels| {
+                    scope.spawn(move || {
+                        panels
+                            .into_iter()
+                            .map(|(name, panel)| (name.clone(), self.run(panel, f)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("panel worker panicked"))
+                .collect()
+        })
+    }
+
+    /// Clears every panel
+    pub fn clear(&self) -> BTreeMap<String, Result<(), QwiicLcdError>> {
+        self.broadcast(|screen| screen.clear())
+    }
+
+    /// Sets the backlight color of every panel
+    pub fn change_backlight(&self, r: u8, g: u8, b: u8) -> BTreeMap<String, Result<(), QwiicLcdError>> {
+        self.broadcast(|screen| screen.change_backlight(r, g, b))
+    }
+
+    /// Health of one panel
+    pub fn health(&self, name: &str) -> Option<PanelHealth> {
+        self.panels.get(name).map(|panel| lock(&panel.health).clone())
+    }
+
+    /// Health of every panel
+    pub fn health_report(&self) -> BTreeMap<String, PanelHealth> {
+        self.panels
+            .iter()
+            .map(|(name, panel)| (name.clone(), lock(&panel.health).clone()))
+            .collect()
+    }
+
+    fn run<R, F>(&self, panel: &Panel<T>, f: F) -> Result<R, QwiicLcdError>
+    where
+        F: FnOnce(&mut Screen<T>) -> Result<R, QwiicLcdError>,
+    {
+        let result = {
+            let _bus = lock(&panel.bus_lock);
+            f(&mut lock(&panel.screen))
+        };
+        lock(&panel.health).record(&result, self.failure_threshold);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Emulator;
+    use i2cdev::linux::LinuxI2CError;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Fails every write with ENODEV, like an unplugged panel
+    struct Unplugged;
+
+    impl Transport for Unplugged {
+        fn write_byte(&mut self, _value: u8) -> Result<(), LinuxI2CError> {
+            Err(LinuxI2CError::Errno(19))
+        }
+
+        fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<(), LinuxI2CError> {
+            Err(LinuxI2CError::Errno(19))
+        }
+
+        fn write_block(&mut self, _register: u8, _data: &[u8]) -> Result<(), LinuxI2CError> {
+            Err(LinuxI2CError::Errno(19))
+        }
+
+        fn delay(&mut self, _duration: Duration) {}
+    }
+
+    /// Flags any overlap between writes of panels that share `busy`
+    struct BusProbe {
+        busy: Arc<AtomicUsize>,
+        overlapped: Arc<AtomicBool>,
+    }
+
+    impl BusProbe {
+        fn write(&mut self) -> Result<(), LinuxI2CError> {
+            if self.busy.fetch_add(1, Ordering::SeqCst) > 0 {
+                self.overlapped.store(true, Ordering::SeqCst);
+            }
+            thread::sleep(Duration::from_millis(1));
+            self.busy.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    impl Transport for BusProbe {
+        fn write_byte(&mut self, _value: u8) -> Result<(), LinuxI2CError> {
+            self.write()
+        }
+
+        fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<(), LinuxI2CError> {
+            self.write()
+        }
+
+        fn write_block(&mut self, _register: u8, _data: &[u8]) -> Result<(), LinuxI2CError> {
+            self.write()
+        }
+
+        fn delay(&mut self, _duration: Duration) {}
+    }
+
+    fn panel<P: Transport + 'static>(transport: P) -> Screen<Box<dyn Transport>> {
+        Screen::with_transport(ScreenConfig::new(2, 16), Box::new(transport) as Box<dyn Transport>)
+    }
+
+    #[test]
+    fn test_failing_panel_is_isolated() {
+        let mut group: ScreenGroup<Box<dyn Transport>> = ScreenGroup::new();
+        group.add("left", "/dev/i2c-1", panel(Emulator::new(2, 16)));
+        group.add("right", "/dev/i2c-1", panel(Unplugged));
+        group.add("remote", "/dev/i2c-3", panel(Emulator::new(2, 16)));
+        group.set_failure_threshold(2);
+
+        let results = group.change_backlight(255, 0, 0);
+        assert!(results["left"].is_ok() && results["remote"].is_ok());
+        assert!(results["right"].is_err());
+        assert_eq!(group.health("right").unwrap().status, PanelStatus::Degraded);
+        group.clear();
+        let report = group.health_report();
+        assert_eq!(report["right"].status, PanelStatus::Failed);
+        assert_eq!(report["right"].consecutive_failures, 2);
+        assert_eq!(report["left"].status, PanelStatus::Healthy);
+        assert_eq!(report["left"].operations, 2);
+
+        group.with("remote", |screen| screen.print("only me")).unwrap().unwrap();
+        assert!(group.with("nowhere", |screen| screen.clear()).is_none());
+        let left = group.remove("left").unwrap();
+        assert_eq!(left.backlight(), Some((255, 0, 0)));
+        assert_eq!(left.framebuffer().row_text(0), " ".repeat(16));
+        assert_eq!(group.names().collect::<Vec<_>>(), ["remote", "right"]);
+    }
+
+    #[test]
+    fn test_panels_on_one_bus_take_turns() {
+        let overlapped = Arc::new(AtomicBool::new(false));
+        let busy = Arc::new(AtomicUsize::new(0));
+        let mut group = ScreenGroup::new();
+        for name in ["a", "b", "c"] {
+            let probe = BusProbe { busy: Arc::clone(&busy), overlapped: Arc::clone(&overlapped) };
+            group.add(name, "/dev/i2c-1", Screen::with_transport(ScreenConfig::new(2, 16), probe));
+        }
+
+        thread::scope(|scope| {
+            scope.spawn(|| group.broadcast(|screen| screen.print("abc")));
+            scope.spawn(|| group.with("b", |screen| screen.print("def")));
+            scope.spawn(|| group.clear());
+        });
+        assert!(!overlapped.load(Ordering::SeqCst));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_health_round_trip() {
+        let health = PanelHealth {
+            status: PanelStatus::Degraded,
+            consecutive_failures: 2,
+            operations: 10,
+            failures: 3,
+            last_error: Some("I/O error".to_string()),
+            last_success: Some(Instant::now()),
+        };
+        let json = serde_json::to_string(&health).unwrap();
+        let back: PanelHealth = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.status, PanelStatus::Degraded);
+        assert_eq!((back.consecutive_failures, back.operations, back.failures), (2, 10, 3));
+        assert_eq!(back.last_error.as_deref(), Some("I/O error"));
+        // Instants are process-local and aren't serialized
+        assert!(back.last_success.is_none());
+        assert!(!json.contains("last_success"));
+    }
+}
@@ -0,0 +1,407 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+
+use crate::{FrameBuffer, QwiicLcdError, Screen, Transport};
+
+/// Request queued for the render thread behind a `ScreenHandle`
+#[derive(Clone, Debug)]
+pub enum RenderCommand {
+    /// Writes text at a position, truncated at the end of the row
+    Text { row: usize, col: usize, text: String },
+    /// Writes raw bytes (custom glyphs 0-7, ROM characters) at a position
+    Bytes { row: usize, col: usize, bytes: Vec<u8> },
+    /// Blanks the whole display
+    Clear,
+    /// Replaces the whole display contents
+    Present(FrameBuffer),
+    /// Changes the backlight color (RGB)
+    Backlight(u8, u8, u8),
+    /// Sets the contrast (0-255)
+    Contrast(u8),
+    /// Defines custom character 0-7
+    Glyph(u8, [u8; 8]),
+    /// Shows or hides the underline cursor
+    Cursor(bool),
+    /// Enables or disables the blinking block cursor
+    Blink(bool),
+    /// Turns the display on or off
+    Display(bool),
+}
+
+/// Error returned by `ScreenHandle` methods
+#[derive(Debug)]
+pub enum HandleError {
+    /// The render thread has stopped
+    Closed,
+    /// The queue is full (only returned by `try_send`)
+    Full(RenderCommand),
+    /// A queued command failed on the panel; reported by `sync`
+    Screen(QwiicLcdError),
+}
+
+impl fmt::Display for HandleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HandleError::Closed => write!(f, "Render thread has stopped"),
+            HandleError::Full(_) => write!(f, "Render queue is full"),
+            HandleError::Screen(e) => write!(f, "Queued command failed: {}", e),
+        }
+    }
+}
+
+impl Error for HandleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HandleError::Screen(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+enum Message {
+    Command(RenderCommand),
+    Sync(Sender<Option<QwiicLcdError>>),
+}
+
+/// Cheap, cloneable handle to a `Screen` owned by a render thread
+///
+/// Commands are queued and return immediately, so application threads never
+/// wait for the I2C bus. The render thread takes everything queued at once
+/// and coalesces it: only the last backlight, contrast and display setting
+/// of a batch are sent, and all text writes are merged into one framebuffer
+/// diff sent with `Screen::present`. The queue is bounded, so a producer
+/// outrunning the bus blocks in `send` (or gets `HandleError::Full` from
+/// `try_send`) instead of growing memory.
+///
+/// A batch is sent in a fixed order, not in arrival order: glyphs, display,
+/// cursor, blink, contrast, backlight, then the merged text. When one
+/// command must reach the panel before another (say, text drawn with a
+/// glyph before that glyph is redefined), call `sync` between them.
+///
+/// The first error from queued commands is kept and reported by `sync`,
+/// which also waits until everything queued before it reached the panel. The
+/// render thread stops once every handle is dropped and hands the screen
+/// back through its `JoinHandle`.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+/// let (handle, render) = ScreenHandle::spawn(screen, 64);
+/// let worker = handle.clone();
+/// std::thread::spawn(move || worker.print_at(1, 0, "from a worker").unwrap()).join().unwrap();
+/// handle.change_backlight(0, 0, 255).unwrap();
+/// handle.sync().unwrap();
+/// drop(handle);
+/// let screen = render.join().unwrap();
+/// assert_eq!(screen.transport().row_text(1), "from a worker   ");
+/// ```
+#[derive(Clone)]
+pub struct ScreenHandle {
+    sender: SyncSender<Message>,
+}
+
+impl ScreenHandle {
+    /// Moves `screen` to a new render thread with a queue of `capacity` commands
+    pub fn spawn<T: Transport + 'static>(screen: Screen<T>, capacity: usize) -> (ScreenHandle, JoinHandle<Screen<T>>) {
+        let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+        let render = thread::Builder::new()
+            .name("qwiic-lcd-render".to_string())
+            .spawn(move || render_loop(screen, receiver))
+            .expect("failed to spawn render thread");
+        (ScreenHandle { sender }, render)
+    }
+
+    /// Queues a command, waiting for room if the queue is full
+    pub fn send(&self, command: RenderCommand) -> Result<(), HandleError> {
+        self.sender.send(Message::Command(command)).map_err(|_| HandleError::Closed)
+    }
+
+    /// Queues a command without waiting
+    pub fn try_send(&self, command: RenderCommand) -> Result<(), HandleError> {
+        self.sender.try_send(Message::Command(command)).map_err(|e| match e {
+            TrySendError::Full(Message::Command(command)) => HandleError::Full(command),
+            _ => HandleError::Closed,
+        })
+    }
+
+    /// Waits until every command queued so far reached the panel
+    ///
+    /// Returns the first error raised since the previous `sync`, if any;
+    /// later errors are dropped so an unsynced handle doesn't grow memory.
+    pub fn sync(&self) -> Result<(), HandleError> {
+        let (reply, done) = mpsc::channel();
+        self.sender.send(Message::Sync(reply)).map_err(|_| HandleError::Closed)?;
+        match done.recv() {
+            Ok(None) => Ok(()),
+            Ok(Some(e)) => Err(HandleError::Screen(e)),
+            Err(_) => Err(HandleError::Closed),
+        }
+    }
+
+    /// Queues text at the given position
+    pub fn print_at(&self, row: usize, col: usize, text: &str) -> Result<(), HandleError> {
+        self.send(RenderCommand::Text { row, col, text: text.to_string() })
+    }
+
+    /// Queues clearing the display
+    pub fn clear(&self) -> Result<(), HandleError> {
+        self.send(RenderCommand::Clear)
+    }
+
+    /// Queues a whole new display contents
+    pub fn present(&self, frame: FrameBuffer) -> Result<(), HandleError> {
+        self.send(RenderCommand::Present(frame))
+    }
+
+    /// Queues a backlight color change
+    pub fn change_backlight(&self, r: u8, g: u8, b: u8) -> Result<(), HandleError> {
+        self.send(RenderCommand::Backlight(r, g, b))
+    }
+
+    /// Queues a contrast change
+    pub fn set_contrast(&self, contrast: u8) -> Result<(), HandleError> {
+        self.send(RenderCommand::Contrast(contrast))
+    }
+
+    /// Queues a custom character definition
+    pub fn create_character(&self, index: u8, data: [u8; 8]) -> Result<(), HandleError> {
+        self.send(RenderCommand::Glyph(index, data))
+    }
+}
+
+/// Net effect of a batch of commands
+#[derive(Default)]
+struct Pending {
+    frame: Option<FrameBuffer>,
+    backlight: Option<(u8, u8, u8)>,
+    contrast: Option<u8>,
+    glyphs: [Option<[u8; 8]>; 8],
+    /// First command rejected while batching
+    error: Option<QwiicLcdError>,
+    cursor: Option<bool>,
+    blink: Option<bool>,
+    display: Option<bool>,
+}
+
+impl Pending {
+    fn apply<T: Transport>(&mut self, screen: &Screen<T>, command: RenderCommand) {
+        match command {
+            RenderCommand::Text { row, col, text } => {
+                let bytes: Vec<u8> = text.chars().map(|c| screen.map_character(c)).collect();
+                self.write_at(screen, row, col, &bytes);
+            }
+            RenderCommand::Bytes { row, col, bytes } => self.write_at(screen, row, col, &bytes),
+            RenderCommand::Clear => self.frame(screen).clear(),
+            RenderCommand::Present(replacement) => *self.frame(screen) = replacement,
+            RenderCommand::Backlight(r, g, b) => self.backlight = Some((r, g, b)),
+            RenderCommand::Contrast(contrast) => self.contrast = Some(contrast),
+            RenderCommand::Glyph(index, data) => match self.glyphs.get_mut(index as usize) {
+                Some(slot) => *slot = Some(data),
+                None => self.reject(QwiicLcdError::InvalidCustomCharIndex(index)),
+            },
+            RenderCommand::Cursor(on) => self.cursor = Some(on),
+            RenderCommand::Blink(on) => self.blink = Some(on),
+            RenderCommand::Display(on) => self.display = Some(on),
+        }
+    }
+
+    fn reject(&mut self, error: QwiicLcdError) {
+        self.error.get_or_insert(error);
+    }
+
+    /// Frame being built by this batch, starting from the screen contents
+    fn frame<T: Transport>(&mut self, screen: &Screen<T>) -> &mut FrameBuffer {
+        self.frame.get_or_insert_with(|| screen.framebuffer().clone())
+    }
+
+    /// Writes bytes into the pending frame at (row, col), without wrapping
+    fn write_at<T: Transport>(&mut self, screen: &Screen<T>, row: usize, col: usize, bytes: &[u8]) {
+        let (rows, columns) = (screen.framebuffer().rows(), screen.framebuffer().columns());
+        if row >= rows as usize || col >= columns as usize {
+            self.reject(QwiicLcdError::InvalidPosition { row, col, max_rows: rows, max_columns: columns });
+            return;
+        }
+        let frame = self.frame(screen);
+        for (offset, byte) in bytes.iter().take(columns as usize - col).enumerate() {
+            frame.set(row, col + offset, *byte);
+        }
+    }
+
+    /// Sends the whole batch even if a command fails, keeping the first
+    /// error in `error` unless it already holds one
+    fn flush<T: Transport>(self, screen: &mut Screen<T>, error: &mut Option<QwiicLcdError>) {
+        let mut results = Vec::new();
+        for (index, glyph) in self.glyphs.iter().enumerate() {
+            if let Some(data) = glyph {
+                results.push(screen.create_character(index as u8, *data));
+            }
+        }
+        if let Some(on) = self.display {
+            results.push(screen.enable_display(on));
+        }
+        if let Some(on) = self.cursor {
+            results.push(screen.enable_cursor(on));
+        }
+        if let Some(on) = self.blink {
+            results.push(screen.enable_blink(on));
+        }
+        if let Some(contrast) = self.contrast.filter(|c| screen.contrast() != Some(*c)) {
+            results.push(screen.set_contrast(contrast));
+        }
+        if let Some((r, g, b)) = self.backlight.filter(|color| screen.backlight() != Some(*color)) {
+            results.push(screen.change_backlight(r, g, b));
+        }
+        if let Some(frame) = self.frame {
+            results.push(screen.present(&frame));
+        }
+        let mut errors = self.error.into_iter().chain(results.into_iter().filter_map(Result::err));
+        if error.is_none() {
+            *error = errors.next();
+        }
+    }
+}
+
+fn render_loop<T: Transport>(mut screen: Screen<T>, receiver: Receiver<Message>) -> Screen<T> {
+    let mut error = None;
+    while let Ok(first) = receiver.recv() {
+        let mut pending = Pending::default();
+        for message in std::iter::once(first).chain(receiver.try_iter()) {
+            match message {
+                Message::Command(command) => pending.apply(&screen, command),
+                Message::Sync(reply) => {
+                    std::mem::take(&mut pending).flush(&mut screen, &mut error);
+                    let _ = reply.send(error.take());
+                }
+            }
+        }
+        pending.flush(&mut screen, &mut error);
+    }
+    screen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+    use i2cdev::linux::LinuxI2CError;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Emulator whose first write waits for the test to open the gate, so
+    /// commands pile up in the queue meanwhile
+    struct Gated {
+        lcd: Emulator,
+        gate: Option<(Sender<()>, Receiver<()>)>,
+        log: WriteLog,
+    }
+
+    type WriteLog = Arc<Mutex<Vec<Vec<u8>>>>;
+
+    impl Gated {
+        fn wait(&mut self) {
+            if let Some((entered, gate)) = self.gate.take() {
+                let _ = entered.send(());
+                let _ = gate.recv();
+            }
+        }
+    }
+
+    impl Transport for Gated {
+        fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+            self.wait();
+            self.log.lock().unwrap().push(vec![value]);
+            self.lcd.write_byte(value)
+        }
+
+        fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+            self.wait();
+            self.log.lock().unwrap().push(vec![register, value]);
+            self.lcd.write_byte_data(register, value)
+        }
+
+        fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+            self.wait();
+            self.log.lock().unwrap().push([&[register], data].concat());
+            self.lcd.write_block(register, data)
+        }
+
+        fn delay(&mut self, _duration: Duration) {}
+    }
+
+    /// Spawns a render thread and waits until it is stuck on its first
+    /// write; returns its handle, a sender opening the gate and the write log
+    fn blocked(capacity: usize) -> (ScreenHandle, JoinHandle<Screen<Gated>>, Sender<()>, WriteLog) {
+        let (open, gate) = mpsc::channel();
+        let (entered, blocked) = mpsc::channel();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let transport = Gated { lcd: Emulator::new(2, 16), gate: Some((entered, gate)), log: Arc::clone(&log) };
+        let (handle, render) = ScreenHandle::spawn(Screen::with_transport(ScreenConfig::new(2, 16), transport), capacity);
+        handle.print_at(0, 0, "x").unwrap();
+        blocked.recv().unwrap();
+        (handle, render, open, log)
+    }
+
+    #[test]
+    fn test_batches_are_coalesced() {
+        let (handle, render, open, log) = blocked(32);
+
+        for level in 1..=5 {
+            handle.change_backlight(level, 0, 0).unwrap();
+        }
+        handle.print_at(1, 0, "Hello world").unwrap();
+        handle.print_at(1, 6, "there").unwrap();
+        log.lock().unwrap().clear();
+        open.send(()).unwrap();
+        handle.sync().unwrap();
+
+        let log = log.lock().unwrap().clone();
+        let backlights: Vec<_> = log.iter().filter(|w| w.starts_with(&[0x7C, 0x2B])).collect();
+        assert_eq!(backlights, vec![&vec![0x7C, 0x2B, 5, 0, 0]]);
+        drop(handle);
+        let screen = render.join().unwrap();
+        assert_eq!(screen.transport().lcd.row_text(1), "Hello there     ");
+        assert_eq!(screen.transport().lcd.backlight(), (5, 0, 0));
+    }
+
+    #[test]
+    fn test_back_pressure_and_errors() {
+        let (handle, render, open, _log) = blocked(2);
+        handle.clear().unwrap();
+        handle.clear().unwrap();
+        assert!(matches!(handle.try_send(RenderCommand::Contrast(9)), Err(HandleError::Full(RenderCommand::Contrast(9)))));
+        open.send(()).unwrap();
+
+        handle.create_character(9, [0; 8]).unwrap();
+        handle.print_at(5, 0, "nowhere").unwrap();
+        let error = handle.sync().unwrap_err();
+        assert!(matches!(error, HandleError::Screen(QwiicLcdError::InvalidCustomCharIndex(9))), "{}", error);
+        handle.sync().unwrap();
+
+        // Without a sync only the first error is kept
+        for row in 5..1000 {
+            handle.print_at(row, 0, "nowhere").unwrap();
+        }
+        let error = handle.sync().unwrap_err();
+        assert!(matches!(error, HandleError::Screen(QwiicLcdError::InvalidPosition { row: 5, .. })), "{}", error);
+        handle.sync().unwrap();
+        drop(handle);
+        render.join().unwrap();
+    }
+}
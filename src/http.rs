@@ -0,0 +1,329 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{self, Read};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{QwiicLcdError, Screen, Transport};
+
+/// Largest request body the API accepts
+const MAX_BODY: u64 = 16 * 1024;
+
+#[derive(Deserialize)]
+struct RowBody {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct BacklightBody {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+#[derive(Deserialize)]
+struct ContrastBody {
+    contrast: u8,
+}
+
+/// Embedded HTTP server exposing a `Screen` as a small JSON API
+///
+/// Every request must carry `Authorization: Bearer <token>`. Routes:
+///
+/// | Method | Path               | Body                        |
+/// |--------|--------------------|-----------------------------|
+/// | GET    | `/api/display`     |                             |
+/// | PUT    | `/api/rows/{row}`  | `{"text": "Hello"}`         |
+/// | PUT    | `/api/backlight`   | `{"r": 0, "g": 255, "b": 0}`|
+/// | PUT    | `/api/contrast`    | `{"contrast": 40}`          |
+/// | POST   | `/api/clear`       |                             |
+///
+/// `GET /api/display` returns the tracked framebuffer rows, cursor,
+/// backlight and contrast. Row writes replace the whole row and only send
+/// the cells that changed.
+///
+/// # Example
+/// ```no_run
+/// # use qwiic_lcd_rs::*;
+/// let screen = Screen::new(ScreenConfig::default(), "/dev/i2c-1", 0x72).unwrap();
+/// let server = HttpServer::bind("0.0.0.0:8080", screen, "s3cret").unwrap();
+/// server.serve().unwrap();
+/// ```
+pub struct HttpServer<T: Transport> {
+    server: Server,
+    screen: Arc<Mutex<Screen<T>>>,
+    token: String,
+}
+
+impl<T: Transport> HttpServer<T> {
+    /// Binds the API to `addr`; requests must present `token`
+    ///
+    /// An empty token is rejected, since it would let anyone in.
+    pub fn bind(addr: impl ToSocketAddrs, screen: Screen<T>, token: impl Into<String>) -> io::Result<HttpServer<T>> {
+        let token = token.into();
+        if token.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "the API token must not be empty"));
+        }
+        let server = Server::http(addr).map_err(io::Error::other)?;
+        Ok(HttpServer {
+            server,
+            screen: Arc::new(Mutex::new(screen)),
+            token,
+        })
+    }
+
+    /// Address the server is listening on (useful after binding port 0)
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Shared handle to the screen being driven
+    pub fn screen(&self) -> Arc<Mutex<Screen<T>>> {
+        Arc::clone(&self.screen)
+    }
+
+    /// Handles requests forever; only returns on a listener error
+    ///
+    /// A client that hangs up before reading its response doesn't stop the
+    /// server.
+    pub fn serve(&self) -> io::Result<()> {
+        loop {
+            let request = self.server.recv()?;
+            if let Err(_e) = self.respond(request) {
+                #[cfg(feature = "log")]
+                log::warn!("http response failed: {}", _e);
+            }
+        }
+    }
+
+    fn respond(&self, mut request: Request) -> io::Result<()> {
+        let (status, body) = if !self.authorized(&request) {
+            (401, json!({ "error": "missing or invalid token" }))
+        } else {
+            match read_body(&mut request) {
+                Ok(body) => {
+                    let mut screen = self.screen.lock().unwrap_or_else(|e| e.into_inner());
+                    route(&mut screen, request.method(), request.url(), &body)
+                }
+                Err(error) => error,
+            }
+        };
+        let content_type = Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type);
+        request.respond(response)
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        let expected = format!("Bearer {}", self.token);
+        request.headers().iter().any(|header| {
+            header.field.equiv("Authorization") && constant_time_eq(header.value.as_str().as_bytes(), expected.as_bytes())
+        })
+    }
+}
+
+/// Reads the request body, refusing bodies over `MAX_BODY` with 413
+fn read_body(request: &mut Request) -> Result<String, (u16, Value)> {
+    let too_large = || (413, json!({ "error": format!("request body is larger than {} bytes", MAX_BODY) }));
+    if request.body_length().is_some_and(|length| length as u64 > MAX_BODY) {
+        return Err(too_large());
+    }
+    // Bodies without a length are read one byte past the limit to spot them
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| (400, json!({ "error": format!("could not read request body: {}", e) })))?;
+    if body.len() as u64 > MAX_BODY {
+        return Err(too_large());
+    }
+    String::from_utf8(body).map_err(|_| (400, json!({ "error": "request body is not valid UTF-8" })))
+}
+
+/// Compares two byte strings without leaking the position of the first
+/// difference through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn parse<'a, B: Deserialize<'a>>(body: &'a str) -> Result<B, (u16, Value)> {
+    serde_json::from_str(body).map_err(|e| (400, json!({ "error": format!("invalid body: {}", e) })))
+}
+
+fn screen_error(error: QwiicLcdError) -> (u16, Value) {
+    let status = match error {
+        QwiicLcdError::InvalidPosition { .. } | QwiicLcdError::InvalidCharacter(_) => 400,
+        ref e if e.is_transient() => 503,
+        _ => 500,
+    };
+    (status, json!({ "error": error.to_string() }))
+}
+
+fn display<T: Transport>(screen: &Screen<T>) -> Value {
+    let framebuffer = screen.framebuffer();
+    let rows: Vec<String> = (0..framebuffer.rows() as usize).map(|row| framebuffer.row_text(row)).collect();
+    json!({
+        "rows": rows,
+        "cursor": framebuffer.cursor(),
+        "backlight": screen.backlight(),
+        "contrast": screen.contrast(),
+    })
+}
+
+fn route<T: Transport>(screen: &mut Screen<T>, method: &Method, url: &str, body: &str) -> (u16, Value) {
+    let path = url.split('?').next().unwrap_or(url).trim_end_matches('/');
+    let result = match (method, path) {
+        (Method::Get, "/api/display") => Ok(()),
+        (Method::Put, "/api/backlight") => {
+            parse::<BacklightBody>(body).and_then(|b| screen.change_backlight(b.r, b.g, b.b).map_err(screen_error))
+        }
+        (Method::Put, "/api/contrast") => {
+            parse::<ContrastBody>(body).and_then(|b| screen.set_contrast(b.contrast).map_err(screen_error))
+        }
+        (Method::Post, "/api/clear") => screen.clear().map_err(screen_error),
+        (Method::Put, path) if path.starts_with("/api/rows/") => {
+            let row = path["/api/rows/".len()..].parse::<usize>().ok();
+            match row.filter(|row| *row < screen.framebuffer().rows() as usize) {
+                Some(row) => parse::<RowBody>(body).and_then(|b| write_row(screen, row, &b.text).map_err(screen_error)),
+                None => Err((404, json!({ "error": "no such row" }))),
+            }
+        }
+        (_, "/api/display" | "/api/backlight" | "/api/contrast" | "/api/clear") => {
+            Err((405, json!({ "error": "method not allowed" })))
+        }
+        _ => Err((404, json!({ "error": "not found" }))),
+    };
+    match result {
+        Ok(()) => (200, display(screen)),
+        Err(error) => error,
+    }
+}
+
+/// Replaces a row with `text`, truncated or padded to the panel width
+fn write_row<T: Transport>(screen: &mut Screen<T>, row: usize, text: &str) -> Result<(), QwiicLcdError> {
+    let mut frame = screen.framebuffer().clone();
+    let mut bytes = text.chars().map(|c| if (' '..='~').contains(&c) { c as u8 } else { b'?' });
+    for col in 0..frame.columns() as usize {
+        frame.set(row, col, bytes.next().unwrap_or(b' '));
+    }
+    screen.present(&frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::thread;
+
+    fn emulated() -> Screen<Emulator> {
+        Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16))
+    }
+
+    #[test]
+    fn test_routes() {
+        let mut screen = emulated();
+        let (status, body) = route(&mut screen, &Method::Put, "/api/rows/1", r#"{"text":"Kiosk 7"}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["rows"][1], "Kiosk 7         ");
+        assert_eq!(screen.transport().row_text(1), "Kiosk 7         ");
+
+        let (status, body) = route(&mut screen, &Method::Put, "/api/backlight", r#"{"r":0,"g":128,"b":255}"#);
+        assert_eq!(status, 200);
+        assert_eq!(body["backlight"], json!([0, 128, 255]));
+        assert_eq!(screen.transport().backlight(), (0, 128, 255));
+
+        let (status, body) = route(&mut screen, &Method::Put, "/api/contrast", r#"{"contrast":40}"#);
+        assert_eq!((status, &body["contrast"]), (200, &json!(40)));
+        assert_eq!(screen.transport().contrast(), 40);
+
+        assert_eq!(route(&mut screen, &Method::Post, "/api/clear", "").0, 200);
+        assert_eq!(screen.transport().row_text(1), " ".repeat(16));
+
+        assert_eq!(route(&mut screen, &Method::Put, "/api/rows/2", r#"{"text":"x"}"#).0, 404);
+        assert_eq!(route(&mut screen, &Method::Put, "/api/contrast", r#"{"contrast":999}"#).0, 400);
+        assert_eq!(route(&mut screen, &Method::Delete, "/api/display", "").0, 405);
+        assert_eq!(route(&mut screen, &Method::Get, "/api/nope", "").0, 404);
+    }
+
+    fn send(addr: SocketAddr, request: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap_or("null");
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_localhost_server() {
+        assert!(HttpServer::bind("127.0.0.1:0", emulated(), "").is_err());
+        let server = HttpServer::bind("127.0.0.1:0", emulated(), "t0ken").unwrap();
+        let addr = server.local_addr().unwrap();
+        let screen = server.screen();
+        thread::spawn(move || server.serve());
+
+        let body = r#"{"text":"Hello fleet"}"#;
+        let (status, _) = send(
+            addr,
+            &format!("PUT /api/rows/0 HTTP/1.1\r\nHost: x\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}", body.len(), body),
+        );
+        assert_eq!(status, 401);
+        assert_eq!(screen.lock().unwrap().transport().row_text(0), " ".repeat(16));
+
+        // A client hanging up without reading its reply doesn't stop the server
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /api/display HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer t0ken\r\n\r\n").unwrap();
+        drop(stream);
+
+        let (status, reply) = send(
+            addr,
+            &format!(
+                "PUT /api/rows/0 HTTP/1.1\r\nHost: x\r\nConnection: close\r\nAuthorization: Bearer t0ken\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+        );
+        assert_eq!(status, 200);
+        assert_eq!(reply["rows"][0], "Hello fleet     ");
+        assert_eq!(screen.lock().unwrap().transport().row_text(0), "Hello fleet     ");
+
+        let (status, reply) = send(
+            addr,
+            "GET /api/display HTTP/1.1\r\nHost: x\r\nConnection: close\r\nAuthorization: Bearer t0ken\r\n\r\n",
+        );
+        assert_eq!(status, 200);
+        assert_eq!(reply["rows"], json!(["Hello fleet     ", "                "]));
+
+        // Oversized bodies are refused from the declared length alone
+        let (status, reply) = send(
+            addr,
+            &format!(
+                "PUT /api/rows/0 HTTP/1.1\r\nHost: x\r\nConnection: close\r\nAuthorization: Bearer t0ken\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY + 1
+            ),
+        );
+        assert_eq!(status, 413);
+        assert!(reply["error"].as_str().unwrap().contains("larger than"));
+    }
+}
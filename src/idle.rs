@@ -0,0 +1,451 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{Backlight, FrameBuffer, QwiicLcdError, Screen, Snapshot, Transport};
+
+type ScreenResult = Result<(), QwiicLcdError>;
+type Clock = Box<dyn Fn() -> u32 + Send>;
+
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
+/// Backlight the panel powers up with, assumed when none was set
+const POWER_ON_BACKLIGHT: (u8, u8, u8) = (255, 255, 255);
+
+/// Animation shown while the panel is idle
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Screensaver {
+    /// The time as `HH:MM`, jumping to a new position every step
+    Clock,
+    /// Text moving one column every step, a row lower on each pass
+    Drift(String),
+}
+
+/// Daily period, as (hour, minute) in the clock's time, during which the panel stays off when idle
+///
+/// `start` after `end` spans midnight, e.g. 22:00 to 07:00.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuietHours {
+    pub start: (u8, u8),
+    pub end: (u8, u8),
+}
+
+impl QuietHours {
+    /// Whether the given time of day (seconds since midnight) falls in the period
+    pub fn contains(&self, seconds: u32) -> bool {
+        let minute = (seconds % SECONDS_PER_DAY) / 60;
+        let start = self.start.0 as u32 * 60 + self.start.1 as u32;
+        let end = self.end.0 as u32 * 60 + self.end.1 as u32;
+        if start <= end {
+            (start..end).contains(&minute)
+        } else {
+            minute >= start || minute < end
+        }
+    }
+}
+
+/// Configuration of an `IdleManager`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct IdleConfig {
+    /// Inactivity before the backlight is dimmed
+    pub dim_after: Option<Duration>,
+    /// Dimmed brightness, in percent of the backlight before dimming
+    pub dim_level: u8,
+    /// Inactivity before the screensaver starts
+    pub screensaver_after: Option<Duration>,
+    /// Animation to run; `None` keeps the display contents
+    pub screensaver: Option<Screensaver>,
+    /// Time between screensaver frames
+    pub screensaver_step: Duration,
+    /// Inactivity before the backlight is turned off
+    pub off_after: Option<Duration>,
+    /// Period in which the backlight goes off as soon as the panel is idle for `dim_after`
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        IdleConfig {
+            dim_after: Some(Duration::from_secs(30)),
+            dim_level: 20,
+            screensaver_after: Some(Duration::from_secs(120)),
+            screensaver: None,
+            screensaver_step: Duration::from_secs(2),
+            off_after: Some(Duration::from_secs(600)),
+            quiet_hours: None,
+        }
+    }
+}
+
+/// Idle stage of the panel
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IdleState {
+    Active,
+    Dimmed,
+    Screensaver,
+    Off,
+}
+
+/// Dims, blanks and wakes a panel based on inactivity
+///
+/// Call `poll` from the main loop and `wake` on every input event. Any
+/// write to the screen also counts as activity: `poll` notices it in the
+/// bus statistics. Before dimming, the manager snapshots the screen, and
+/// on wake it restores the backlight, the display contents under the
+/// screensaver and the cursor, keeping anything the application wrote in
+/// the meantime.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// # use std::time::{Duration, Instant};
+/// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+/// screen.change_backlight(200, 200, 200).unwrap();
+/// let mut idle = IdleManager::new(IdleConfig::default());
+///
+/// let start = Instant::now();
+/// idle.poll(&mut screen, start).unwrap();
+/// idle.poll(&mut screen, start + Duration::from_secs(30)).unwrap();
+/// assert_eq!(idle.state(), IdleState::Dimmed);
+/// assert_eq!(screen.transport().backlight(), (40, 40, 40));
+///
+/// idle.wake(&mut screen, start + Duration::from_secs(31)).unwrap();
+/// assert_eq!(screen.transport().backlight(), (200, 200, 200));
+/// ```
+pub struct IdleManager {
+    config: IdleConfig,
+    clock: Clock,
+    state: IdleState,
+    last_activity: Option<Instant>,
+    writes: Option<u64>,
+    snapshot: Option<Snapshot>,
+    dimmed_to: Option<(u8, u8, u8)>,
+    saver_frame: Option<FrameBuffer>,
+    saver_step: u32,
+    next_step: Option<Instant>,
+}
+
+impl IdleManager {
+    /// Creates a manager reading the time of day from the system clock in UTC
+    pub fn new(config: IdleConfig) -> IdleManager {
+        IdleManager {
+            config,
+            clock: Box::new(|| {
+                let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+                (seconds % SECONDS_PER_DAY as u64) as u32
+            }),
+            state: IdleState::Active,
+            last_activity: None,
+            writes: None,
+            snapshot: None,
+            dimmed_to: None,
+            saver_frame: None,
+            saver_step: 0,
+            next_step: None,
+        }
+    }
+
+    /// Reads the local time of day (seconds since midnight) from `clock`
+    ///
+    /// Used for quiet hours and the clock screensaver.
+    pub fn with_clock<F: Fn() -> u32 + Send + 'static>(mut self, clock: F) -> IdleManager {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Current idle stage
+    pub fn state(&self) -> IdleState {
+        self.state
+    }
+
+    /// Records activity, restoring the panel if it was idle
+    pub fn wake<T: Transport>(&mut self, screen: &mut Screen<T>, now: Instant) -> ScreenResult {
+        self.last_activity = Some(now);
+        if self.state != IdleState::Active {
+            self.restore(screen)?;
+        }
+        self.writes = Some(writes(screen));
+        Ok(())
+    }
+
+    /// Moves to the idle stage due at `now` and advances the screensaver
+    pub fn poll<T: Transport>(&mut self, screen: &mut Screen<T>, now: Instant) -> Result<IdleState, QwiicLcdError> {
+        let seen = writes(screen);
+        if self.writes != Some(seen) || self.last_activity.is_none() {
+            self.wake(screen, now)?;
+            return Ok(self.state);
+        }
+
+        let idle = now.saturating_duration_since(self.last_activity.unwrap_or(now));
+        let target = self.target(idle);
+        if target > self.state {
+            self.enter(screen, target, now)?;
+        }
+        if self.state >= IdleState::Screensaver && self.next_step.is_some_and(|step| now >= step) {
+            self.draw_saver(screen, now)?;
+        }
+        self.writes = Some(writes(screen));
+        Ok(self.state)
+    }
+
+    /// Deepest stage reached after being idle for `idle`
+    fn target(&self, idle: Duration) -> IdleState {
+        let reached = |after: Option<Duration>| after.is_some_and(|after| idle >= after);
+        if let Some(quiet) = self.config.quiet_hours {
+            if quiet.contains((self.clock)()) && idle >= self.config.dim_after.unwrap_or_default() {
+                return IdleState::Off;
+            }
+        }
+        if reached(self.config.off_after) {
+            IdleState::Off
+        } else if reached(self.config.screensaver_after) && self.config.screensaver.is_some() {
+            IdleState::Screensaver
+        } else if reached(self.config.dim_after) {
+            IdleState::Dimmed
+        } else {
+            IdleState::Active
+        }
+    }
+
+    fn enter<T: Transport>(&mut self, screen: &mut Screen<T>, target: IdleState, now: Instant) -> ScreenResult {
+        if self.snapshot.is_none() {
+            let mut snapshot = screen.snapshot();
+            // Without this, waking would leave the dimmed color in place
+            snapshot.backlight.get_or_insert(POWER_ON_BACKLIGHT);
+            self.snapshot = Some(snapshot);
+        }
+        #[cfg(feature = "log")]
+        log::debug!("panel idle: {:?}", target);
+        match target {
+            IdleState::Active => {}
+            IdleState::Dimmed | IdleState::Screensaver => {
+                if self.dimmed_to.is_none() {
+                    let (r, g, b) = screen.backlight().unwrap_or(POWER_ON_BACKLIGHT);
+                    let scale = |c: u8| (c as u32 * self.config.dim_level.min(100) as u32 / 100) as u8;
+                    let dimmed = (scale(r), scale(g), scale(b));
+                    screen.change_backlight(dimmed.0, dimmed.1, dimmed.2)?;
+                    self.dimmed_to = Some(dimmed);
+                }
+            }
+            IdleState::Off => {
+                screen.set_backlight_state(Backlight::Off)?;
+                self.dimmed_to = Some((0, 0, 0));
+            }
+        }
+        let quiet = self.config.quiet_hours.is_some_and(|quiet| quiet.contains((self.clock)()));
+        if target >= IdleState::Screensaver && self.saver_frame.is_none() && !quiet && self.config.screensaver.is_some() {
+            screen.enable_cursor(false)?;
+            screen.enable_blink(false)?;
+            self.draw_saver(screen, now)?;
+        }
+        self.state = target;
+        Ok(())
+    }
+
+    /// Draws the next screensaver frame
+    fn draw_saver<T: Transport>(&mut self, screen: &mut Screen<T>, now: Instant) -> ScreenResult {
+        let Some(saver) = &self.config.screensaver else { return Ok(()) };
+        let (rows, columns) = (screen.framebuffer().rows() as usize, screen.framebuffer().columns() as usize);
+        let mut frame = FrameBuffer::new(rows as u8, columns as u8);
+        let step = self.saver_step as usize;
+        let (text, row, start) = match saver {
+            Screensaver::Clock => {
+                let seconds = (self.clock)() % SECONDS_PER_DAY;
+                let text = format!("{:02}:{:02}", seconds / 3600, seconds / 60 % 60);
+                let (row, col) = bounce(step, rows, columns.saturating_sub(text.len()) + 1);
+                (text, row, col as isize)
+            }
+            Screensaver::Drift(text) => {
+                // Enters from the left edge and leaves past the right one
+                let len = text.chars().count();
+                let span = (columns + len).max(1);
+                (text.clone(), (step / span) % rows.max(1), (step % span) as isize - len as isize)
+            }
+        };
+        for (i, c) in text.chars().enumerate() {
+            if let Ok(col) = usize::try_from(start + i as isize) {
+                frame.set(row, col, u8::try_from(c).unwrap_or(b'?'));
+            }
+        }
+        screen.present(&frame)?;
+        self.saver_frame = Some(frame);
+        self.saver_step = self.saver_step.wrapping_add(1);
+        self.next_step = Some(now + self.config.screensaver_step);
+        Ok(())
+    }
+
+    /// Puts back the display and backlight, keeping what the application changed while idle
+    fn restore<T: Transport>(&mut self, screen: &mut Screen<T>) -> ScreenResult {
+        #[cfg(feature = "log")]
+        log::debug!("panel awake");
+        if let Some(mut snapshot) = self.snapshot.take() {
+            if let Some(saver) = self.saver_frame.take() {
+                let current = screen.framebuffer();
+                for row in 0..current.rows() as usize {
+                    for col in 0..current.columns() as usize {
+                        if current.get(row, col) != saver.get(row, col) {
+                            snapshot.frame.set(row, col, current.get(row, col).unwrap_or(b' '));
+                        }
+                    }
+                }
+            } else {
+                snapshot.frame = screen.framebuffer().clone();
+            }
+            if screen.backlight() != self.dimmed_to {
+                snapshot.backlight = screen.backlight();
+            }
+            screen.restore(&snapshot)?;
+        }
+        self.state = IdleState::Active;
+        self.dimmed_to = None;
+        self.saver_step = 0;
+        self.next_step = None;
+        Ok(())
+    }
+}
+
+/// Transactions sent to the screen so far
+fn writes<T: Transport>(screen: &Screen<T>) -> u64 {
+    screen.stats().total().transactions
+}
+
+/// Position of a bouncing item after `step` moves, within `rows` x `columns` positions
+fn bounce(step: usize, rows: usize, columns: usize) -> (usize, usize) {
+    let fold = |step: usize, size: usize| {
+        if size <= 1 {
+            return 0;
+        }
+        let period = 2 * (size - 1);
+        let position = step % period;
+        if position < size { position } else { period - position }
+    };
+    (fold(step, rows), fold(step * 3, columns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+
+    fn screen() -> Screen<Emulator> {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        screen.change_backlight(0, 100, 200).unwrap();
+        screen.print("Boiler 62C").unwrap();
+        screen
+    }
+
+    fn at(start: Instant, secs: u64) -> Instant {
+        start + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_stages_and_wake_on_write() {
+        let mut screen = screen();
+        let config = IdleConfig {
+            dim_after: Some(Duration::from_secs(10)),
+            dim_level: 50,
+            off_after: Some(Duration::from_secs(60)),
+            ..IdleConfig::default()
+        };
+        let mut idle = IdleManager::new(config);
+        let start = Instant::now();
+        assert_eq!(idle.poll(&mut screen, start).unwrap(), IdleState::Active);
+        assert_eq!(idle.poll(&mut screen, at(start, 10)).unwrap(), IdleState::Dimmed);
+        assert_eq!(screen.transport().backlight(), (0, 50, 100));
+        assert_eq!(idle.poll(&mut screen, at(start, 30)).unwrap(), IdleState::Dimmed);
+        assert_eq!(idle.poll(&mut screen, at(start, 60)).unwrap(), IdleState::Off);
+        assert_eq!(screen.transport().backlight(), (0, 0, 0));
+
+        screen.move_cursor(1, 0).unwrap();
+        screen.print("Pump on").unwrap();
+        assert_eq!(idle.poll(&mut screen, at(start, 61)).unwrap(), IdleState::Active);
+        assert_eq!(screen.transport().backlight(), (0, 100, 200));
+        assert_eq!(screen.transport().row_text(1), "Pump on         ");
+        assert_eq!(idle.poll(&mut screen, at(start, 70)).unwrap(), IdleState::Active);
+        assert_eq!(idle.poll(&mut screen, at(start, 71)).unwrap(), IdleState::Dimmed);
+    }
+
+    #[test]
+    fn test_wake_restores_power_on_backlight() {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        let config = IdleConfig { dim_after: Some(Duration::from_secs(10)), ..IdleConfig::default() };
+        let mut idle = IdleManager::new(config);
+        let start = Instant::now();
+        idle.poll(&mut screen, start).unwrap();
+        assert_eq!(idle.poll(&mut screen, at(start, 10)).unwrap(), IdleState::Dimmed);
+        assert_eq!(screen.transport().backlight(), (51, 51, 51));
+
+        idle.wake(&mut screen, at(start, 11)).unwrap();
+        assert_eq!(screen.transport().backlight(), (255, 255, 255));
+    }
+
+    #[test]
+    fn test_screensaver_restores_contents() {
+        let mut screen = screen();
+        screen.enable_cursor(true).unwrap();
+        let config = IdleConfig {
+            screensaver: Some(Screensaver::Drift("zzz".to_string())),
+            screensaver_after: Some(Duration::from_secs(60)),
+            screensaver_step: Duration::from_secs(1),
+            ..IdleConfig::default()
+        };
+        let mut idle = IdleManager::new(config);
+        let start = Instant::now();
+        idle.poll(&mut screen, start).unwrap();
+        assert_eq!(idle.poll(&mut screen, at(start, 60)).unwrap(), IdleState::Screensaver);
+        assert_eq!(screen.transport().row_text(0), " ".repeat(16));
+        assert!(!screen.transport().cursor_visible());
+        for secs in 61..=63 {
+            idle.poll(&mut screen, at(start, secs)).unwrap();
+        }
+        assert_eq!(screen.transport().row_text(0), "zzz             ");
+
+        idle.wake(&mut screen, at(start, 66)).unwrap();
+        assert_eq!(screen.transport().row_text(0), "Boiler 62C      ");
+        assert_eq!(screen.transport().backlight(), (0, 100, 200));
+        assert!(screen.transport().cursor_visible());
+    }
+
+    #[test]
+    fn test_quiet_hours_turn_off_and_clock_saver() {
+        let quiet = QuietHours { start: (22, 0), end: (7, 0) };
+        assert!(quiet.contains(23 * 3600) && quiet.contains(3600) && !quiet.contains(12 * 3600));
+
+        let config = IdleConfig {
+            screensaver: Some(Screensaver::Clock),
+            screensaver_after: Some(Duration::from_secs(40)),
+            quiet_hours: Some(quiet),
+            ..IdleConfig::default()
+        };
+        let start = Instant::now();
+        let mut screen = screen();
+        let mut night = IdleManager::new(config.clone()).with_clock(|| 23 * 3600);
+        night.poll(&mut screen, start).unwrap();
+        assert_eq!(night.poll(&mut screen, at(start, 30)).unwrap(), IdleState::Off);
+        assert_eq!(screen.transport().row_text(0), "Boiler 62C      ");
+
+        let mut screen = self::screen();
+        let mut day = IdleManager::new(config).with_clock(|| 9 * 3600 + 5 * 60);
+        day.poll(&mut screen, start).unwrap();
+        assert_eq!(day.poll(&mut screen, at(start, 40)).unwrap(), IdleState::Screensaver);
+        assert_eq!(screen.transport().row_text(0), "09:05           ");
+        day.poll(&mut screen, at(start, 42)).unwrap();
+        assert_eq!(screen.transport().row_text(1), "   09:05        ");
+    }
+}
@@ -0,0 +1,506 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "png")]
+use std::fs;
+#[cfg(feature = "png")]
+use std::io::{self, Write};
+#[cfg(feature = "png")]
+use std::path::{Path, PathBuf};
+
+use crate::font::rom_glyph;
+use crate::Emulator;
+
+/// Dots per character cell
+const CELL_WIDTH: u32 = 5;
+const CELL_HEIGHT: u32 = 8;
+
+/// Base brightness of an unlit panel
+const PANEL_FLOOR: u32 = 20;
+
+/// Layout of a rendered panel image
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct RenderOptions {
+    /// Size of one LCD dot in image pixels
+    pub scale: u32,
+    /// Space between character cells in image pixels
+    pub gap: u32,
+    /// Margin around the character area in image pixels
+    pub border: u32,
+    /// Draw the blinking block cursor in its visible phase
+    pub blink_phase: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            scale: 4,
+            gap: 2,
+            border: 8,
+            blink_phase: true,
+        }
+    }
+}
+
+/// The rendered image would not fit in memory addressable by `PanelImage`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageTooLarge;
+
+impl fmt::Display for ImageTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rendered image too large; reduce scale, gap or border")
+    }
+}
+
+impl Error for ImageTooLarge {}
+
+/// RGB snapshot of the emulated panel
+///
+/// Produced by `Emulator::render_image`. With the `png` feature it can be
+/// saved as a PNG and compared against golden images in tests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PanelImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl PanelImage {
+    /// Creates an image filled with one color
+    pub fn new(width: u32, height: u32, color: (u8, u8, u8)) -> PanelImage {
+        let pixels = [color.0, color.1, color.2].repeat(width as usize * height as usize);
+        PanelImage { width, height, pixels }
+    }
+
+    /// Width in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Raw RGB bytes, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Color of a pixel, or `None` if it is out of range
+    pub fn pixel(&self, x: u32, y: u32) -> Option<(u8, u8, u8)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 3;
+        Some((self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]))
+    }
+
+    fn fill(&mut self, x: u32, y: u32, size: u32, color: (u8, u8, u8)) {
+        for py in y..(y + size).min(self.height) {
+            for px in x..(x + size).min(self.width) {
+                let i = (py as usize * self.width as usize + px as usize) * 3;
+                self.pixels[i..i + 3].copy_from_slice(&[color.0, color.1, color.2]);
+            }
+        }
+    }
+}
+
+/// Background, unlit dot and lit dot colors for a backlight and contrast
+///
+/// On the SerLCD a lower contrast value drives the liquid crystal harder:
+/// lit dots get darker and unlit dots start to show faintly.
+fn panel_colors(backlight: (u8, u8, u8), contrast: u8) -> [(u8, u8, u8); 3] {
+    let strength = 255 - contrast as u32;
+    let tint = |c: u8| PANEL_FLOOR + c as u32 * (255 - PANEL_FLOOR) / 255;
+    let shade = |c: u8, amount: u32| (tint(c) * (255 - strength * amount / 255) / 255) as u8;
+    let (r, g, b) = backlight;
+    [
+        (shade(r, 0), shade(g, 0), shade(b, 0)),
+        (shade(r, 24), shade(g, 24), shade(b, 24)),
+        (shade(r, 230), shade(g, 230), shade(b, 230)),
+    ]
+}
+
+impl Emulator {
+    /// Rows of the 5x8 bitmap shown at a visible cell, cursor included
+    pub fn cell_bitmap(&self, row: usize, col: usize, blink_phase: bool) -> [u8; 8] {
+        if !self.display_on() {
+            return [0; 8];
+        }
+        let code = self.cell(row, col).unwrap_or(b' ');
+        // CGRAM codes 0x08-0x0F mirror 0x00-0x07
+        let mut bitmap = rom_glyph(code).unwrap_or_else(|| self.glyph(code as usize));
+        if self.cursor() == (row, col) {
+            if self.blink() && blink_phase {
+                bitmap = [0x1F; 8];
+            } else if self.cursor_visible() {
+                bitmap[7] = 0x1F;
+            }
+        }
+        bitmap
+    }
+
+    /// Rasterizes the panel with the ROM font, custom glyphs, backlight
+    /// tint and contrast
+    ///
+    /// Fails if `scale`, `gap` and `border` make the image larger than
+    /// `u32::MAX` pixels in either direction or in total.
+    pub fn render_image(&self, options: &RenderOptions) -> Result<PanelImage, ImageTooLarge> {
+        let scale = options.scale.max(1);
+        let rows = self.rows() as u32;
+        let columns = self.columns() as u32;
+        let cell = |dots: u32| dots.checked_mul(scale)?.checked_add(options.gap);
+        let side = |cells: u32, cell: u32| {
+            cells.checked_mul(cell)?.saturating_sub(options.gap).checked_add(options.border.checked_mul(2)?)
+        };
+        let (cell_width, cell_height) = cell(CELL_WIDTH).zip(cell(CELL_HEIGHT)).ok_or(ImageTooLarge)?;
+        let (width, height) = side(columns, cell_width).zip(side(rows, cell_height)).ok_or(ImageTooLarge)?;
+        (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(3))
+            .ok_or(ImageTooLarge)?;
+
+        let [background, unlit, lit] = panel_colors(self.backlight(), self.contrast());
+        let mut image = PanelImage::new(width, height, background);
+        for row in 0..rows {
+            for col in 0..columns {
+                let bitmap = self.cell_bitmap(row as usize, col as usize, options.blink_phase);
+                let x0 = options.border + col * cell_width;
+                let y0 = options.border + row * cell_height;
+                for (dy, bits) in bitmap.iter().enumerate() {
+                    for dx in 0..CELL_WIDTH {
+                        let on = bits & (0x10 >> dx) != 0;
+                        let x = x0 + dx * scale;
+                        let y = y0 + dy as u32 * scale;
+                        image.fill(x, y, scale, if on { lit } else { unlit });
+                    }
+                }
+            }
+        }
+        Ok(image)
+    }
+}
+
+/// Environment variable that makes `check_golden` rewrite golden files
+#[cfg(feature = "png")]
+pub const UPDATE_GOLDEN_ENV: &str = "QWIIC_LCD_UPDATE_GOLDEN";
+
+/// Why an image did not match its golden file
+#[cfg(feature = "png")]
+#[derive(Debug)]
+pub enum GoldenError {
+    /// The golden file could not be read, decoded or written
+    Io(io::Error),
+    /// The golden file doesn't exist and `UPDATE_GOLDEN_ENV` isn't set
+    Missing(PathBuf),
+    /// The images have different dimensions
+    SizeMismatch { expected: (u32, u32), actual: (u32, u32) },
+    /// Some pixels differ; the rendered image was saved next to the golden
+    PixelMismatch {
+        /// Number of differing pixels
+        differing: usize,
+        /// First differing pixel as (x, y)
+        first: (u32, u32),
+        /// Where the rendered image was written for inspection
+        actual_path: PathBuf,
+    },
+}
+
+#[cfg(feature = "png")]
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GoldenError::Io(e) => write!(f, "golden image I/O error: {}", e),
+            GoldenError::Missing(path) => write!(
+                f,
+                "golden image {} does not exist; set {} to create it",
+                path.display(),
+                UPDATE_GOLDEN_ENV
+            ),
+            GoldenError::SizeMismatch { expected, actual } => write!(
+                f,
+                "image is {}x{} but the golden image is {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            GoldenError::PixelMismatch { differing, first, actual_path } => write!(
+                f,
+                "{} pixel(s) differ from the golden image, first at ({}, {}); rendered image saved to {}",
+                differing,
+                first.0,
+                first.1,
+                actual_path.display()
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "png")]
+impl Error for GoldenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GoldenError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "png")]
+impl From<io::Error> for GoldenError {
+    fn from(error: io::Error) -> Self {
+        GoldenError::Io(error)
+    }
+}
+
+#[cfg(feature = "png")]
+fn invalid_data<E: Into<Box<dyn Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(feature = "png")]
+impl PanelImage {
+    /// Encodes the image as an 8-bit RGB PNG
+    pub fn write_png<W: Write>(&self, out: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(out, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(invalid_data)?;
+        writer.write_image_data(&self.pixels).map_err(invalid_data)?;
+        writer.finish().map_err(invalid_data)
+    }
+
+    /// Encodes the image as PNG bytes
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_png(&mut bytes).expect("encoding to memory cannot fail");
+        bytes
+    }
+
+    /// Writes the image to a PNG file
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_png())
+    }
+
+    /// Decodes an 8-bit RGB or RGBA PNG; alpha is dropped
+    pub fn from_png(bytes: &[u8]) -> io::Result<PanelImage> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(invalid_data)?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(invalid_data)?;
+        let channels = match info.color_type {
+            png::ColorType::Rgb => 3,
+            png::ColorType::Rgba => 4,
+            other => return Err(invalid_data(format!("unsupported PNG color type {:?}", other))),
+        };
+        let pixels = buffer[..info.buffer_size()]
+            .chunks(channels)
+            .flat_map(|p| p[..3].iter().copied())
+            .collect();
+        Ok(PanelImage { width: info.width, height: info.height, pixels })
+    }
+
+    /// Reads a PNG file
+    pub fn load_png<P: AsRef<Path>>(path: P) -> io::Result<PanelImage> {
+        PanelImage::from_png(&fs::read(path)?)
+    }
+
+    /// Compares the image against a golden PNG file
+    ///
+    /// A missing golden file is an error. Set the `QWIIC_LCD_UPDATE_GOLDEN`
+    /// environment variable to create golden files, or to overwrite them
+    /// after an intended rendering change. On a pixel mismatch the
+    /// rendered image is written next to the golden one with an
+    /// `.actual.png` extension.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use qwiic_lcd_rs::*;
+    /// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+    /// screen.print("Hello").unwrap();
+    /// let image = screen.transport().render_image(&RenderOptions::default()).unwrap();
+    /// image.check_golden("tests/golden/hello.png").unwrap();
+    /// ```
+    pub fn check_golden<P: AsRef<Path>>(&self, path: P) -> Result<(), GoldenError> {
+        let path = path.as_ref();
+        if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            self.save_png(path)?;
+            return Ok(());
+        }
+        if !path.exists() {
+            return Err(GoldenError::Missing(path.to_path_buf()));
+        }
+
+        let golden = PanelImage::load_png(path)?;
+        if (golden.width, golden.height) != (self.width, self.height) {
+            return Err(GoldenError::SizeMismatch {
+                expected: (golden.width, golden.height),
+                actual: (self.width, self.height),
+            });
+        }
+        let mut differing = golden
+            .pixels
+            .chunks(3)
+            .zip(self.pixels.chunks(3))
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .map(|(i, _)| i);
+        let Some(first) = differing.next() else {
+            return Ok(());
+        };
+        let actual_path = path.with_extension("actual.png");
+        self.save_png(&actual_path)?;
+        Err(GoldenError::PixelMismatch {
+            differing: differing.count() + 1,
+            first: (first as u32 % self.width, first as u32 / self.width),
+            actual_path,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Screen, ScreenConfig};
+
+    fn options() -> RenderOptions {
+        RenderOptions { scale: 2, gap: 1, border: 3, blink_phase: true }
+    }
+
+    fn screen() -> Screen<Emulator> {
+        Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16))
+    }
+
+    /// Color of the top-left image pixel of dot (dx, dy) in a cell
+    fn dot(image: &PanelImage, row: u32, col: u32, dx: u32, dy: u32) -> (u8, u8, u8) {
+        let o = options();
+        let x = o.border + col * (5 * o.scale + o.gap) + dx * o.scale;
+        let y = o.border + row * (8 * o.scale + o.gap) + dy * o.scale;
+        image.pixel(x, y).unwrap()
+    }
+
+    #[test]
+    fn test_image_dimensions() {
+        let image = Emulator::new(2, 16).render_image(&options()).unwrap();
+        assert_eq!(image.width(), 2 * 3 + 16 * 11 - 1);
+        assert_eq!(image.height(), 2 * 3 + 2 * 17 - 1);
+        assert_eq!(image.pixels().len(), (image.width() * image.height() * 3) as usize);
+        assert_eq!(image.pixel(image.width(), 0), None);
+
+        let huge = RenderOptions { scale: u32::MAX / 4, ..options() };
+        assert_eq!(Emulator::new(2, 16).render_image(&huge), Err(ImageTooLarge));
+        let huge = RenderOptions { gap: u32::MAX / 8, ..options() };
+        assert_eq!(Emulator::new(2, 16).render_image(&huge), Err(ImageTooLarge));
+        let huge = RenderOptions { border: u32::MAX / 2 + 1, ..options() };
+        assert_eq!(Emulator::new(2, 16).render_image(&huge), Err(ImageTooLarge));
+    }
+
+    #[test]
+    fn test_rom_and_custom_glyphs() {
+        let mut screen = screen();
+        let heart = [0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00];
+        screen.create_character(1, heart).unwrap();
+        screen.print("A").unwrap();
+        screen.write_byte(1).unwrap();
+
+        let lcd = screen.transport();
+        let image = lcd.render_image(&options()).unwrap();
+        let [background, unlit, lit] = panel_colors(lcd.backlight(), lcd.contrast());
+        assert_eq!(image.pixel(0, 0), Some(background));
+        // 'A' has a flat top spanning the three middle dots
+        assert_eq!(dot(&image, 0, 0, 0, 0), unlit);
+        assert_eq!(dot(&image, 0, 0, 2, 0), lit);
+        assert_eq!(dot(&image, 0, 0, 0, 4), lit);
+        assert_eq!(dot(&image, 0, 1, 1, 1), lit);
+        assert_eq!(dot(&image, 0, 1, 0, 1), unlit);
+        assert_eq!(lcd.cell_bitmap(0, 1, true), heart);
+    }
+
+    #[test]
+    fn test_backlight_contrast_and_cursor() {
+        let mut screen = screen();
+        screen.change_backlight(0, 0, 255).unwrap();
+        screen.print(" A").unwrap();
+        screen.home().unwrap();
+        let image = screen.transport().render_image(&options()).unwrap();
+        let (r, g, b) = image.pixel(0, 0).unwrap();
+        assert!(b > 200 && r < 40 && g < 40);
+        assert_ne!(dot(&image, 0, 1, 2, 0), dot(&image, 0, 1, 0, 0));
+
+        // Maximum contrast value washes the text out entirely
+        screen.set_contrast(255).unwrap();
+        let image = screen.transport().render_image(&options()).unwrap();
+        assert_eq!(dot(&image, 0, 1, 2, 0), dot(&image, 0, 1, 0, 0));
+
+        screen.enable_cursor(true).unwrap();
+        let lcd = screen.transport();
+        assert_eq!(lcd.cell_bitmap(0, 0, true)[7], 0x1F);
+        screen.enable_blink(true).unwrap();
+        let lcd = screen.transport();
+        assert_eq!(lcd.cell_bitmap(0, 0, true), [0x1F; 8]);
+        assert_eq!(lcd.cell_bitmap(0, 0, false)[..7], [0; 7]);
+
+        screen.enable_display(false).unwrap();
+        assert_eq!(screen.transport().cell_bitmap(0, 0, true), [0; 8]);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn test_png_round_trip_and_golden() {
+        let mut screen = screen();
+        screen.print("Golden").unwrap();
+        let image = screen.transport().render_image(&options()).unwrap();
+        assert_eq!(PanelImage::from_png(&image.to_png()).unwrap(), image);
+
+        let dir = std::env::temp_dir().join(format!("qwiic-lcd-golden-{}", std::process::id()));
+        let golden = dir.join("golden.png");
+        // Golden files are only created on request
+        assert!(matches!(image.check_golden(&golden), Err(GoldenError::Missing(_))));
+        assert!(!golden.exists());
+        fs::create_dir_all(&dir).unwrap();
+        image.save_png(&golden).unwrap();
+        image.check_golden(&golden).unwrap();
+
+        screen.print("!").unwrap();
+        let changed = screen.transport().render_image(&options()).unwrap();
+        match changed.check_golden(&golden) {
+            Err(GoldenError::PixelMismatch { differing, actual_path, .. }) => {
+                assert!(differing > 0);
+                assert_eq!(PanelImage::load_png(actual_path).unwrap(), changed);
+            }
+            other => panic!("expected a pixel mismatch, got {:?}", other),
+        }
+
+        let bigger = RenderOptions { scale: 3, ..options() };
+        let resized = screen.transport().render_image(&bigger).unwrap();
+        assert!(matches!(resized.check_golden(&golden), Err(GoldenError::SizeMismatch { .. })));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_options_round_trip() {
+        let json = serde_json::to_string(&options()).unwrap();
+        assert_eq!(serde_json::from_str::<RenderOptions>(&json).unwrap(), options());
+        // Settings left out take their defaults
+        let partial: RenderOptions = serde_json::from_str(r#"{"scale":6}"#).unwrap();
+        assert_eq!(partial, RenderOptions { scale: 6, ..RenderOptions::default() });
+    }
+}
@@ -0,0 +1,686 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::template::{field_spec, Field};
+use crate::{FieldValue, FrameBuffer, QwiicLcdError, Screen, ScreenConfig, Transport};
+
+/// 1-based line and column in a layout file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    /// Position of byte `offset` in `source`
+    #[cfg(feature = "toml")]
+    fn at_offset(source: &str, offset: usize) -> Position {
+        let before = &source[..offset.min(source.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+        Position { line, column }
+    }
+}
+
+/// Error loading a `Layout`
+#[derive(Debug)]
+pub enum LayoutError {
+    /// The layout file couldn't be read
+    Io { file: PathBuf, error: io::Error },
+    /// The file extension isn't a format enabled in this build
+    UnsupportedFormat(PathBuf),
+    /// The file isn't valid TOML/YAML or doesn't match the layout schema
+    Syntax { file: Option<PathBuf>, at: Option<Position>, message: String },
+    /// The layout parsed but doesn't fit the panel or refers to unknown names
+    Invalid { file: Option<PathBuf>, at: Option<Position>, message: String },
+}
+
+impl LayoutError {
+    /// Where in the file the error is, when known
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            LayoutError::Syntax { at, .. } | LayoutError::Invalid { at, .. } => *at,
+            _ => None,
+        }
+    }
+
+    fn in_file(self, path: &Path) -> LayoutError {
+        match self {
+            LayoutError::Syntax { at, message, .. } => LayoutError::Syntax { file: Some(path.to_path_buf()), at, message },
+            LayoutError::Invalid { at, message, .. } => LayoutError::Invalid { file: Some(path.to_path_buf()), at, message },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayoutError::Io { file, error } => write!(f, "Cannot read layout {}: {}", file.display(), error),
+            LayoutError::UnsupportedFormat(file) => write!(f, "Unsupported layout format: {}", file.display()),
+            LayoutError::Syntax { file, at, message } | LayoutError::Invalid { file, at, message } => {
+                match file {
+                    Some(file) => write!(f, "{}:", file.display())?,
+                    None => write!(f, "layout:")?,
+                }
+                if let Some(at) = at {
+                    write!(f, "{}:{}:", at.line, at.column)?;
+                }
+                write!(f, " {}", message)
+            }
+        }
+    }
+}
+
+impl Error for LayoutError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LayoutError::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Panel size and the theme shown at start
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Panel {
+    pub rows: u8,
+    pub columns: u8,
+    #[serde(default)]
+    pub theme: Option<String>,
+}
+
+/// Named backlight color and contrast
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    #[serde(default)]
+    pub backlight: Option<[u8; 3]>,
+    #[serde(default)]
+    pub contrast: Option<u8>,
+}
+
+/// One element of a layout, placed at a 0-based row and column
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Region {
+    /// Static text
+    Label { row: u8, col: u8, text: String },
+    /// Value bound to `name`, formatted with a `Template` spec such as `>5.1`
+    Field {
+        row: u8,
+        col: u8,
+        width: u8,
+        name: String,
+        #[serde(default)]
+        format: String,
+    },
+    /// Horizontal bar filled in proportion to the value bound to `name`
+    Bar {
+        row: u8,
+        col: u8,
+        width: u8,
+        name: String,
+        #[serde(default)]
+        min: f64,
+        #[serde(default = "default_bar_max")]
+        max: f64,
+        /// Custom glyph for filled cells instead of the ROM block
+        #[serde(default)]
+        glyph: Option<String>,
+    },
+    /// A single custom glyph
+    Icon { row: u8, col: u8, glyph: String },
+}
+
+fn default_bar_max() -> f64 {
+    100.0
+}
+
+impl Region {
+    /// (row, col, width) covered by the region
+    pub fn extent(&self) -> (u8, u8, usize) {
+        match self {
+            Region::Label { row, col, text } => (*row, *col, text.chars().count()),
+            Region::Field { row, col, width, .. } | Region::Bar { row, col, width, .. } => (*row, *col, *width as usize),
+            Region::Icon { row, col, .. } => (*row, *col, 1),
+        }
+    }
+
+    /// Name of the value the region shows, if any
+    pub fn binding(&self) -> Option<&str> {
+        match self {
+            Region::Field { name, .. } | Region::Bar { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// Screen layout described in a TOML or YAML file
+///
+/// A layout has a `panel` size, optional `glyphs` (name to 8 rows of
+/// 5-bit pixels, loaded into slots 0-7 in name order), `themes` (backlight
+/// color and contrast) and a list of `regions`: static labels, fields and
+/// bars bound to named values, and glyph icons.
+///
+/// ```toml
+/// [panel]
+/// rows = 2
+/// columns = 16
+/// theme = "day"
+///
+/// [themes.day]
+/// backlight = [255, 255, 255]
+///
+/// [[regions]]
+/// type = "label"
+/// row = 0
+/// col = 0
+/// text = "CPU"
+///
+/// [[regions]]
+/// type = "field"
+/// row = 0
+/// col = 4
+/// width = 4
+/// name = "cpu"
+/// format = ">3"
+/// ```
+///
+/// Loading checks every region against the panel and reports the line
+/// and column of the first one that doesn't fit.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Layout {
+    panel: Panel,
+    #[serde(default)]
+    glyphs: BTreeMap<String, [u8; 8]>,
+    #[serde(default)]
+    themes: BTreeMap<String, Theme>,
+    #[serde(default)]
+    regions: Vec<Region>,
+}
+
+/// Start of each part of a layout file, for pointing errors at the source
+#[derive(Default)]
+struct Positions {
+    sections: BTreeMap<String, Position>,
+    regions: Vec<Position>,
+}
+
+impl Layout {
+    /// Loads a layout, picking the format from the `.toml`, `.yaml` or `.yml` extension
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Layout, LayoutError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|error| LayoutError::Io { file: path.to_path_buf(), error })?;
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let layout = match extension {
+            #[cfg(feature = "toml")]
+            "toml" => Layout::from_toml_str(&source),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Layout::from_yaml_str(&source),
+            _ => return Err(LayoutError::UnsupportedFormat(path.to_path_buf())),
+        };
+        layout.map_err(|e| e.in_file(path))
+    }
+
+    /// Parses and validates a TOML layout
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(source: &str) -> Result<Layout, LayoutError> {
+        use serde::de::IgnoredAny;
+        use toml::Spanned;
+
+        #[derive(Deserialize)]
+        struct Spans {
+            panel: Option<Spanned<IgnoredAny>>,
+            glyphs: Option<Spanned<IgnoredAny>>,
+            #[serde(default)]
+            regions: Vec<Spanned<IgnoredAny>>,
+        }
+
+        let layout: Layout = toml::from_str(source).map_err(|e| LayoutError::Syntax {
+            file: None,
+            at: e.span().map(|s| Position::at_offset(source, s.start)),
+            message: e.message().to_string(),
+        })?;
+        let spans: Spans = toml::from_str(source).map_err(|e| LayoutError::Syntax {
+            file: None,
+            at: None,
+            message: e.message().to_string(),
+        })?;
+        let at = |span: &Spanned<IgnoredAny>| Position::at_offset(source, span.span().start);
+        let mut positions = Positions { regions: spans.regions.iter().map(at).collect(), ..Default::default() };
+        for (name, span) in [("panel", &spans.panel), ("glyphs", &spans.glyphs)] {
+            if let Some(span) = span {
+                positions.sections.insert(name.to_string(), at(span));
+            }
+        }
+        layout.validate(&positions)?;
+        Ok(layout)
+    }
+
+    /// Parses and validates a YAML layout
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml_str(source: &str) -> Result<Layout, LayoutError> {
+        use serde::de::IgnoredAny;
+        use serde_saphyr::Spanned;
+
+        #[derive(Deserialize)]
+        struct Spans {
+            panel: Option<Spanned<IgnoredAny>>,
+            glyphs: Option<Spanned<IgnoredAny>>,
+            #[serde(default)]
+            regions: Vec<Spanned<IgnoredAny>>,
+        }
+
+        let syntax = |e: serde_saphyr::Error| {
+            let at = e.location().map(|l| Position { line: l.line() as usize, column: l.column() as usize });
+            // The position is reported separately, so keep it out of the message
+            let message = match e.without_snippet() {
+                serde_saphyr::Error::Message { msg, .. } => msg.clone(),
+                other => {
+                    let message = other.to_string();
+                    match message.rfind(" at line ") {
+                        Some(i) => message[..i].to_string(),
+                        None => message,
+                    }
+                }
+            };
+            LayoutError::Syntax { file: None, at, message }
+        };
+        let layout: Layout = serde_saphyr::from_str(source).map_err(syntax)?;
+        let spans: Spans = serde_saphyr::from_str(source).map_err(syntax)?;
+        let at = |span: &Spanned<IgnoredAny>| Position {
+            line: span.referenced.line() as usize,
+            column: span.referenced.column() as usize,
+        };
+        let mut positions = Positions { regions: spans.regions.iter().map(at).collect(), ..Default::default() };
+        for (name, span) in [("panel", &spans.panel), ("glyphs", &spans.glyphs)] {
+            if let Some(span) = span {
+                positions.sections.insert(name.to_string(), at(span));
+            }
+        }
+        layout.validate(&positions)?;
+        Ok(layout)
+    }
+
+    /// Panel size and start theme
+    pub fn panel(&self) -> &Panel {
+        &self.panel
+    }
+
+    /// Custom glyphs by name
+    pub fn glyphs(&self) -> &BTreeMap<String, [u8; 8]> {
+        &self.glyphs
+    }
+
+    /// Themes by name
+    pub fn themes(&self) -> &BTreeMap<String, Theme> {
+        &self.themes
+    }
+
+    /// Regions in file order
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Screen configuration for the layout's panel size
+    pub fn screen_config(&self) -> ScreenConfig {
+        ScreenConfig::new(self.panel.rows, self.panel.columns)
+    }
+
+    /// Slot (0-7) the named glyph is loaded into
+    fn glyph_slot(&self, name: &str) -> Option<u8> {
+        self.glyphs.keys().position(|k| k == name).map(|i| i as u8)
+    }
+
+    fn validate(&self, positions: &Positions) -> Result<(), LayoutError> {
+        let invalid = |at: Option<&Position>, message: String| LayoutError::Invalid { file: None, at: at.copied(), message };
+        let panel_at = positions.sections.get("panel");
+        let Panel { rows: max_rows, columns: max_columns, .. } = self.panel;
+
+        if !(1..=4).contains(&max_rows) || !(1..=20).contains(&max_columns) {
+            return Err(invalid(panel_at, format!("Panel size {}x{} is outside 1x1 to 4x20", max_rows, max_columns)));
+        }
+        if let Some(theme) = self.panel.theme.as_ref().filter(|t| !self.themes.contains_key(*t)) {
+            return Err(invalid(panel_at, format!("Unknown theme '{}'", theme)));
+        }
+        if self.glyphs.len() > 8 {
+            let at = positions.sections.get("glyphs");
+            return Err(invalid(at, format!("{} glyphs defined but the panel only has 8 slots", self.glyphs.len())));
+        }
+
+        for (i, region) in self.regions.iter().enumerate() {
+            let at = positions.regions.get(i);
+            let (row, col, width) = region.extent();
+            if row >= max_rows {
+                return Err(invalid(at, format!("Region row {} exceeds max_rows {}", row, max_rows)));
+            }
+            if col as usize + width > max_columns as usize {
+                return Err(invalid(
+                    at,
+                    format!("Region at column {} with width {} exceeds max_columns {}", col, width, max_columns),
+                ));
+            }
+            match region {
+                Region::Field { format, width, .. } if field_spec(format, *width as usize).is_none() => {
+                    return Err(invalid(at, format!("Invalid format spec '{}'", format)));
+                }
+                Region::Bar { min, max, .. } if min >= max => {
+                    return Err(invalid(at, format!("Bar min {} is not below max {}", min, max)));
+                }
+                Region::Bar { glyph: Some(glyph), .. } | Region::Icon { glyph, .. } if self.glyph_slot(glyph).is_none() => {
+                    return Err(invalid(at, format!("Unknown glyph '{}'", glyph)));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Draws a `Layout` and keeps it up to date as bound values change
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// # #[cfg(feature = "toml")] {
+/// let layout = Layout::from_toml_str(r#"
+///     panel = { rows = 2, columns = 16 }
+///     regions = [
+///         { type = "label", row = 0, col = 0, text = "Disk" },
+///         { type = "field", row = 0, col = 5, width = 4, name = "disk", format = ">3" },
+///         { type = "bar", row = 1, col = 0, width = 16, name = "disk" },
+///     ]
+/// "#).unwrap();
+/// let mut screen = Screen::with_transport(layout.screen_config(), Emulator::new(2, 16));
+/// let mut renderer = LayoutRenderer::new(layout);
+/// renderer.set("disk", 50);
+/// renderer.render(&mut screen).unwrap();
+/// assert_eq!(screen.transport().row_text(0), "Disk   50       ");
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct LayoutRenderer {
+    layout: Layout,
+    formats: Vec<Option<Field>>,
+    values: BTreeMap<String, FieldValue>,
+    frame: FrameBuffer,
+    theme: Option<String>,
+    shown_theme: Option<String>,
+}
+
+impl LayoutRenderer {
+    /// Creates a renderer with labels drawn and bound regions blank
+    pub fn new(layout: Layout) -> LayoutRenderer {
+        let formats = layout
+            .regions
+            .iter()
+            .map(|region| match region {
+                Region::Field { format, width, .. } => field_spec(format, *width as usize),
+                _ => None,
+            })
+            .collect();
+        let mut renderer = LayoutRenderer {
+            frame: FrameBuffer::new(layout.panel.rows, layout.panel.columns),
+            theme: layout.panel.theme.clone(),
+            shown_theme: None,
+            layout,
+            formats,
+            values: BTreeMap::new(),
+        };
+        for i in 0..renderer.layout.regions.len() {
+            renderer.draw(i);
+        }
+        renderer
+    }
+
+    /// The layout being drawn
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Binds `value` to `name`; returns false if no region shows it
+    pub fn set<V: Into<FieldValue>>(&mut self, name: &str, value: V) -> bool {
+        let value = value.into();
+        if self.values.get(name) == Some(&value) {
+            return self.layout.regions.iter().any(|r| r.binding() == Some(name));
+        }
+        self.values.insert(name.to_string(), value);
+        let mut bound = false;
+        for i in 0..self.layout.regions.len() {
+            if self.layout.regions[i].binding() == Some(name) {
+                self.draw(i);
+                bound = true;
+            }
+        }
+        bound
+    }
+
+    /// Current value bound to `name`
+    pub fn get(&self, name: &str) -> Option<&FieldValue> {
+        self.values.get(name)
+    }
+
+    /// Switches theme on the next `render`; returns false for an unknown theme
+    pub fn set_theme(&mut self, name: &str) -> bool {
+        if !self.layout.themes.contains_key(name) {
+            return false;
+        }
+        self.theme = Some(name.to_string());
+        true
+    }
+
+    /// Current theme
+    pub fn theme(&self) -> Option<&str> {
+        self.theme.as_deref()
+    }
+
+    /// The drawn layout
+    pub fn framebuffer(&self) -> &FrameBuffer {
+        &self.frame
+    }
+
+    /// Shows the layout on `screen`
+    ///
+    /// Glyphs the panel doesn't have yet are loaded, the theme is applied
+    /// when it changed, and only the cells that changed are sent.
+    pub fn render<T: Transport>(&mut self, screen: &mut Screen<T>) -> Result<(), QwiicLcdError> {
+        for (slot, data) in self.layout.glyphs.values().enumerate() {
+            if screen.glyphs[slot] != Some(*data) {
+                screen.create_character(slot as u8, *data)?;
+            }
+        }
+        if self.shown_theme != self.theme {
+            if let Some(theme) = self.theme.as_ref().and_then(|t| self.layout.themes.get(t)) {
+                if let Some([r, g, b]) = theme.backlight {
+                    screen.change_backlight(r, g, b)?;
+                }
+                if let Some(contrast) = theme.contrast {
+                    screen.set_contrast(contrast)?;
+                }
+            }
+            self.shown_theme = self.theme.clone();
+        }
+        screen.present(&self.frame)
+    }
+
+    fn draw(&mut self, index: usize) {
+        let region = &self.layout.regions[index];
+        let (row, col, width) = region.extent();
+        let (row, col) = (row as usize, col as usize);
+        let cells: Vec<u8> = match region {
+            Region::Label { text, .. } => text.chars().map(|c| u8::try_from(c).unwrap_or(b'?')).collect(),
+            Region::Field { name, .. } => match &self.formats[index] {
+                Some(field) => field.format(self.values.get(name)).chars().map(|c| u8::try_from(c).unwrap_or(b'?')).collect(),
+                None => vec![b' '; width],
+            },
+            Region::Bar { name, min, max, glyph, .. } => {
+                let value = match self.values.get(name) {
+                    Some(FieldValue::Int(v)) => *v as f64,
+                    Some(FieldValue::Float(v)) => *v,
+                    Some(FieldValue::Text(s)) => s.trim().parse().unwrap_or(*min),
+                    None => *min,
+                };
+                let fraction = ((value - min) / (max - min)).clamp(0.0, 1.0);
+                let filled = (fraction * width as f64).round() as usize;
+                let block = glyph.as_deref().and_then(|g| self.layout.glyph_slot(g)).unwrap_or(0xFF);
+                (0..width).map(|i| if i < filled { block } else { b' ' }).collect()
+            }
+            Region::Icon { glyph, .. } => vec![self.layout.glyph_slot(glyph).unwrap_or(b'?')],
+        };
+        for (i, byte) in cells.into_iter().enumerate() {
+            self.frame.set(row, col + i, byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "toml")]
+    use crate::Emulator;
+
+    #[cfg(feature = "toml")]
+    const TOML: &str = r#"
+[panel]
+rows = 2
+columns = 16
+theme = "day"
+
+[glyphs]
+heart = [0, 10, 31, 31, 14, 4, 0, 0]
+
+[themes.day]
+backlight = [255, 255, 255]
+
+[themes.night]
+backlight = [64, 0, 0]
+contrast = 10
+
+[[regions]]
+type = "icon"
+row = 0
+col = 0
+glyph = "heart"
+
+[[regions]]
+type = "label"
+row = 0
+col = 2
+text = "Load"
+
+[[regions]]
+type = "field"
+row = 0
+col = 7
+width = 5
+name = "load"
+format = ">.1"
+
+[[regions]]
+type = "bar"
+row = 1
+col = 0
+width = 10
+name = "load"
+max = 4.0
+"#;
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_layout_renders_fields_bars_and_glyphs() {
+        let layout = Layout::from_toml_str(TOML).unwrap();
+        assert_eq!(layout.regions().len(), 4);
+        let mut screen = Screen::with_transport(layout.screen_config(), Emulator::new(2, 16));
+        let mut renderer = LayoutRenderer::new(layout);
+        assert!(renderer.set("load", 2.0));
+        assert!(!renderer.set("unbound", 1));
+        renderer.render(&mut screen).unwrap();
+
+        assert_eq!(screen.transport().row_text(0), "? Load   2.0    ");
+        assert_eq!(screen.framebuffer().get(0, 0), Some(0));
+        assert_eq!(screen.transport().glyph(0), [0, 10, 31, 31, 14, 4, 0, 0]);
+        assert_eq!(screen.framebuffer().row(1), b"\xFF\xFF\xFF\xFF\xFF           ");
+        assert_eq!(screen.transport().backlight(), (255, 255, 255));
+
+        // Glyphs aren't reloaded and an unchanged theme isn't resent
+        let before = screen.stats().total().transactions;
+        renderer.render(&mut screen).unwrap();
+        assert_eq!(screen.stats().total().transactions, before);
+
+        assert!(renderer.set_theme("night"));
+        assert!(!renderer.set_theme("dusk"));
+        renderer.render(&mut screen).unwrap();
+        assert_eq!(screen.transport().backlight(), (64, 0, 0));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_errors_point_at_the_region() {
+        let source = TOML.replace("width = 10", "width = 17");
+        let err = Layout::from_toml_str(&source).unwrap_err();
+        assert!(matches!(err, LayoutError::Invalid { .. }), "{}", err);
+        let at = err.position().unwrap();
+        assert_eq!(source.lines().nth(at.line - 1), Some("[[regions]]"));
+        assert_eq!(source.lines().skip(at.line).position(|l| l.contains("width = 17")), Some(3));
+        assert!(err.to_string().contains("exceeds max_columns 16"), "{}", err);
+
+        let err = Layout::from_toml_str("[panel]\nrows = 2\ncolums = 16\n").unwrap_err();
+        assert!(matches!(err, LayoutError::Syntax { .. }), "{}", err);
+        assert_eq!(err.position().map(|p| p.line), Some(3));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml_errors_point_at_the_region() {
+        let source = "\
+panel:
+  rows: 2
+  columns: 16
+regions:
+  - type: label
+    row: 0
+    col: 0
+    text: Ready
+  - {type: label, row: 1, col: 0, text: ok}
+  - type: field
+    row: 2
+    col: 0
+    width: 4
+    name: x
+";
+        let err = Layout::from_yaml_str(source).unwrap_err();
+        assert_eq!(err.position(), Some(Position { line: 10, column: 5 }));
+        assert!(err.to_string().starts_with("layout:10:5: Region row 2 exceeds max_rows 2"), "{}", err);
+
+        let err = Layout::from_yaml_str(&source.replace("row: 2", "row: [2")).unwrap_err();
+        assert!(matches!(err, LayoutError::Syntax { at: Some(_), .. }), "{:?}", err);
+        assert!(!err.to_string().contains(" at line "), "{}", err);
+
+        let layout = Layout::from_yaml_str(&source.replace("row: 2", "row: 1")).unwrap();
+        let renderer = LayoutRenderer::new(layout);
+        assert_eq!(renderer.framebuffer().row_text(0), "Ready           ");
+    }
+}
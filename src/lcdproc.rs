@@ -0,0 +1,1168 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::{FrameBuffer, Screen, Transport};
+
+/// Length of one server tick; LCDproc durations and speeds count these
+pub const TICK: Duration = Duration::from_millis(125);
+
+/// Default time a screen is shown before rotating (4 seconds)
+const DEFAULT_DURATION: u64 = 32;
+
+/// Pixel size of a character cell announced to clients
+const CELL_WIDTH: i32 = 5;
+const CELL_HEIGHT: i32 = 8;
+
+/// Lines queued for a client before it is considered stalled and dropped
+const WRITE_QUEUE: usize = 256;
+
+/// Screen priority classes, lowest first
+///
+/// Only screens of the highest class present are shown; screens sharing
+/// that class rotate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Hidden,
+    Background,
+    Info,
+    Foreground,
+    Alert,
+    Input,
+}
+
+impl Priority {
+    fn parse(value: &str) -> Option<Priority> {
+        let priority = match value {
+            "hidden" => Priority::Hidden,
+            "background" => Priority::Background,
+            "info" => Priority::Info,
+            "foreground" => Priority::Foreground,
+            "alert" => Priority::Alert,
+            "input" => Priority::Input,
+            // Numeric priorities from protocol 0.3 and older
+            number => match number.parse::<u32>().ok()? {
+                0 => Priority::Hidden,
+                1..=64 => Priority::Foreground,
+                65..=192 => Priority::Info,
+                _ => Priority::Background,
+            },
+        };
+        Some(priority)
+    }
+}
+
+/// Backlight requested by a client or screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BacklightMode {
+    On,
+    Off,
+    /// Slow on/off blinking
+    Blink,
+    /// Mostly on with a short dark pulse every second
+    Flash,
+}
+
+impl BacklightMode {
+    /// Parses a mode, resolving `toggle` against the current mode
+    fn parse(value: &str, current: BacklightMode) -> Option<BacklightMode> {
+        Some(match value {
+            "on" => BacklightMode::On,
+            "off" => BacklightMode::Off,
+            "blink" => BacklightMode::Blink,
+            "flash" => BacklightMode::Flash,
+            "toggle" if current == BacklightMode::Off => BacklightMode::On,
+            "toggle" => BacklightMode::Off,
+            _ => return None,
+        })
+    }
+
+    fn is_lit(self, tick: u64) -> bool {
+        match self {
+            BacklightMode::On => true,
+            BacklightMode::Off => false,
+            BacklightMode::Blink => (tick / 4) % 2 == 0,
+            BacklightMode::Flash => tick % 8 != 0,
+        }
+    }
+}
+
+/// Which set of bar glyphs a frame needs in CGRAM
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarStyle {
+    /// Partial cells 1-4 dots wide in glyphs 0-3
+    Horizontal,
+    /// Partial cells 1-7 dots high in glyphs 0-6
+    Vertical,
+}
+
+impl BarStyle {
+    /// Bitmaps to load into CGRAM, starting at glyph 0
+    pub fn glyphs(self) -> Vec<[u8; 8]> {
+        match self {
+            BarStyle::Horizontal => (1..CELL_WIDTH as u8)
+                .map(|width| [0x1F & !(0x1F >> width); 8])
+                .collect(),
+            BarStyle::Vertical => (1..CELL_HEIGHT as usize)
+                .map(|height| {
+                    let mut glyph = [0; 8];
+                    glyph[8 - height..].iter_mut().for_each(|row| *row = 0x1F);
+                    glyph
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What the panel should show after a tick
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LcdProcFrame {
+    /// Characters to display
+    pub cells: FrameBuffer,
+    /// Bar glyphs the cells refer to, if any
+    pub bars: Option<BarStyle>,
+    /// Whether the backlight is on
+    pub backlight: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScrollDirection {
+    Horizontal,
+    Vertical,
+    Marquee,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum WidgetKind {
+    String { x: i32, y: i32, text: String },
+    Title { text: String },
+    HBar { x: i32, y: i32, length: i32 },
+    VBar { x: i32, y: i32, length: i32 },
+    Scroller { left: i32, top: i32, right: i32, bottom: i32, direction: ScrollDirection, speed: i32, text: String },
+    Num { x: i32, digit: i32 },
+}
+
+impl WidgetKind {
+    fn new(kind: &str) -> Option<WidgetKind> {
+        Some(match kind {
+            "string" => WidgetKind::String { x: 1, y: 1, text: String::new() },
+            "title" => WidgetKind::Title { text: String::new() },
+            "hbar" => WidgetKind::HBar { x: 1, y: 1, length: 0 },
+            "vbar" => WidgetKind::VBar { x: 1, y: 1, length: 0 },
+            "scroller" => WidgetKind::Scroller {
+                left: 1,
+                top: 1,
+                right: 1,
+                bottom: 1,
+                direction: ScrollDirection::Horizontal,
+                speed: 1,
+                text: String::new(),
+            },
+            "num" => WidgetKind::Num { x: 1, digit: 0 },
+            _ => return None,
+        })
+    }
+
+    /// Applies the arguments of `widget_set`
+    fn set(&mut self, args: &[String]) -> Result<(), String> {
+        let int = |index: usize| -> Result<i32, String> {
+            let arg = args.get(index).ok_or("Wrong number of arguments")?;
+            arg.parse().map_err(|_| format!("Invalid coordinate or number \"{}\"", arg))
+        };
+        let text = |index: usize| -> Result<String, String> {
+            args.get(index).cloned().ok_or_else(|| "Wrong number of arguments".to_string())
+        };
+        *self = match self {
+            WidgetKind::String { .. } => WidgetKind::String { x: int(0)?, y: int(1)?, text: text(2)? },
+            WidgetKind::Title { .. } => WidgetKind::Title { text: text(0)? },
+            WidgetKind::HBar { .. } => WidgetKind::HBar { x: int(0)?, y: int(1)?, length: int(2)? },
+            WidgetKind::VBar { .. } => WidgetKind::VBar { x: int(0)?, y: int(1)?, length: int(2)? },
+            WidgetKind::Scroller { .. } => {
+                let direction = match text(4)?.as_str() {
+                    "h" => ScrollDirection::Horizontal,
+                    "v" => ScrollDirection::Vertical,
+                    "m" => ScrollDirection::Marquee,
+                    other => return Err(format!("Invalid direction \"{}\"", other)),
+                };
+                WidgetKind::Scroller {
+                    left: int(0)?,
+                    top: int(1)?,
+                    right: int(2)?,
+                    bottom: int(3)?,
+                    direction,
+                    speed: int(5)?,
+                    text: text(6)?,
+                }
+            }
+            WidgetKind::Num { .. } => WidgetKind::Num { x: int(0)?, digit: int(1)? },
+        };
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Widget {
+    id: String,
+    kind: WidgetKind,
+}
+
+#[derive(Clone, Debug)]
+struct LcdScreen {
+    id: String,
+    name: String,
+    priority: Priority,
+    duration: u64,
+    backlight: Option<BacklightMode>,
+    widgets: Vec<Widget>,
+}
+
+#[derive(Debug, Default)]
+struct Client {
+    name: Option<String>,
+    greeted: bool,
+    screens: Vec<LcdScreen>,
+    backlight: Option<BacklightMode>,
+    outbox: Vec<String>,
+}
+
+/// LCDproc protocol state: clients, their screens and widgets
+///
+/// This is the transport-independent core of the LCDproc server. Each
+/// protocol line is passed to `handle` and answered like LCDproc 0.5 would;
+/// `tick` advances time by one `TICK`, rotates screens and composes the
+/// frame to show. `LcdProcServer` wires it to TCP and a `Screen`.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let mut lcdproc = LcdProc::new(2, 16);
+/// let client = lcdproc.connect();
+/// assert!(lcdproc.handle(client, "hello").unwrap().starts_with("connect LCDproc"));
+/// lcdproc.handle(client, "screen_add s1");
+/// lcdproc.handle(client, "widget_add s1 w1 string");
+/// lcdproc.handle(client, "widget_set s1 w1 1 2 {Load 0.42}");
+/// assert_eq!(lcdproc.tick().cells.row_text(1), "Load 0.42       ");
+/// ```
+#[derive(Debug)]
+pub struct LcdProc {
+    rows: u8,
+    columns: u8,
+    clients: BTreeMap<u32, Client>,
+    next_client: u32,
+    tick: u64,
+    current: Option<(u32, String)>,
+    shown_at: u64,
+}
+
+impl LcdProc {
+    /// Creates a server for a panel of the given size
+    pub fn new(rows: u8, columns: u8) -> LcdProc {
+        LcdProc {
+            rows,
+            columns,
+            clients: BTreeMap::new(),
+            next_client: 1,
+            tick: 0,
+            current: None,
+            shown_at: 0,
+        }
+    }
+
+    /// Registers a new connection and returns its client id
+    pub fn connect(&mut self) -> u32 {
+        let id = self.next_client;
+        self.next_client += 1;
+        self.clients.insert(id, Client::default());
+        id
+    }
+
+    /// Drops a client together with its screens
+    pub fn disconnect(&mut self, client: u32) {
+        self.clients.remove(&client);
+    }
+
+    /// Name the client gave with `client_set -name`
+    pub fn client_name(&self, client: u32) -> Option<&str> {
+        self.clients.get(&client)?.name.as_deref()
+    }
+
+    /// Screen currently shown as (client id, screen id)
+    pub fn current_screen(&self) -> Option<(u32, &str)> {
+        self.current.as_ref().map(|(client, screen)| (*client, screen.as_str()))
+    }
+
+    /// Number of ticks since the server started
+    pub fn ticks(&self) -> u64 {
+        self.tick
+    }
+
+    /// Takes the `listen`/`ignore` notifications queued for a client
+    pub fn take_messages(&mut self, client: u32) -> Vec<String> {
+        self.clients
+            .get_mut(&client)
+            .map(|c| std::mem::take(&mut c.outbox))
+            .unwrap_or_default()
+    }
+
+    /// Handles one protocol line and returns the reply
+    ///
+    /// Returns `None` when the client said `bye` and the connection should
+    /// be closed.
+    pub fn handle(&mut self, client: u32, line: &str) -> Option<String> {
+        let args = match tokenize(line) {
+            Ok(args) => args,
+            Err(e) => return Some(huh(&e)),
+        };
+        let Some(command) = args.first() else {
+            return Some(huh("Empty command"));
+        };
+        if command == "bye" {
+            return None;
+        }
+        let args = &args[1..];
+        let greeted = self.clients.get(&client).is_some_and(|c| c.greeted);
+        if command != "hello" && !greeted {
+            return Some(huh("Client must say \"hello\" first"));
+        }
+        let reply = match command.as_str() {
+            "hello" => {
+                self.clients.entry(client).or_default().greeted = true;
+                return Some(format!(
+                    "connect LCDproc 0.5.9 protocol 0.3 lcd wid {} hgt {} cellwid {} cellhgt {}",
+                    self.columns, self.rows, CELL_WIDTH, CELL_HEIGHT
+                ));
+            }
+            "info" => return Some(format!("Qwiic SerLCD {}x{}", self.columns, self.rows)),
+            "noop" | "sleep" | "client_add_key" | "client_del_key" | "output" => Ok(()),
+            "client_set" => self.client_set(client, args),
+            "screen_add" => self.screen_add(client, args),
+            "screen_del" => self.screen_del(client, args),
+            "screen_set" => self.screen_set(client, args),
+            "widget_add" => self.widget_add(client, args),
+            "widget_del" => self.widget_del(client, args),
+            "widget_set" => self.widget_set(client, args),
+            "backlight" => self.client_backlight(client, args),
+            other => Err(format!("Invalid command \"{}\"", other)),
+        };
+        Some(match reply {
+            Ok(()) => "success".to_string(),
+            Err(e) => huh(&e),
+        })
+    }
+
+    /// Advances time by one tick and composes the frame to display
+    pub fn tick(&mut self) -> LcdProcFrame {
+        self.tick += 1;
+        self.rotate();
+        self.compose()
+    }
+
+    fn client(&mut self, client: u32) -> &mut Client {
+        self.clients.entry(client).or_default()
+    }
+
+    fn screen(&mut self, client: u32, id: &str) -> Result<&mut LcdScreen, String> {
+        self.client(client)
+            .screens
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| format!("Unknown screen id \"{}\"", id))
+    }
+
+    fn client_set(&mut self, client: u32, args: &[String]) -> Result<(), String> {
+        for (option, value) in options(args)? {
+            match option {
+                "name" => self.client(client).name = Some(value.to_string()),
+                other => return Err(format!("Unknown option \"{}\"", other)),
+            }
+        }
+        Ok(())
+    }
+
+    fn screen_add(&mut self, client: u32, args: &[String]) -> Result<(), String> {
+        let [id] = args else {
+            return Err("Usage: screen_add <screenid>".to_string());
+        };
+        let screens = &mut self.client(client).screens;
+        if screens.iter().any(|s| &s.id == id) {
+            return Err(format!("Screen \"{}\" already exists", id));
+        }
+        screens.push(LcdScreen {
+            id: id.clone(),
+            name: id.clone(),
+            priority: Priority::Info,
+            duration: DEFAULT_DURATION,
+            backlight: None,
+            widgets: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn screen_del(&mut self, client: u32, args: &[String]) -> Result<(), String> {
+        let [id] = args else {
+            return Err("Usage: screen_del <screenid>".to_string());
+        };
+        let screens = &mut self.client(client).screens;
+        let before = screens.len();
+        screens.retain(|s| &s.id != id);
+        if screens.len() == before {
+            return Err(format!("Unknown screen id \"{}\"", id));
+        }
+        Ok(())
+    }
+
+    fn screen_set(&mut self, client: u32, args: &[String]) -> Result<(), String> {
+        let (id, args) = args.split_first().ok_or("Usage: screen_set <screenid> [-<option> <value>]...")?;
+        let screen = self.screen(client, id)?;
+        for (option, value) in options(args)? {
+            match option {
+                "name" => screen.name = value.to_string(),
+                "priority" => {
+                    screen.priority =
+                        Priority::parse(value).ok_or_else(|| format!("Invalid priority \"{}\"", value))?
+                }
+                "duration" => {
+                    screen.duration = value.parse().map_err(|_| format!("Invalid duration \"{}\"", value))?
+                }
+                "backlight" => {
+                    let current = screen.backlight.unwrap_or(BacklightMode::On);
+                    screen.backlight = match value {
+                        "open" => None,
+                        _ => Some(
+                            BacklightMode::parse(value, current)
+                                .ok_or_else(|| format!("Invalid backlight \"{}\"", value))?,
+                        ),
+                    };
+                }
+                // Accepted for compatibility; this panel has a fixed size,
+                // no heartbeat icon and no client-visible cursor
+                "wid" | "hgt" | "heartbeat" | "cursor" | "cursor_x" | "cursor_y" | "timeout" => {}
+                other => return Err(format!("Unknown option \"{}\"", other)),
+            }
+        }
+        Ok(())
+    }
+
+    fn widget_add(&mut self, client: u32, args: &[String]) -> Result<(), String> {
+        let [screen, id, kind, rest @ ..] = args else {
+            return Err("Usage: widget_add <screenid> <widgetid> <widgettype>".to_string());
+        };
+        if !rest.is_empty() {
+            return Err("Frames are not supported".to_string());
+        }
+        let kind = WidgetKind::new(kind).ok_or_else(|| format!("Unsupported widget type \"{}\"", kind))?;
+        let screen = self.screen(client, screen)?;
+        if screen.widgets.iter().any(|w| &w.id == id) {
+            return Err(format!("Widget \"{}\" already exists", id));
+        }
+        screen.widgets.push(Widget { id: id.clone(), kind });
+        Ok(())
+    }
+
+    fn widget_del(&mut self, client: u32, args: &[String]) -> Result<(), String> {
+        let [screen, id] = args else {
+            return Err("Usage: widget_del <screenid> <widgetid>".to_string());
+        };
+        let widgets = &mut self.screen(client, screen)?.widgets;
+        let before = widgets.len();
+        widgets.retain(|w| &w.id != id);
+        if widgets.len() == before {
+            return Err(format!("Unknown widget id \"{}\"", id));
+        }
+        Ok(())
+    }
+
+    fn widget_set(&mut self, client: u32, args: &[String]) -> Result<(), String> {
+        let [screen, id, rest @ ..] = args else {
+            return Err("Usage: widget_set <screenid> <widgetid> <widget-specific data>".to_string());
+        };
+        let widget = self
+            .screen(client, screen)?
+            .widgets
+            .iter_mut()
+            .find(|w| &w.id == id)
+            .ok_or_else(|| format!("Unknown widget id \"{}\"", id))?;
+        widget.kind.set(rest)
+    }
+
+    fn client_backlight(&mut self, client: u32, args: &[String]) -> Result<(), String> {
+        let [value] = args else {
+            return Err("Usage: backlight {on|off|toggle|blink|flash}".to_string());
+        };
+        let client = self.client(client);
+        let current = client.backlight.unwrap_or(BacklightMode::On);
+        client.backlight =
+            Some(BacklightMode::parse(value, current).ok_or_else(|| format!("Invalid backlight \"{}\"", value))?);
+        Ok(())
+    }
+
+    /// Picks the screen to show: the highest priority class wins and its
+    /// screens take turns for their duration
+    fn rotate(&mut self) {
+        let candidates: Vec<(u32, &LcdScreen)> = self
+            .clients
+            .iter()
+            .flat_map(|(id, client)| client.screens.iter().map(move |screen| (*id, screen)))
+            .filter(|(_, screen)| screen.priority > Priority::Hidden)
+            .collect();
+        let top = candidates.iter().map(|(_, s)| s.priority).max();
+        let list: Vec<(u32, &LcdScreen)> = candidates.into_iter().filter(|(_, s)| Some(s.priority) == top).collect();
+
+        let position = self
+            .current
+            .as_ref()
+            .and_then(|(client, id)| list.iter().position(|(c, s)| c == client && &s.id == id));
+        let next = match position {
+            Some(i) if self.tick - self.shown_at < list[i].1.duration.max(1) => return,
+            Some(i) => Some(list[(i + 1) % list.len()]),
+            None => list.first().copied(),
+        };
+        let next = next.map(|(client, screen)| (client, screen.id.clone()));
+        if next == self.current {
+            self.shown_at = self.tick;
+            return;
+        }
+        if let Some((client, id)) = self.current.take() {
+            if let Some(c) = self.clients.get_mut(&client) {
+                c.outbox.push(format!("ignore {}", id));
+            }
+        }
+        if let Some((client, id)) = &next {
+            if let Some(c) = self.clients.get_mut(client) {
+                c.outbox.push(format!("listen {}", id));
+            }
+        }
+        self.current = next;
+        self.shown_at = self.tick;
+    }
+
+    fn compose(&self) -> LcdProcFrame {
+        let mut frame = LcdProcFrame {
+            cells: FrameBuffer::new(self.rows, self.columns),
+            bars: None,
+            backlight: true,
+        };
+        let Some((client_id, screen_id)) = &self.current else {
+            return frame;
+        };
+        let Some(client) = self.clients.get(client_id) else {
+            return frame;
+        };
+        let Some(screen) = client.screens.iter().find(|s| &s.id == screen_id) else {
+            return frame;
+        };
+
+        let elapsed = self.tick - self.shown_at;
+        let mode = screen.backlight.or(client.backlight).unwrap_or(BacklightMode::On);
+        frame.backlight = mode.is_lit(self.tick);
+        if screen.widgets.iter().any(|w| matches!(w.kind, WidgetKind::VBar { .. })) {
+            frame.bars = Some(BarStyle::Vertical);
+        } else if screen.widgets.iter().any(|w| matches!(w.kind, WidgetKind::HBar { .. })) {
+            frame.bars = Some(BarStyle::Horizontal);
+        }
+        for widget in &screen.widgets {
+            self.draw(&mut frame, &widget.kind, elapsed);
+        }
+        frame
+    }
+
+    fn draw(&self, frame: &mut LcdProcFrame, kind: &WidgetKind, elapsed: u64) {
+        let cells = &mut frame.cells;
+        match kind {
+            WidgetKind::String { x, y, text } => put(cells, *x, *y, &encode(text)),
+            WidgetKind::Title { text } => {
+                let mut line = vec![0xFF, 0xFF, b' '];
+                line.extend(encode(text));
+                line.push(b' ');
+                line.resize(line.len().max(self.columns as usize), 0xFF);
+                put(cells, 1, 1, &line);
+            }
+            WidgetKind::HBar { x, y, length } => {
+                // Client values are clipped to the panel before any arithmetic or allocation
+                let mut line = vec![0xFF; (*length / CELL_WIDTH).clamp(0, self.columns as i32) as usize];
+                let rest = length % CELL_WIDTH;
+                if rest > 0 && frame.bars == Some(BarStyle::Horizontal) {
+                    line.push(rest as u8 - 1);
+                }
+                put(cells, *x, *y, &line);
+            }
+            WidgetKind::VBar { x, y, length } => {
+                let full = (*length / CELL_HEIGHT).clamp(0, self.rows as i32);
+                for i in 0..full {
+                    put(cells, *x, y.saturating_sub(i), &[0xFF]);
+                }
+                let rest = length % CELL_HEIGHT;
+                if rest > 0 {
+                    put(cells, *x, y.saturating_sub(full), &[rest as u8 - 1]);
+                }
+            }
+            WidgetKind::Scroller { left, top, right, bottom, direction, speed, text } => {
+                let width = right.saturating_sub(*left).saturating_add(1).clamp(0, self.columns as i32) as usize;
+                let height = bottom.saturating_sub(*top).saturating_add(1).clamp(1, self.rows.max(1) as i32) as usize;
+                let steps = scroll_steps(elapsed, *speed);
+                let text = encode(text);
+                match direction {
+                    ScrollDirection::Horizontal => {
+                        let overflow = text.len().saturating_sub(width);
+                        let offset = (steps % (overflow as u64 + 1)) as usize;
+                        put(cells, *left, *top, &text[offset..(offset + width).min(text.len())]);
+                    }
+                    ScrollDirection::Marquee if text.len() > width => {
+                        let mut looped = text.clone();
+                        looped.push(b' ');
+                        let offset = (steps % looped.len() as u64) as usize;
+                        let line: Vec<u8> = looped.iter().cycle().skip(offset).take(width).copied().collect();
+                        put(cells, *left, *top, &line);
+                    }
+                    ScrollDirection::Marquee => put(cells, *left, *top, &text),
+                    ScrollDirection::Vertical => {
+                        let lines: Vec<&[u8]> = text.chunks(width.max(1)).collect();
+                        let overflow = lines.len().saturating_sub(height);
+                        let offset = (steps % (overflow as u64 + 1)) as usize;
+                        for (i, line) in lines.iter().skip(offset).take(height).enumerate() {
+                            put(cells, *left, top.saturating_add(i as i32), line);
+                        }
+                    }
+                }
+            }
+            WidgetKind::Num { x, digit } => {
+                let glyph = big_digit(*digit);
+                if self.rows >= 4 {
+                    for (row, line) in glyph.iter().enumerate() {
+                        let line: Vec<u8> = line.bytes().map(|b| if b == b'#' { 0xFF } else { b' ' }).collect();
+                        put(cells, *x, row as i32 + 1, &line);
+                    }
+                } else {
+                    let c = if *digit == 10 { b':' } else { b'0' + (*digit).clamp(0, 9) as u8 };
+                    put(cells, *x, (self.rows as i32 + 1) / 2, &[c]);
+                }
+            }
+        }
+    }
+}
+
+/// Number of scroll steps after `elapsed` ticks; positive speeds are ticks
+/// per step, negative speeds are steps per tick
+fn scroll_steps(elapsed: u64, speed: i32) -> u64 {
+    match speed {
+        0 => 0,
+        s if s > 0 => elapsed / s as u64,
+        s => elapsed.saturating_mul(s.unsigned_abs() as u64),
+    }
+}
+
+/// 3x4 block art for big numbers; 10 is the colon
+fn big_digit(digit: i32) -> [&'static str; 4] {
+    match digit {
+        0 => ["###", "# #", "# #", "###"],
+        1 => [" # ", "## ", " # ", "###"],
+        2 => ["###", "  #", "## ", "###"],
+        3 => ["###", " ##", "  #", "###"],
+        4 => ["# #", "# #", "###", "  #"],
+        5 => ["###", "## ", "  #", "## "],
+        6 => ["#  ", "###", "# #", "###"],
+        7 => ["###", "  #", " # ", " # "],
+        8 => ["###", "###", "# #", "###"],
+        9 => ["###", "# #", "###", "  #"],
+        10 => [" ", "#", " ", "#"],
+        _ => ["   ", "   ", "   ", "   "],
+    }
+}
+
+/// Writes bytes at 1-based LCDproc coordinates, clipping to the panel
+fn put(cells: &mut FrameBuffer, x: i32, y: i32, bytes: &[u8]) {
+    if y < 1 || x < 1 {
+        return;
+    }
+    for (i, byte) in bytes.iter().enumerate() {
+        cells.set(y as usize - 1, x as usize - 1 + i, *byte);
+    }
+}
+
+/// Converts client text to panel bytes; Latin-1 passes through, anything
+/// else becomes '?'
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            code @ (0x20..=0x7E | 0xA0..=0xFF) => code as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+fn huh(message: &str) -> String {
+    format!("huh? {}", message)
+}
+
+/// Splits `-option value` pairs
+fn options(args: &[String]) -> Result<Vec<(&str, &str)>, String> {
+    args.chunks(2)
+        .map(|pair| match pair {
+            [option, value] => Ok((option.trim_start_matches('-'), value.as_str())),
+            _ => Err(format!("Missing value for option \"{}\"", pair[0])),
+        })
+        .collect()
+}
+
+/// Splits a protocol line into words, honoring "quotes", {braces} and
+/// backslash escapes
+fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        match c {
+            '"' | '{' => {
+                let close = if c == '"' { '"' } else { '}' };
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('\\') => word.extend(chars.next()),
+                        Some(c) if c == close => break,
+                        Some(c) => word.push(c),
+                        None => return Err(format!("Unterminated string, expected '{}'", close)),
+                    }
+                }
+            }
+            _ => {
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    chars.next();
+                    if c == '\\' {
+                        word.extend(chars.next());
+                    } else {
+                        word.push(c);
+                    }
+                }
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// LCDproc-compatible TCP server driving a `Screen`
+///
+/// Each client connection gets its own thread; a render thread ticks the
+/// protocol state every `TICK`, sends `listen`/`ignore` notifications and
+/// updates the panel with `Screen::present`, so only changed cells go over
+/// the bus. Panel errors are not fatal: the next tick simply retries.
+///
+/// # Example
+/// ```no_run
+/// # use qwiic_lcd_rs::*;
+/// # use std::net::TcpListener;
+/// let screen = Screen::new(ScreenConfig::default(), "/dev/i2c-1", 0x72).unwrap();
+/// let server = LcdProcServer::new(screen);
+/// server.serve(TcpListener::bind("127.0.0.1:13666").unwrap()).unwrap();
+/// ```
+pub struct LcdProcServer<T: Transport + Send + 'static> {
+    state: Arc<Mutex<LcdProc>>,
+    screen: Arc<Mutex<Screen<T>>>,
+    writers: Arc<Mutex<Writers>>,
+    color: (u8, u8, u8),
+}
+
+/// Outgoing line queue and socket of each connected client
+type Writers = HashMap<u32, (SyncSender<String>, TcpStream)>;
+
+impl<T: Transport + Send + 'static> LcdProcServer<T> {
+    /// Creates a server that renders to the given screen
+    pub fn new(screen: Screen<T>) -> LcdProcServer<T> {
+        let framebuffer = screen.framebuffer();
+        LcdProcServer {
+            state: Arc::new(Mutex::new(LcdProc::new(framebuffer.rows(), framebuffer.columns()))),
+            screen: Arc::new(Mutex::new(screen)),
+            writers: Arc::new(Mutex::new(HashMap::new())),
+            color: (255, 255, 255),
+        }
+    }
+
+    /// Sets the backlight color used when clients turn the backlight on
+    pub fn set_backlight_color(&mut self, r: u8, g: u8, b: u8) {
+        self.color = (r, g, b);
+    }
+
+    /// Shared handle to the protocol state
+    pub fn state(&self) -> Arc<Mutex<LcdProc>> {
+        Arc::clone(&self.state)
+    }
+
+    /// Shared handle to the screen being driven
+    pub fn screen(&self) -> Arc<Mutex<Screen<T>>> {
+        Arc::clone(&self.screen)
+    }
+
+    /// Accepts clients forever
+    ///
+    /// A failed `accept` (e.g. out of file descriptors) is skipped, and a
+    /// client that stops reading is disconnected once its queue of
+    /// `WRITE_QUEUE` lines is full, so neither stalls other clients.
+    pub fn serve(&self, listener: TcpListener) -> io::Result<()> {
+        let state = Arc::clone(&self.state);
+        let screen = Arc::clone(&self.screen);
+        let writers = Arc::clone(&self.writers);
+        let color = self.color;
+        thread::spawn(move || render_loop(state, screen, writers, color));
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_e) => {
+                    #[cfg(feature = "log")]
+                    log::warn!("lcdproc accept failed: {}", _e);
+                    // Don't spin while the error persists
+                    thread::sleep(TICK);
+                    continue;
+                }
+            };
+            let state = Arc::clone(&self.state);
+            let writers = Arc::clone(&self.writers);
+            thread::spawn(move || {
+                let _ = handle_client(stream, state, writers);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Queues a line for the client's writer thread without blocking
+fn send(writers: &Mutex<Writers>, client: u32, line: &str) {
+    let mut writers = writers.lock().unwrap_or_else(|e| e.into_inner());
+    let Some((lines, stream)) = writers.get(&client) else { return };
+    match lines.try_send(format!("{}\n", line)) {
+        Ok(()) | Err(TrySendError::Disconnected(_)) => {}
+        Err(TrySendError::Full(_)) => {
+            // The client stopped reading; its reader thread ends on shutdown
+            let _ = stream.shutdown(Shutdown::Both);
+            writers.remove(&client);
+        }
+    }
+}
+
+fn handle_client(stream: TcpStream, state: Arc<Mutex<LcdProc>>, writers: Arc<Mutex<Writers>>) -> io::Result<()> {
+    let client = state.lock().unwrap_or_else(|e| e.into_inner()).connect();
+    let (lines, queued) = mpsc::sync_channel::<String>(WRITE_QUEUE);
+    let mut output = stream.try_clone()?;
+    thread::spawn(move || {
+        for line in queued {
+            if output.write_all(line.as_bytes()).is_err() {
+                break;
+            }
+        }
+    });
+    writers.lock().unwrap_or_else(|e| e.into_inner()).insert(client, (lines, stream.try_clone()?));
+
+    let mut result = Ok(());
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        };
+        let reply = state.lock().unwrap_or_else(|e| e.into_inner()).handle(client, &line);
+        match reply {
+            Some(reply) => send(&writers, client, &reply),
+            None => break,
+        }
+    }
+
+    state.lock().unwrap_or_else(|e| e.into_inner()).disconnect(client);
+    writers.lock().unwrap_or_else(|e| e.into_inner()).remove(&client);
+    result
+}
+
+fn render_loop<T: Transport>(
+    state: Arc<Mutex<LcdProc>>,
+    screen: Arc<Mutex<Screen<T>>>,
+    writers: Arc<Mutex<Writers>>,
+    color: (u8, u8, u8),
+) {
+    let mut bars = None;
+    let mut backlight = None;
+    loop {
+        thread::sleep(TICK);
+        let (frame, messages) = {
+            let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+            let frame = state.tick();
+            let clients: Vec<u32> = state.clients.keys().copied().collect();
+            let messages: Vec<(u32, Vec<String>)> =
+                clients.into_iter().map(|client| (client, state.take_messages(client))).collect();
+            (frame, messages)
+        };
+        for (client, lines) in messages {
+            for line in lines {
+                send(&writers, client, &line);
+            }
+        }
+
+        let mut screen = screen.lock().unwrap_or_else(|e| e.into_inner());
+        if frame.bars.is_some() && frame.bars != bars {
+            let style = frame.bars.unwrap_or(BarStyle::Horizontal);
+            let loaded = style
+                .glyphs()
+                .iter()
+                .enumerate()
+                .try_for_each(|(index, glyph)| screen.create_character(index as u8, *glyph));
+            if loaded.is_ok() {
+                bars = frame.bars;
+            }
+        }
+        if backlight != Some(frame.backlight) {
+            let (r, g, b) = if frame.backlight { color } else { (0, 0, 0) };
+            if screen.change_backlight(r, g, b).is_ok() {
+                backlight = Some(frame.backlight);
+            }
+        }
+        let _ = screen.present(&frame.cells);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+
+    fn session(rows: u8, columns: u8) -> (LcdProc, u32) {
+        let mut lcdproc = LcdProc::new(rows, columns);
+        let client = lcdproc.connect();
+        lcdproc.handle(client, "hello");
+        (lcdproc, client)
+    }
+
+    fn run(lcdproc: &mut LcdProc, client: u32, lines: &[&str]) {
+        for line in lines {
+            assert_eq!(lcdproc.handle(client, line).as_deref(), Some("success"), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("widget_set s w 1 2 \"a \\\"b\\\"\"").unwrap(), ["widget_set", "s", "w", "1", "2", "a \"b\""]);
+        assert_eq!(tokenize("  widget_set s w {two words}  ").unwrap(), ["widget_set", "s", "w", "two words"]);
+        assert!(tokenize("widget_set \"open").is_err());
+    }
+
+    #[test]
+    fn test_handshake_and_errors() {
+        let mut lcdproc = LcdProc::new(4, 20);
+        let client = lcdproc.connect();
+        assert!(lcdproc.handle(client, "screen_add s1").unwrap().starts_with("huh?"));
+        assert_eq!(
+            lcdproc.handle(client, "hello").unwrap(),
+            "connect LCDproc 0.5.9 protocol 0.3 lcd wid 20 hgt 4 cellwid 5 cellhgt 8"
+        );
+        run(&mut lcdproc, client, &["client_set -name top", "screen_add s1", "noop"]);
+        assert_eq!(lcdproc.client_name(client), Some("top"));
+        assert!(lcdproc.handle(client, "screen_add s1").unwrap().starts_with("huh?"));
+        assert!(lcdproc.handle(client, "widget_add s2 w1 string").unwrap().starts_with("huh?"));
+        assert!(lcdproc.handle(client, "widget_add s1 w1 frame").unwrap().starts_with("huh?"));
+        assert!(lcdproc.handle(client, "frobnicate").unwrap().starts_with("huh?"));
+        assert_eq!(lcdproc.handle(client, "bye"), None);
+    }
+
+    #[test]
+    fn test_widgets() {
+        let (mut lcdproc, client) = session(4, 20);
+        run(
+            &mut lcdproc,
+            client,
+            &[
+                "screen_add s",
+                "widget_add s t title",
+                "widget_add s str string",
+                "widget_add s bar hbar",
+                "widget_add s n num",
+                "widget_set s t Hi",
+                "widget_set s str 1 2 {CPU 12%}",
+                "widget_set s bar 1 3 13",
+                "widget_set s n 18 1",
+            ],
+        );
+        let frame = lcdproc.tick();
+        assert_eq!(frame.cells.row(0)[..6], [0xFF, 0xFF, b' ', b'H', b'i', b' ']);
+        assert_eq!(frame.cells.row_text(1), "CPU 12%          ?? ");
+        assert_eq!(frame.cells.row(2)[..4], [0xFF, 0xFF, 2, b' ']);
+        assert_eq!(frame.bars, Some(BarStyle::Horizontal));
+        assert!(frame.backlight);
+
+        run(&mut lcdproc, client, &["widget_del s bar", "widget_add s v vbar", "widget_set s v 1 4 10"]);
+        let frame = lcdproc.tick();
+        assert_eq!(frame.bars, Some(BarStyle::Vertical));
+        assert_eq!(frame.cells.get(3, 0), Some(0xFF));
+        assert_eq!(frame.cells.get(2, 0), Some(1));
+        assert_eq!(BarStyle::Vertical.glyphs()[1], [0, 0, 0, 0, 0, 0, 0x1F, 0x1F]);
+        assert_eq!(BarStyle::Horizontal.glyphs()[2], [0x1C; 8]);
+    }
+
+    #[test]
+    fn test_extreme_widget_values_are_clipped() {
+        let (mut lcdproc, client) = session(2, 8);
+        run(
+            &mut lcdproc,
+            client,
+            &[
+                "screen_add s",
+                "widget_add s h hbar",
+                "widget_add s v vbar",
+                "widget_add s x scroller",
+                "widget_add s y scroller",
+                "widget_set s h 1 1 2147483647",
+                "widget_set s v 1 -2147483648 2147483647",
+                "widget_set s x -2147483648 2 2147483647 2 m -2147483648 wide",
+                "widget_set s y 1 2147483647 8 -2147483648 v 1 tall",
+            ],
+        );
+        for _ in 0..3 {
+            lcdproc.tick();
+        }
+        assert_eq!(lcdproc.tick().cells.row(0), &[0xFF; 8]);
+    }
+
+    #[test]
+    fn test_scrollers() {
+        let (mut lcdproc, client) = session(2, 8);
+        run(
+            &mut lcdproc,
+            client,
+            &[
+                "screen_add s",
+                "widget_add s h scroller",
+                "widget_add s m scroller",
+                "widget_set s h 1 1 4 1 h 2 abcdef",
+                "widget_set s m 5 2 8 2 m -1 xyz12",
+            ],
+        );
+        let frame = lcdproc.tick();
+        assert_eq!(frame.cells.row_text(0), "abcd    ");
+        assert_eq!(frame.cells.row_text(1), "    xyz1");
+        for _ in 0..3 {
+            lcdproc.tick();
+        }
+        // Four ticks later: two steps for the slow one, four for the fast one
+        let frame = lcdproc.tick();
+        assert_eq!(frame.cells.row_text(0), "cdef    ");
+        assert_eq!(frame.cells.row_text(1), "    2 xy");
+    }
+
+    #[test]
+    fn test_priorities_and_rotation() {
+        let (mut lcdproc, a) = session(2, 16);
+        let b = lcdproc.connect();
+        lcdproc.handle(b, "hello");
+        run(&mut lcdproc, a, &["screen_add one", "screen_set one -duration 2"]);
+        run(&mut lcdproc, b, &["screen_add two", "screen_set two -duration 2"]);
+
+        lcdproc.tick();
+        assert_eq!(lcdproc.current_screen(), Some((a, "one")));
+        assert_eq!(lcdproc.take_messages(a), ["listen one"]);
+        lcdproc.tick();
+        lcdproc.tick();
+        assert_eq!(lcdproc.current_screen(), Some((b, "two")));
+        assert_eq!(lcdproc.take_messages(a), ["ignore one"]);
+        assert_eq!(lcdproc.take_messages(b), ["listen two"]);
+
+        // An alert pre-empts the rotation; hidden screens never show
+        run(&mut lcdproc, a, &["screen_add alarm", "screen_set alarm -priority alert -backlight off"]);
+        run(&mut lcdproc, b, &["screen_set two -priority hidden"]);
+        let frame = lcdproc.tick();
+        assert_eq!(lcdproc.current_screen(), Some((a, "alarm")));
+        assert!(!frame.backlight);
+        for _ in 0..10 {
+            lcdproc.tick();
+        }
+        assert_eq!(lcdproc.current_screen(), Some((a, "alarm")));
+
+        lcdproc.disconnect(a);
+        lcdproc.tick();
+        assert_eq!(lcdproc.current_screen(), None);
+    }
+
+    #[test]
+    fn test_client_backlight() {
+        let (mut lcdproc, client) = session(2, 16);
+        run(&mut lcdproc, client, &["screen_add s", "backlight off"]);
+        assert!(!lcdproc.tick().backlight);
+        run(&mut lcdproc, client, &["backlight toggle"]);
+        assert!(lcdproc.tick().backlight);
+        run(&mut lcdproc, client, &["backlight blink"]);
+        let lit: Vec<bool> = (0..8).map(|_| lcdproc.tick().backlight).collect();
+        assert!(lit.contains(&true) && lit.contains(&false));
+    }
+
+    #[test]
+    fn test_stalled_client_is_dropped_without_blocking() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _peer = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        // Nothing drains the queue, like a writer stuck on a client that stopped reading
+        let (lines, _queued) = mpsc::sync_channel(WRITE_QUEUE);
+        let writers = Mutex::new(Writers::from([(7, (lines, stream))]));
+
+        for _ in 0..WRITE_QUEUE {
+            send(&writers, 7, "success");
+        }
+        assert!(writers.lock().unwrap().contains_key(&7));
+        send(&writers, 7, "success");
+        assert!(writers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tcp_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        let server = LcdProcServer::new(screen);
+        let panel = server.screen();
+        thread::spawn(move || server.serve(listener));
+
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut writer = stream.try_clone().unwrap();
+        for line in ["hello", "screen_add s1", "widget_add s1 w1 string", "widget_set s1 w1 3 2 \"over TCP\""] {
+            writeln!(writer, "{}", line).unwrap();
+        }
+
+        // Replies come in order; the listen notification may arrive in between
+        let mut replies = Vec::new();
+        let mut lines = BufReader::new(stream).lines();
+        while replies.len() < 5 {
+            replies.push(lines.next().unwrap().unwrap());
+        }
+        let (notifications, replies): (Vec<String>, Vec<String>) =
+            replies.into_iter().partition(|line| line.starts_with("listen"));
+        assert!(replies[0].starts_with("connect LCDproc"));
+        assert_eq!(replies[1..], ["success", "success", "success"]);
+        assert_eq!(notifications, ["listen s1"]);
+        // The frame is presented right after the notification is sent
+        thread::sleep(TICK * 2);
+        let panel = panel.lock().unwrap();
+        assert_eq!(panel.transport().row_text(1), "  over TCP      ");
+    }
+}
@@ -17,35 +17,270 @@ extern crate i2cdev;
 
 use std::error::Error;
 use std::fmt;
-use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use i2cdev::linux::LinuxI2CError;
+
+#[cfg(feature = "tokio")]
+mod async_screen;
+#[cfg(feature = "gif")]
+mod capture;
+mod carousel;
+#[cfg(feature = "daemon")]
+mod daemon;
+mod emulator;
+mod font;
+mod framebuffer;
+mod group;
+mod handle;
+#[cfg(feature = "http")]
+mod http;
+mod idle;
+mod image;
+mod lcdproc;
+#[cfg(any(feature = "toml", feature = "yaml"))]
+mod layout;
+mod menu;
+mod mirror;
+mod overlay;
+mod reconnect;
+mod retry;
+mod simulator;
+mod stats;
+mod template;
+mod text_input;
+mod tiled;
+mod transport;
+
+#[cfg(feature = "tokio")]
+pub use async_screen::AsyncScreen;
+#[cfg(feature = "gif")]
+pub use capture::{CapturedFrame, FrameSampling, GifCapture};
+pub use carousel::{Carousel, Page, Transition};
+#[cfg(feature = "daemon")]
+pub use daemon::{Compositor, LcdDaemon, Request, Response, MAX_LINE, REFRESH_INTERVAL};
+pub use emulator::Emulator;
+pub use framebuffer::FrameBuffer;
+pub use group::{PanelHealth, PanelStatus, ScreenGroup, DEFAULT_FAILURE_THRESHOLD};
+pub use handle::{HandleError, RenderCommand, ScreenHandle};
+#[cfg(feature = "http")]
+pub use http::HttpServer;
+pub use idle::{IdleConfig, IdleManager, IdleState, QuietHours, Screensaver};
+pub use image::{ImageTooLarge, PanelImage, RenderOptions};
+#[cfg(feature = "png")]
+pub use image::{GoldenError, UPDATE_GOLDEN_ENV};
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub use layout::{Layout, LayoutError, LayoutRenderer, Panel, Position, Region, Theme};
+pub use lcdproc::{BacklightMode, BarStyle, LcdProc, LcdProcFrame, LcdProcServer, Priority};
+pub use menu::{Input, Menu, MenuEvent, MenuItem, MenuValue};
+pub use mirror::{Mirror, MirrorTarget};
+pub use overlay::{alert, confirm, Dialog, DialogOutcome, Toast};
+pub use reconnect::{ReconnectConfig, ReconnectEvent};
+pub use retry::{
+    CircuitBreakerConfig, CircuitState, FixedBackoff, JitteredBackoff, RetryConfig, RetryFilter, RetryPolicy,
+};
+pub use simulator::TerminalSimulator;
+pub use stats::{I2CStats, OperationStats};
+pub use template::{FieldValue, Template, TemplateError};
+pub use text_input::{CharSet, TextInput, TextInputEvent};
+pub use tiled::TiledDisplay;
+pub use transport::{
+    LinuxTransport, NullTransport, Recorder, ReplayTransport, TraceFormat, Transaction, Transport,
+};
+
+use retry::RetryEngine;
+
+/// Kind of SMBus transaction sent to the LCD
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum I2COperation {
+    /// Single byte write (`smbus_write_byte`)
+    WriteByte,
+    /// Register/value pair (`smbus_write_byte_data`)
+    WriteByteData,
+    /// Register followed by a data block (`smbus_write_i2c_block_data`)
+    WriteBlock,
+}
 
-use i2cdev::core::*;
-use i2cdev::linux::{LinuxI2CDevice, LinuxI2CError};
+impl I2COperation {
+    /// Performs one attempt of this transaction on `transport`
+    pub(crate) fn send<T: Transport + ?Sized>(
+        self,
+        transport: &mut T,
+        register: Option<u8>,
+        bytes: &[u8],
+    ) -> Result<(), LinuxI2CError> {
+        let register = register.unwrap_or_default();
+        match self {
+            I2COperation::WriteByte => transport.write_byte(bytes[0]),
+            I2COperation::WriteByteData => transport.write_byte_data(register, bytes[0]),
+            I2COperation::WriteBlock => transport.write_block(register, bytes),
+        }
+    }
+}
+
+impl fmt::Display for I2COperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            I2COperation::WriteByte => "write_byte",
+            I2COperation::WriteByteData => "write_byte_data",
+            I2COperation::WriteBlock => "write_block",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Whether a failure is worth retrying or needs intervention
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The same operation may succeed if retried (bus busy, NACK, timeout)
+    Transient,
+    /// Retrying will not help: bad arguments, missing device or permissions
+    Permanent,
+}
+
+// Linux errno values reported by the i2c-dev driver
+const EPERM: i32 = 1;
+const ENOENT: i32 = 2;
+const EINTR: i32 = 4;
+const EIO: i32 = 5;
+const ENXIO: i32 = 6;
+const EBADF: i32 = 9;
+const EAGAIN: i32 = 11;
+const EACCES: i32 = 13;
+const EBUSY: i32 = 16;
+const ENODEV: i32 = 19;
+const EINVAL: i32 = 22;
+const EPROTO: i32 = 71;
+const EOPNOTSUPP: i32 = 95;
+const ETIMEDOUT: i32 = 110;
+const EREMOTEIO: i32 = 121;
+
+/// Extracts the OS error number from an I2C error, if there is one
+fn i2c_errno(error: &LinuxI2CError) -> Option<i32> {
+    match error {
+        LinuxI2CError::Errno(errno) => Some(*errno),
+        LinuxI2CError::Io(e) => e.raw_os_error(),
+    }
+}
+
+/// Classifies an errno returned by the I2C bus
+fn classify_errno(errno: Option<i32>) -> ErrorClass {
+    match errno {
+        Some(EPERM | ENOENT | EBADF | EACCES | ENODEV | EINVAL | EOPNOTSUPP) => ErrorClass::Permanent,
+        Some(EINTR | EIO | ENXIO | EAGAIN | EBUSY | EPROTO | ETIMEDOUT | EREMOTEIO) => ErrorClass::Transient,
+        // Unknown or missing errno: give the retry logic the benefit of the doubt
+        _ => ErrorClass::Transient,
+    }
+}
 
 /// Custom error types for QwiicLCD operations
 #[derive(Debug)]
 pub enum QwiicLcdError {
     /// Wraps underlying I2C communication errors
     I2CError(LinuxI2CError),
+    /// An I2C transfer failed after all retry attempts
+    Transfer {
+        /// Kind of transaction that was attempted
+        operation: I2COperation,
+        /// Register (command prefix) byte, if the transaction had one
+        register: Option<u8>,
+        /// Payload bytes that were being sent
+        bytes: Vec<u8>,
+        /// Number of attempts made, including the first one
+        attempts: u32,
+        /// OS error number reported by the driver, if available
+        errno: Option<i32>,
+        /// Error returned by the last attempt
+        source: LinuxI2CError,
+    },
     /// Invalid cursor position
     InvalidPosition { row: usize, col: usize, max_rows: u8, max_columns: u8 },
     /// Invalid character (non-ASCII)
     InvalidCharacter(char),
     /// Communication timeout after retries
     CommunicationTimeout,
+    /// The retry circuit breaker is open after repeated failures
+    CircuitOpen {
+        /// Time left until a trial attempt is allowed
+        retry_after: Duration,
+    },
     /// Device initialization failed
     InitializationFailed(String),
     /// Custom character index out of range (0-7)
     InvalidCustomCharIndex(u8),
+    /// I2C address outside the 7-bit range usable by the panel (0x08-0x77)
+    InvalidAddress(u8),
     /// Contrast value out of range (0-255)
+    #[deprecated(note = "set_contrast accepts the full u8 range, so this error is never returned")]
     InvalidContrastValue(u8),
 }
 
+impl QwiicLcdError {
+    /// Builds a `Transfer` error from the last failure of a retried transaction
+    fn transfer(
+        operation: I2COperation,
+        register: Option<u8>,
+        bytes: &[u8],
+        attempts: u32,
+        source: LinuxI2CError,
+    ) -> QwiicLcdError {
+        QwiicLcdError::Transfer {
+            operation,
+            register,
+            bytes: bytes.to_vec(),
+            attempts,
+            errno: i2c_errno(&source),
+            source,
+        }
+    }
+
+    /// Returns the OS error number behind this error, if any
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            QwiicLcdError::I2CError(e) => i2c_errno(e),
+            QwiicLcdError::Transfer { errno, .. } => *errno,
+            _ => None,
+        }
+    }
+
+    /// Classifies the error as transient (retry may help) or permanent
+    ///
+    /// Bus errors are classified by errno: `ENODEV`, `ENOENT`, `EACCES` and
+    /// similar mean the device or bus is gone and a supervisor should
+    /// reconnect, while `EIO`, `EREMOTEIO`, `EAGAIN` and friends are usually
+    /// glitches. Argument validation errors are always permanent.
+    #[allow(deprecated)]
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            QwiicLcdError::I2CError(_) | QwiicLcdError::Transfer { .. } => classify_errno(self.errno()),
+            QwiicLcdError::CommunicationTimeout | QwiicLcdError::CircuitOpen { .. } => ErrorClass::Transient,
+            QwiicLcdError::InvalidPosition { .. }
+            | QwiicLcdError::InvalidCharacter(_)
+            | QwiicLcdError::InitializationFailed(_)
+            | QwiicLcdError::InvalidCustomCharIndex(_)
+            | QwiicLcdError::InvalidAddress(_)
+            | QwiicLcdError::InvalidContrastValue(_) => ErrorClass::Permanent,
+        }
+    }
+
+    /// Returns true if retrying the operation may succeed
+    pub fn is_transient(&self) -> bool {
+        self.class() == ErrorClass::Transient
+    }
+}
+
 impl fmt::Display for QwiicLcdError {
+    #[allow(deprecated)]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             QwiicLcdError::I2CError(e) => write!(f, "I2C communication error: {}", e),
+            QwiicLcdError::Transfer { operation, register, bytes, attempts, source, .. } => {
+                write!(f, "I2C {} failed after {} attempt(s)", operation, attempts)?;
+                if let Some(register) = register {
+                    write!(f, " at register 0x{:02X}", register)?;
+                }
+                write!(f, " sending {:02X?}: {}", bytes, source)
+            },
             QwiicLcdError::InvalidPosition { row, col, max_rows, max_columns } => {
                 write!(f, "Invalid cursor position ({}, {}). Screen dimensions are {}x{}", 
                        row, col, max_rows, max_columns)
@@ -56,12 +291,18 @@ impl fmt::Display for QwiicLcdError {
             QwiicLcdError::CommunicationTimeout => {
                 write!(f, "Communication timeout: device did not respond after retries")
             },
+            QwiicLcdError::CircuitOpen { retry_after } => {
+                write!(f, "Circuit breaker open after repeated I2C failures; retry in {}ms", retry_after.as_millis())
+            },
             QwiicLcdError::InitializationFailed(msg) => {
                 write!(f, "Failed to initialize LCD: {}", msg)
             },
             QwiicLcdError::InvalidCustomCharIndex(idx) => {
                 write!(f, "Invalid custom character index {}. Must be 0-7", idx)
             },
+            QwiicLcdError::InvalidAddress(address) => {
+                write!(f, "Invalid I2C address 0x{:02X}. Must be 0x08-0x77", address)
+            },
             QwiicLcdError::InvalidContrastValue(val) => {
                 write!(f, "Invalid contrast value {}. Must be 0-255", val)
             },
@@ -73,6 +314,7 @@ impl Error for QwiicLcdError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             QwiicLcdError::I2CError(e) => Some(e),
+            QwiicLcdError::Transfer { source, .. } => Some(source),
             _ => None,
         }
     }
@@ -84,32 +326,8 @@ impl From<LinuxI2CError> for QwiicLcdError {
     }
 }
 
-/// Configuration for retry logic
-#[derive(Clone, Copy, Debug)]
-pub struct RetryConfig {
-    /// Maximum number of retry attempts
-    pub max_retries: u32,
-    /// Initial delay between retries in milliseconds
-    pub initial_delay_ms: u64,
-    /// Multiplier for exponential backoff
-    pub backoff_multiplier: f32,
-    /// Maximum delay between retries in milliseconds
-    pub max_delay_ms: u64,
-}
-
-impl Default for RetryConfig {
-    fn default() -> Self {
-        RetryConfig {
-            max_retries: 3,
-            initial_delay_ms: 10,
-            backoff_multiplier: 2.0,
-            max_delay_ms: 1000,
-        }
-    }
-}
-
 /// LCD commands for controlling the display
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Command {
     ClearDisplay = 0x2D,
     ReturnHome = 0x02,
@@ -125,63 +343,71 @@ pub enum Command {
 }
 
 /// Display entry mode direction
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EntryMode {
     Right = 0x00,
     Left = 0x02,
 }
 
 /// Entry shift direction
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EntryShift {
     Increment = 0x01,
     Decrement = 0x00,
 }
 
 /// Display on/off status
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayStatus {
     Off = 0x00,
     On = 0x04,
 }
 
 /// Cursor visibility state
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum CursorState {
     Off = 0x00,
     On = 0x02,
 }
 
 /// Cursor blink state
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlinkState {
     Off = 0x00,
     On = 0x01,
 }
 
 /// Type of movement (cursor or display)
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MoveType {
     Cursor = 0x00,
     Display = 0x08,
 }
 
 /// Direction for cursor/display movement
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MoveDirection {
     Left = 0x00,
     Right = 0x04,
 }
 
 /// Backlight state
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Backlight {
     Off = 0x00,
     On = 0x04,
 }
 
 /// Write mode flags for LCD communication
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum WriteMode {
     Enable = 0x04,
     ReadWrite = 0x02,
@@ -190,16 +416,23 @@ pub enum WriteMode {
 }
 
 /// Bit mode configuration (4-bit or 8-bit)
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BitMode {
     B4 = 0x00,
     B8 = 0x10,
 }
 
+/// DDRAM address of the first column of each row
+pub(crate) const ROW_OFFSETS: [usize; 4] = [0x00, 0x40, 0x14, 0x54];
+
 /// Configuration for the LCD screen dimensions and retry behavior
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScreenConfig {
     max_rows: u8,
     max_columns: u8,
+    #[cfg_attr(feature = "serde", serde(default))]
     retry_config: RetryConfig,
 }
 
@@ -221,6 +454,21 @@ impl ScreenConfig {
             retry_config,
         }
     }
+
+    /// Number of rows on the panel
+    pub fn max_rows(&self) -> u8 {
+        self.max_rows
+    }
+
+    /// Number of columns on the panel
+    pub fn max_columns(&self) -> u8 {
+        self.max_columns
+    }
+
+    /// Retry configuration
+    pub fn retry_config(&self) -> RetryConfig {
+        self.retry_config
+    }
 }
 
 impl Default for ScreenConfig {
@@ -231,6 +479,8 @@ impl Default for ScreenConfig {
 }
 
 /// Current state of the display (status, cursor, blink)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DisplayState {
     status: DisplayStatus,
     cursor: CursorState,
@@ -238,13 +488,29 @@ pub struct DisplayState {
 }
 
 impl DisplayState {
-    fn new(status: DisplayStatus, cursor: CursorState, blink: BlinkState) -> DisplayState {
+    /// Creates a display state
+    pub fn new(status: DisplayStatus, cursor: CursorState, blink: BlinkState) -> DisplayState {
         DisplayState {
             status,
             cursor,
             blink,
         }
     }
+
+    /// Whether the display is on
+    pub fn status(&self) -> DisplayStatus {
+        self.status
+    }
+
+    /// Whether the cursor is shown
+    pub fn cursor(&self) -> CursorState {
+        self.cursor
+    }
+
+    /// Whether the cursor blinks
+    pub fn blink(&self) -> BlinkState {
+        self.blink
+    }
 }
 
 impl Default for DisplayState {
@@ -253,16 +519,46 @@ impl Default for DisplayState {
     }
 }
 
+/// Copy of what a `Screen` shows, taken with `Screen::snapshot`
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    frame: FrameBuffer,
+    state: DisplayState,
+    backlight: Option<(u8, u8, u8)>,
+}
+
+impl Snapshot {
+    /// The characters on the display when the snapshot was taken
+    pub fn framebuffer(&self) -> &FrameBuffer {
+        &self.frame
+    }
+}
+
 /// Main struct for controlling the QwiicLCD screen via I2C
-pub struct Screen {
-    dev: LinuxI2CDevice,
+///
+/// The transport defaults to a Linux i2c-dev device; any other `Transport`
+/// (recorder, replay, emulator...) can be used with `Screen::with_transport`.
+pub struct Screen<T: Transport = LinuxTransport> {
+    transport: T,
     config: ScreenConfig,
+    retry: RetryEngine,
+    stats: I2CStats,
     state: DisplayState,
+    framebuffer: FrameBuffer,
+    backlight: Option<(u8, u8, u8)>,
+    contrast: Option<u8>,
+    glyphs: [Option<[u8; 8]>; 8],
+    display_shift: i32,
+    reconnect: Option<ReconnectConfig>,
+    reconnect_callback: Option<reconnect::ReconnectCallback>,
+    replaying: bool,
+    in_operation: bool,
 }
 
 type ScreenResult = Result<(), QwiicLcdError>;
 
-impl Screen {
+impl Screen<LinuxTransport> {
     /// Creates a new Screen instance with the given configuration
     ///
     /// # Arguments
@@ -270,52 +566,101 @@ impl Screen {
     /// * `bus` - I2C bus path (e.g., "/dev/i2c-1")
     /// * `i2c_addr` - I2C address of the LCD (default is 0x72)
     pub fn new(config: ScreenConfig, bus: &str, i2c_addr: u16) -> Result<Screen, QwiicLcdError> {
-        let dev = LinuxI2CDevice::new(bus, i2c_addr)
+        let transport = LinuxTransport::open(bus, i2c_addr)
             .map_err(|e| QwiicLcdError::InitializationFailed(
                 format!("Failed to open I2C device on {} at address 0x{:02X}: {}", bus, i2c_addr, e)
             ))?;
-        Ok(Screen {
-            dev,
+        Ok(Screen::with_transport(config, transport))
+    }
+}
+
+impl<T: Transport> Screen<T> {
+    /// Creates a Screen that talks through the given transport
+    ///
+    /// # Example
+    /// ```
+    /// # use qwiic_lcd_rs::*;
+    /// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), NullTransport);
+    /// screen.print("Dry run").unwrap();
+    /// assert_eq!(screen.framebuffer().row_text(0), "Dry run         ");
+    /// ```
+    pub fn with_transport(config: ScreenConfig, transport: T) -> Screen<T> {
+        Screen {
+            transport,
+            framebuffer: FrameBuffer::new(config.max_rows, config.max_columns),
+            retry: RetryEngine::new(config.retry_config),
+            stats: I2CStats::default(),
             config,
             state: DisplayState::default(),
-        })
+            backlight: None,
+            contrast: None,
+            glyphs: [None; 8],
+            display_shift: 0,
+            reconnect: None,
+            reconnect_callback: None,
+            replaying: false,
+            in_operation: false,
+        }
+    }
+
+    /// Returns the underlying transport
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns the underlying transport mutably
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Consumes the screen and returns its transport
+    pub fn into_transport(self) -> T {
+        self.transport
     }
 
     /// Initializes the LCD screen with default settings
     pub fn init(&mut self) -> ScreenResult {
-        self.apply_display_state()?;
-        self.clear()?;
-        self.enable_blink(false)?;
-        self.enable_cursor(false)?;
+        self.resilient(|s| {
+            s.apply_display_state()?;
+            s.clear()?;
+            s.enable_blink(false)?;
+            s.enable_cursor(false)?;
 
-        // Wait for the screen to set up
-        thread::sleep(Duration::from_millis(200));
+            // Wait for the screen to set up
+            s.transport.delay(Duration::from_millis(200));
 
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Changes the backlight color to the specified RGB values
     pub fn change_backlight(&mut self, r: u8, g: u8, b: u8) -> ScreenResult {
         let block = vec![Command::SetRGB as u8, r, g, b];
 
-        self.write_block(Command::SettingCommand as u8, block)
+        self.write_block(Command::SettingCommand as u8, block)?;
+        self.backlight = Some((r, g, b));
+        Ok(())
     }
 
     /// Clears the display and returns cursor to home position
     pub fn clear(&mut self) -> ScreenResult {
-        self.write_setting_cmd(Command::ClearDisplay as u8)?;
-        self.home()
+        self.resilient(|s| {
+            s.write_setting_cmd(Command::ClearDisplay as u8)?;
+            s.framebuffer.clear();
+            s.home()
+        })
     }
 
     /// Returns the cursor to home position (0,0)
     pub fn home(&mut self) -> ScreenResult {
-        self.write_special_cmd(Command::ReturnHome as u8)
+        self.write_special_cmd(Command::ReturnHome as u8)?;
+        self.framebuffer.home();
+        self.display_shift = 0;
+        Ok(())
     }
 
     /// Moves the cursor to the specified row and column
     pub fn move_cursor(&mut self, row: usize, col: usize) -> ScreenResult {
-        let row_offsets: Vec<usize> = vec![0x00, 0x40, 0x14, 0x54];
-
         if row >= self.config.max_rows.into() {
             return Err(QwiicLcdError::InvalidPosition {
                 row,
@@ -333,9 +678,11 @@ impl Screen {
             });
         }
 
-        let command = (Command::SetDDRamAddr as u8) | ((col + row_offsets[row]) as u8);
+        let command = (Command::SetDDRamAddr as u8) | ((col + ROW_OFFSETS[row]) as u8);
 
-        self.write_special_cmd(command)
+        self.write_special_cmd(command)?;
+        self.framebuffer.set_cursor(row, col);
+        Ok(())
     }
 
     /// Enables or disables the cursor visibility
@@ -368,6 +715,17 @@ impl Screen {
         self.apply_display_state()
     }
 
+    /// Current display on/off, cursor and blink state
+    pub fn display_state(&self) -> DisplayState {
+        self.state
+    }
+
+    /// Replaces the display state and applies it, e.g. one saved before a restart
+    pub fn set_display_state(&mut self, state: DisplayState) -> ScreenResult {
+        self.state = state;
+        self.apply_display_state()
+    }
+
     /// Applies the current display state to the hardware
     pub fn apply_display_state(&mut self) -> ScreenResult {
         let flags =
@@ -388,39 +746,36 @@ impl Screen {
     ///
     /// For strict ASCII-only printing, use `print_ascii()` instead.
     pub fn print(&mut self, s: &str) -> ScreenResult {
-        for c in s.chars() {
-            let byte = self.map_character(c);
-            self.write_byte(byte)?;
-        }
-
-        Ok(())
+        let bytes: Vec<u8> = s.chars().map(|c| self.map_character(c)).collect();
+        self.print_bytes(&bytes)
     }
 
     /// Prints ASCII-only text to the LCD at the current cursor position
     ///
     /// This method strictly accepts only ASCII characters (0x20-0x7E).
-    /// Returns an error if any non-ASCII character is encountered.
-    pub fn print_ascii(&mut self, s: &str) -> Result<(), String> {
-        if !s.is_ascii() {
-            return Err("String contains non-ASCII characters".to_string());
-        }
+    /// Tabs, newlines and carriage returns are skipped. The whole string is
+    /// validated before anything is sent, so an invalid character returns
+    /// `QwiicLcdError::InvalidCharacter` without a partial write.
+    pub fn print_ascii(&mut self, s: &str) -> ScreenResult {
+        validate_ascii(s)?;
 
-        for c in s.chars() {
-            if c as u32 >= 0x20 && c as u32 <= 0x7E {
-                self.write_byte(c as u8)
-                    .map_err(|e| format!("I2C error: {:?}", e))?;
-            } else if c == '\n' || c == '\r' || c == '\t' {
-                // Skip control characters silently
-                continue;
-            } else {
-                return Err(format!(
-                    "Character '{}' (0x{:02X}) is not printable ASCII",
-                    c, c as u32
-                ));
+        let bytes: Vec<u8> = s.bytes().filter(|b| (0x20..=0x7E).contains(b)).collect();
+        self.print_bytes(&bytes)
+    }
+
+    /// Writes raw bytes at the current cursor position
+    ///
+    /// Unlike `print`, bytes are sent unchanged, so custom glyph indices
+    /// 0-7 and ROM characters can be mixed with text.
+    pub fn print_bytes(&mut self, bytes: &[u8]) -> ScreenResult {
+        self.resilient(|s| {
+            for byte in bytes {
+                s.write_byte(*byte)?;
+                s.framebuffer.write(*byte);
             }
-        }
 
-        Ok(())
+            Ok(())
+        })
     }
 
     /// Maps a character to a byte value suitable for the LCD
@@ -449,38 +804,65 @@ impl Screen {
 
     /// Writes a single byte to the LCD
     pub fn write_byte(&mut self, command: u8) -> ScreenResult {
-        let result = self.retry_i2c_write_byte(command)?;
-        thread::sleep(Duration::new(0, 10_000));
-        Ok(result)
+        self.resilient(|s| s.transfer(I2COperation::WriteByte, None, &[command]))?;
+        self.transport.delay(Duration::from_micros(10));
+        Ok(())
     }
 
     /// Writes a block of data to the LCD
     pub fn write_block(&mut self, register: u8, data: Vec<u8>) -> ScreenResult {
-        let result = self.retry_i2c_write_block(register, data)?;
-        thread::sleep(Duration::new(0, 10_000));
-        Ok(result)
+        self.resilient(|s| s.transfer(I2COperation::WriteBlock, Some(register), &data))?;
+        self.transport.delay(Duration::from_micros(10));
+        Ok(())
     }
 
     /// Writes a setting command to the LCD
     pub fn write_setting_cmd(&mut self, command: u8) -> ScreenResult {
-        let result = self.retry_i2c_write_byte_data(Command::SettingCommand as u8, command)?;
-        thread::sleep(Duration::new(0, 10_000));
-        Ok(result)
+        self.resilient(|s| s.transfer(I2COperation::WriteByteData, Some(Command::SettingCommand as u8), &[command]))?;
+        self.transport.delay(Duration::from_micros(10));
+        Ok(())
     }
 
     /// Writes a special command to the LCD
     pub fn write_special_cmd(&mut self, command: u8) -> ScreenResult {
-        let result = self.retry_i2c_write_byte_data(Command::SpecialCommand as u8, command)?;
-        thread::sleep(Duration::new(0, 10_000));
-        Ok(result)
+        self.resilient(|s| s.transfer(I2COperation::WriteByteData, Some(Command::SpecialCommand as u8), &[command]))?;
+        self.transport.delay(Duration::from_micros(10));
+        Ok(())
     }
     
     /// Sets the LCD contrast (0-255)
+    ///
+    /// OpenLCD reads the byte right after `|` Ctrl+X as the value, so both
+    /// must go in one transaction; two setting commands would send
+    /// `7C 18 7C <value>` and set the contrast to 0x7C.
     pub fn set_contrast(&mut self, contrast: u8) -> ScreenResult {
-        self.write_setting_cmd(0x18)?;
-        self.write_setting_cmd(contrast)
+        self.write_block(Command::SettingCommand as u8, vec![0x18, contrast])?;
+        self.contrast = Some(contrast);
+        Ok(())
     }
     
+    /// Changes the I2C address the panel answers to (0x08-0x77)
+    ///
+    /// The firmware stores the new address in EEPROM and switches to it
+    /// right away, so this screen must be reopened at the new address
+    /// before it can send anything else.
+    pub fn set_address(&mut self, address: u8) -> ScreenResult {
+        if !(0x08..=0x77).contains(&address) {
+            return Err(QwiicLcdError::InvalidAddress(address));
+        }
+        self.write_block(Command::SettingCommand as u8, vec![0x19, address])
+    }
+
+    /// Enables or disables the splash screen shown at power-up
+    pub fn enable_splash(&mut self, activated: bool) -> ScreenResult {
+        self.write_setting_cmd(if activated { 0x30 } else { 0x31 })
+    }
+
+    /// Saves the current display contents as the power-up splash screen
+    pub fn save_splash(&mut self) -> ScreenResult {
+        self.write_setting_cmd(0x0A)
+    }
+
     /// Creates a custom character at the specified index (0-7)
     /// 
     /// # Arguments
@@ -492,13 +874,16 @@ impl Screen {
         }
         
         let addr = (Command::SetCGRamAddr as u8) | (index << 3);
-        self.write_special_cmd(addr)?;
-        
-        for byte in data.iter() {
-            self.write_byte(*byte)?;
-        }
-        
-        self.home()
+        self.resilient(|s| {
+            s.write_special_cmd(addr)?;
+
+            for byte in data.iter() {
+                s.write_byte(*byte)?;
+            }
+            s.glyphs[index as usize] = Some(data);
+
+            s.home()
+        })
     }
 
     /// Sets the entry mode for text display (left-to-right or right-to-left)
@@ -549,7 +934,12 @@ impl Screen {
     /// ```
     pub fn shift_cursor(&mut self, direction: MoveDirection) -> ScreenResult {
         let command = Command::CursorShift as u8 | MoveType::Cursor as u8 | direction as u8;
-        self.write_special_cmd(command)
+        self.write_special_cmd(command)?;
+        match direction {
+            MoveDirection::Right => self.framebuffer.advance(),
+            MoveDirection::Left => self.framebuffer.retreat(),
+        }
+        Ok(())
     }
 
     /// Shifts the display left or right without moving the cursor
@@ -566,7 +956,12 @@ impl Screen {
     /// ```
     pub fn shift_display(&mut self, direction: MoveDirection) -> ScreenResult {
         let command = Command::CursorShift as u8 | MoveType::Display as u8 | direction as u8;
-        self.write_special_cmd(command)
+        self.write_special_cmd(command)?;
+        self.display_shift += match direction {
+            MoveDirection::Right => 1,
+            MoveDirection::Left => -1,
+        };
+        Ok(())
     }
 
     /// Sets the backlight state (on or off)
@@ -588,6 +983,104 @@ impl Screen {
         }
     }
 
+    /// Returns the tracked contents of the display
+    ///
+    /// The framebuffer mirrors everything written with `print`/`print_ascii`
+    /// and follows `clear`, `home`, `move_cursor` and `shift_cursor`.
+    pub fn framebuffer(&self) -> &FrameBuffer {
+        &self.framebuffer
+    }
+
+    /// Returns the last backlight color set through this screen, if any
+    pub fn backlight(&self) -> Option<(u8, u8, u8)> {
+        self.backlight
+    }
+
+    /// Returns the last contrast set through this screen, if any
+    pub fn contrast(&self) -> Option<u8> {
+        self.contrast
+    }
+
+    /// Updates the display to show `frame`, sending only the cells that differ
+    ///
+    /// Each run of changed cells costs one cursor move plus its bytes, which
+    /// keeps periodic redraws of mostly static content cheap on the bus.
+    ///
+    /// # Example
+    /// ```
+    /// # use qwiic_lcd_rs::*;
+    /// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+    /// let mut frame = FrameBuffer::new(2, 16);
+    /// frame.set(1, 4, b'!');
+    /// screen.present(&frame).unwrap();
+    /// assert_eq!(screen.transport().row_text(1), "    !           ");
+    /// ```
+    pub fn present(&mut self, frame: &FrameBuffer) -> ScreenResult {
+        self.resilient(|s| {
+            let rows = s.framebuffer.rows().min(frame.rows()) as usize;
+            for row in 0..rows {
+                let wanted = frame.row(row);
+                let current = s.framebuffer.row(row).to_vec();
+                let width = wanted.len().min(current.len());
+                let mut col = 0;
+                while col < width {
+                    if wanted[col] == current[col] {
+                        col += 1;
+                        continue;
+                    }
+                    let start = col;
+                    while col < width && wanted[col] != current[col] {
+                        col += 1;
+                    }
+                    s.move_cursor(row, start)?;
+                    s.print_bytes(&wanted[start..col])?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Captures the display contents, cursor, display state and backlight
+    ///
+    /// Overlays such as `Dialog` and `Toast` take one before drawing and
+    /// hand it to `restore` when they are dismissed.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            frame: self.framebuffer.clone(),
+            state: self.state,
+            backlight: self.backlight,
+        }
+    }
+
+    /// Puts back what `snapshot` captured, sending only what changed since
+    ///
+    /// # Example
+    /// ```
+    /// # use qwiic_lcd_rs::*;
+    /// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+    /// screen.print("Uptime 3d").unwrap();
+    /// let snapshot = screen.snapshot();
+    /// screen.clear().unwrap();
+    /// screen.print("Saving...").unwrap();
+    /// screen.restore(&snapshot).unwrap();
+    /// assert_eq!(screen.transport().row_text(0), "Uptime 3d       ");
+    /// ```
+    pub fn restore(&mut self, snapshot: &Snapshot) -> ScreenResult {
+        self.resilient(|s| {
+            s.present(&snapshot.frame)?;
+            if let Some((r, g, b)) = snapshot.backlight {
+                if s.backlight != snapshot.backlight {
+                    s.change_backlight(r, g, b)?;
+                }
+            }
+            s.state = snapshot.state;
+            s.apply_display_state()?;
+            let (row, col) = snapshot.frame.cursor();
+            s.move_cursor(row, col)
+        })
+    }
+
     /// Configures the bit mode of the display (4-bit or 8-bit)
     ///
     /// # Arguments
@@ -602,100 +1095,88 @@ impl Screen {
     /// ```
     pub fn configure_bit_mode(&mut self, mode: BitMode) -> ScreenResult {
         // Configure function set with bit mode, 2-line display, and 5x8 font
-        let command = Command::FunctionSet as u8 | mode as u8 | 0x08 | 0x00;
+        let command = Command::FunctionSet as u8 | mode as u8 | 0x08;
         self.write_special_cmd(command)
     }
     
-    /// Retry I2C write byte operation
-    fn retry_i2c_write_byte(&mut self, command: u8) -> ScreenResult {
-        let mut delay_ms = self.config.retry_config.initial_delay_ms;
-        let mut last_error = None;
-        
-        for attempt in 0..=self.config.retry_config.max_retries {
-            match self.dev.smbus_write_byte(command) {
-                Ok(_) => return Ok(()),
-                Err(e) => {
-                    last_error = Some(e);
-                    
-                    // Don't sleep after the last attempt
-                    if attempt < self.config.retry_config.max_retries {
-                        thread::sleep(Duration::from_millis(delay_ms));
-                        
-                        // Apply exponential backoff
-                        delay_ms = ((delay_ms as f32 * self.config.retry_config.backoff_multiplier) as u64)
-                            .min(self.config.retry_config.max_delay_ms);
-                    }
-                }
-            }
-        }
-        
-        // All retries exhausted
-        match last_error {
-            Some(e) => Err(QwiicLcdError::from(e)),
-            None => Err(QwiicLcdError::CommunicationTimeout),
-        }
+    /// Replaces the retry policy (the default comes from `RetryConfig`)
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use qwiic_lcd_rs::*;
+    /// # let mut screen = Screen::new(ScreenConfig::default(), "/dev/i2c-1", 0x72).unwrap();
+    /// screen.set_retry_policy(JitteredBackoff::new(RetryConfig::default(), 0.5));
+    /// ```
+    pub fn set_retry_policy<P: RetryPolicy + 'static>(&mut self, policy: P) {
+        self.retry.set_policy(Box::new(policy));
     }
-    
-    /// Retry I2C write block operation
-    fn retry_i2c_write_block(&mut self, register: u8, data: Vec<u8>) -> ScreenResult {
-        let mut delay_ms = self.config.retry_config.initial_delay_ms;
-        let mut last_error = None;
-        
-        for attempt in 0..=self.config.retry_config.max_retries {
-            match self.dev.smbus_write_i2c_block_data(register, &data) {
-                Ok(_) => return Ok(()),
-                Err(e) => {
-                    last_error = Some(e);
-                    
-                    // Don't sleep after the last attempt
-                    if attempt < self.config.retry_config.max_retries {
-                        thread::sleep(Duration::from_millis(delay_ms));
-                        
-                        // Apply exponential backoff
-                        delay_ms = ((delay_ms as f32 * self.config.retry_config.backoff_multiplier) as u64)
-                            .min(self.config.retry_config.max_delay_ms);
-                    }
-                }
-            }
-        }
-        
-        // All retries exhausted
-        match last_error {
-            Some(e) => Err(QwiicLcdError::from(e)),
-            None => Err(QwiicLcdError::CommunicationTimeout),
-        }
+
+    /// Sets an overall time budget for each `Screen` call, including retries
+    ///
+    /// All transfers of one call (e.g. every character of a `print`) share
+    /// the budget, which counts the time spent in transfers and backoff
+    /// waits. A retry is skipped if its backoff delay would run past the
+    /// deadline; a reconnect starts the re-run with a fresh budget.
+    pub fn set_operation_deadline(&mut self, deadline: Option<Duration>) {
+        self.retry.set_deadline(deadline);
     }
-    
-    /// Retry I2C write byte data operation
-    fn retry_i2c_write_byte_data(&mut self, register: u8, data: u8) -> ScreenResult {
-        let mut delay_ms = self.config.retry_config.initial_delay_ms;
-        let mut last_error = None;
-        
-        for attempt in 0..=self.config.retry_config.max_retries {
-            match self.dev.smbus_write_byte_data(register, data) {
-                Ok(_) => return Ok(()),
-                Err(e) => {
-                    last_error = Some(e);
-                    
-                    // Don't sleep after the last attempt
-                    if attempt < self.config.retry_config.max_retries {
-                        thread::sleep(Duration::from_millis(delay_ms));
-                        
-                        // Apply exponential backoff
-                        delay_ms = ((delay_ms as f32 * self.config.retry_config.backoff_multiplier) as u64)
-                            .min(self.config.retry_config.max_delay_ms);
-                    }
-                }
-            }
-        }
-        
-        // All retries exhausted
-        match last_error {
-            Some(e) => Err(QwiicLcdError::from(e)),
-            None => Err(QwiicLcdError::CommunicationTimeout),
-        }
+
+    /// Selects which failures are retried (transient errors only by default)
+    pub fn set_retry_filter(&mut self, filter: RetryFilter) {
+        self.retry.set_filter(filter);
+    }
+
+    /// Enables or disables the circuit breaker that fails fast after repeated outages
+    pub fn set_circuit_breaker(&mut self, config: Option<CircuitBreakerConfig>) {
+        self.retry.set_circuit_breaker(config);
+    }
+
+    /// Returns the current circuit breaker state
+    pub fn circuit_state(&self) -> CircuitState {
+        self.retry.circuit_state()
+    }
+
+    /// The configuration the screen was created with
+    pub fn config(&self) -> &ScreenConfig {
+        &self.config
+    }
+
+    /// Returns a snapshot of the I2C traffic counters
+    pub fn stats(&self) -> I2CStats {
+        self.stats
+    }
+
+    /// Resets the I2C traffic counters to zero
+    pub fn reset_stats(&mut self) {
+        self.stats = I2CStats::default();
+    }
+
+    /// Sends one SMBus transaction through the retry engine
+    fn transfer(&mut self, operation: I2COperation, register: Option<u8>, bytes: &[u8]) -> ScreenResult {
+        let mut attempts = 0;
+        let started = Instant::now();
+        let result = self.retry.run(&mut self.transport, operation, register, bytes, |transport| {
+            attempts += 1;
+            operation.send(transport, register, bytes)
+        });
+        let latency = started.elapsed();
+
+        let wire_bytes = bytes.len() + register.is_some() as usize;
+        self.stats.record(operation, wire_bytes, attempts, latency, result.is_err());
+        stats::trace_transaction(operation, register, bytes, attempts, latency, &result);
+        result
+    }
+}
+
+/// Checks that a string only contains printable ASCII (tabs and line breaks are allowed)
+fn validate_ascii(s: &str) -> ScreenResult {
+    match s
+        .chars()
+        .find(|c| !(0x20..=0x7E).contains(&(*c as u32)) && !matches!(c, '\n' | '\r' | '\t'))
+    {
+        Some(c) => Err(QwiicLcdError::InvalidCharacter(c)),
+        None => Ok(()),
     }
-    
 }
 
 /// Maps a value from one range to another
@@ -727,6 +1208,7 @@ pub fn map(x: usize, in_min: usize, in_max: usize, out_min: usize, out_max: usiz
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::thread;
 
     #[test]
     #[ignore] // This test requires hardware
@@ -812,6 +1294,29 @@ mod tests {
         assert_eq!(config.max_columns, 20);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_config_and_state() {
+        // Settings left out of a config file take their defaults
+        let config: ScreenConfig = serde_json::from_str(r#"{"max_rows":2,"max_columns":16}"#).unwrap();
+        assert_eq!(config, ScreenConfig::new(2, 16));
+        let config: ScreenConfig =
+            serde_json::from_str(r#"{"max_rows":2,"max_columns":16,"retry_config":{"max_retries":7}}"#).unwrap();
+        assert_eq!(config.retry_config().max_retries, 7);
+        assert_eq!(config.retry_config().max_delay_ms, RetryConfig::default().max_delay_ms);
+
+        // Display state saved before a restart is put back on the new screen
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        screen.enable_cursor(false).unwrap();
+        let saved = serde_json::to_string(&screen.display_state()).unwrap();
+        assert_eq!(saved, r#"{"status":"On","cursor":"Off","blink":"On"}"#);
+
+        let mut restarted = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        restarted.set_display_state(serde_json::from_str(&saved).unwrap()).unwrap();
+        assert_eq!(restarted.display_state().cursor(), CursorState::Off);
+        assert!(!restarted.transport().cursor_visible());
+    }
+
     #[test]
     fn test_display_state_new() {
         let state = DisplayState::new(DisplayStatus::On, CursorState::Off, BlinkState::On);
@@ -988,88 +1493,35 @@ mod tests {
 
     #[test]
     fn test_print_ascii_valid() {
-        // Test ASCII validation logic without actual I2C device
-        let validate_ascii = |s: &str| -> Result<(), String> {
-            if !s.is_ascii() {
-                return Err("String contains non-ASCII characters".to_string());
-            }
-
-            for c in s.chars() {
-                if c as u32 >= 0x20 && c as u32 <= 0x7E {
-                    // Valid printable ASCII
-                } else if c == '\n' || c == '\r' || c == '\t' {
-                    // Skip control characters silently
-                    continue;
-                } else {
-                    return Err(format!(
-                        "Character '{}' (0x{:02X}) is not printable ASCII",
-                        c, c as u32
-                    ));
-                }
-            }
-
-            Ok(())
-        };
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        screen.print_ascii("Test 123!@#").unwrap();
+        assert_eq!(screen.transport().row_text(0), "Test 123!@#     ");
 
-        // Test valid ASCII strings
-        assert!(validate_ascii("Hello World").is_ok());
-        assert!(validate_ascii("Test 123!@#").is_ok());
-        assert!(validate_ascii("").is_ok()); // Empty string is valid
-        assert!(validate_ascii(" ~!@#$%^&*()_+{}|:\"<>?").is_ok());
+        // Tabs and line breaks pass validation but aren't sent
+        screen.home().unwrap();
+        screen.print_ascii("a\tb\r\nc~").unwrap();
+        assert_eq!(screen.transport().row_text(0), "abc~ 123!@#     ");
     }
 
     #[test]
     fn test_print_ascii_invalid() {
-        // Test ASCII validation logic without actual I2C device
-        let validate_ascii = |s: &str| -> Result<(), String> {
-            if !s.is_ascii() {
-                return Err("String contains non-ASCII characters".to_string());
-            }
-
-            for c in s.chars() {
-                if c as u32 >= 0x20 && c as u32 <= 0x7E {
-                    // Valid printable ASCII
-                } else if c == '\n' || c == '\r' || c == '\t' {
-                    // Skip control characters silently
-                    continue;
-                } else {
-                    return Err(format!(
-                        "Character '{}' (0x{:02X}) is not printable ASCII",
-                        c, c as u32
-                    ));
-                }
-            }
-
-            Ok(())
-        };
-
-        // Test non-ASCII strings
-        assert!(validate_ascii("Café").is_err()); // Contains é
-        assert!(validate_ascii("Hello 世界").is_err()); // Contains Chinese
-        assert!(validate_ascii("€100").is_err()); // Contains Euro symbol
-        assert!(validate_ascii("Temperature: 25°C").is_err()); // Contains degree symbol
-        assert!(validate_ascii("😀").is_err()); // Contains emoji
-
-        // Verify error messages
-        match validate_ascii("Café") {
-            Err(msg) => assert!(msg.contains("non-ASCII")),
-            Ok(_) => panic!("Expected error for non-ASCII string"),
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        for text in ["Café", "Hello 世界", "€100", "Temperature: 25°C", "😀"] {
+            assert!(matches!(screen.print_ascii(text), Err(QwiicLcdError::InvalidCharacter(_))), "{}", text);
         }
+
+        // Nothing is written before the bad character is found
+        assert_eq!(screen.transport().row_text(0), " ".repeat(16));
+        assert_eq!(screen.framebuffer().row_text(0), " ".repeat(16));
     }
 
     #[test]
     fn test_print_empty_string() {
-        // Test that empty strings are valid
-        let validate_ascii = |s: &str| -> Result<(), String> {
-            if !s.is_ascii() {
-                return Err("String contains non-ASCII characters".to_string());
-            }
-            Ok(())
-        };
-
-        // Empty string should work for both validation methods
-        assert!(validate_ascii("").is_ok());
-        assert_eq!("".len(), 0);
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        screen.reset_stats();
+        screen.print_ascii("").unwrap();
+        screen.print("").unwrap();
+        assert_eq!(screen.stats().total().transactions, 0);
     }
 
     #[test]
@@ -1098,8 +1550,9 @@ mod tests {
     }
     
     #[test]
+    #[allow(deprecated)]
     fn test_qwiic_lcd_error_display() {
-        let i2c_error = LinuxI2CError::Io(std::io::Error::new(std::io::ErrorKind::Other, "test error"));
+        let i2c_error = LinuxI2CError::Io(std::io::Error::other("test error"));
         let error = QwiicLcdError::I2CError(i2c_error);
         assert!(error.to_string().contains("I2C communication error"));
         
@@ -1134,7 +1587,7 @@ mod tests {
     
     #[test]
     fn test_error_conversion_from_linux_i2c() {
-        let i2c_error = LinuxI2CError::Io(std::io::Error::new(std::io::ErrorKind::Other, "test"));
+        let i2c_error = LinuxI2CError::Io(std::io::Error::other("test"));
         let lcd_error: QwiicLcdError = i2c_error.into();
         assert!(matches!(lcd_error, QwiicLcdError::I2CError(_)));
     }
@@ -1225,4 +1678,97 @@ mod tests {
         let error = QwiicLcdError::InvalidCustomCharIndex(invalid_index);
         assert!(error.to_string().contains("Invalid custom character index"));
     }
+
+    #[test]
+    fn test_validate_ascii() {
+        assert!(validate_ascii("Hello World").is_ok());
+        assert!(validate_ascii("Line 1\nLine 2\t!").is_ok());
+        assert!(validate_ascii("").is_ok());
+
+        assert!(matches!(validate_ascii("Café"), Err(QwiicLcdError::InvalidCharacter('é'))));
+        assert!(matches!(validate_ascii("bell\x07"), Err(QwiicLcdError::InvalidCharacter('\x07'))));
+        assert!(matches!(validate_ascii("25°C"), Err(QwiicLcdError::InvalidCharacter('°'))));
+    }
+
+    #[test]
+    fn test_transfer_error_context() {
+        let error = QwiicLcdError::transfer(
+            I2COperation::WriteBlock,
+            Some(0x7C),
+            &[0x2B, 255, 0, 0],
+            4,
+            LinuxI2CError::Errno(EREMOTEIO),
+        );
+        match &error {
+            QwiicLcdError::Transfer { operation, register, bytes, attempts, errno, .. } => {
+                assert_eq!(*operation, I2COperation::WriteBlock);
+                assert_eq!(*register, Some(0x7C));
+                assert_eq!(bytes, &vec![0x2B, 255, 0, 0]);
+                assert_eq!(*attempts, 4);
+                assert_eq!(*errno, Some(EREMOTEIO));
+            },
+            _ => panic!("Wrong error type"),
+        }
+        assert!(error.source().is_some());
+
+        let msg = error.to_string();
+        assert!(msg.contains("write_block failed after 4 attempt(s)"));
+        assert!(msg.contains("register 0x7C"));
+        assert!(msg.contains("[2B, FF, 00, 00]"));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_error_classification() {
+        let bus_error = |errno| QwiicLcdError::transfer(I2COperation::WriteByte, None, &[0x41], 1, LinuxI2CError::Errno(errno));
+
+        assert_eq!(bus_error(EREMOTEIO).class(), ErrorClass::Transient);
+        assert_eq!(bus_error(EIO).class(), ErrorClass::Transient);
+        assert_eq!(bus_error(EAGAIN).class(), ErrorClass::Transient);
+        assert_eq!(bus_error(ENODEV).class(), ErrorClass::Permanent);
+        assert_eq!(bus_error(EACCES).class(), ErrorClass::Permanent);
+        assert!(!bus_error(ENODEV).is_transient());
+
+        let io_error = QwiicLcdError::I2CError(LinuxI2CError::Io(std::io::Error::from_raw_os_error(ENOENT)));
+        assert_eq!(io_error.errno(), Some(ENOENT));
+        assert_eq!(io_error.class(), ErrorClass::Permanent);
+
+        let io_error = QwiicLcdError::I2CError(LinuxI2CError::Io(std::io::Error::other("no errno")));
+        assert_eq!(io_error.errno(), None);
+        assert!(io_error.is_transient());
+
+        assert!(QwiicLcdError::CommunicationTimeout.is_transient());
+        assert_eq!(QwiicLcdError::InvalidCharacter('é').class(), ErrorClass::Permanent);
+        assert_eq!(QwiicLcdError::InvalidCustomCharIndex(8).class(), ErrorClass::Permanent);
+        assert_eq!(QwiicLcdError::InitializationFailed("gone".to_string()).class(), ErrorClass::Permanent);
+        assert_eq!(
+            QwiicLcdError::InvalidPosition { row: 4, col: 0, max_rows: 4, max_columns: 20 }.class(),
+            ErrorClass::Permanent
+        );
+    }
+
+    #[test]
+    fn test_present_sends_only_changes() {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 8), Emulator::new(2, 8));
+        let mut frame = FrameBuffer::new(2, 8);
+        for (col, byte) in b"ab\x00".iter().enumerate() {
+            frame.set(0, col, *byte);
+        }
+        frame.set(1, 7, b'z');
+        screen.present(&frame).unwrap();
+        assert_eq!(screen.transport().row(0), b"ab\x00     ");
+        assert_eq!(screen.transport().row_text(1), "       z");
+        assert_eq!(screen.framebuffer().row(0), frame.row(0));
+        assert_eq!(screen.framebuffer().row(1), frame.row(1));
+
+        let sent = screen.stats().total().transactions;
+        screen.present(&frame).unwrap();
+        assert_eq!(screen.stats().total().transactions, sent);
+
+        frame.set(1, 0, b'y');
+        screen.present(&frame).unwrap();
+        // One cursor move and one byte
+        assert_eq!(screen.stats().total().transactions, sent + 2);
+        assert_eq!(screen.transport().row_text(1), "y      z");
+    }
 }
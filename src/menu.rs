@@ -0,0 +1,646 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::{FrameBuffer, QwiicLcdError, Screen, Transport};
+
+type Action = Box<dyn FnMut() + Send>;
+type ChangeCallback = Box<dyn FnMut(&str, &MenuValue) + Send>;
+
+/// HD44780 ROM right arrow, shown after submenu labels
+const ARROW: u8 = 0x7E;
+
+/// Characters offered, in order, when editing a text item
+const TEXT_CHARSET: &str = " ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_.";
+
+/// Abstract input event, mapped from buttons, a rotary encoder or a keyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back,
+}
+
+/// What a `Menu` did with an input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuEvent {
+    /// The input had no effect
+    Ignored,
+    /// The selection moved or an edit changed without being committed
+    Moved,
+    /// A submenu was opened
+    Entered,
+    /// The menu went back to the parent level or cancelled an edit
+    Exited,
+    /// `Back` was pressed at the top level
+    Closed,
+    /// An action item was run (its label)
+    Activated(String),
+    /// A value was committed (its key)
+    Changed(String),
+}
+
+/// Value of a toggle, spinner, choice or text item
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MenuValue {
+    Bool(bool),
+    Number(i32),
+    Text(String),
+}
+
+impl fmt::Display for MenuValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MenuValue::Bool(value) => write!(f, "{}", value),
+            MenuValue::Number(value) => write!(f, "{}", value),
+            MenuValue::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+enum Kind {
+    Action(Action),
+    Submenu(Vec<MenuItem>),
+    Toggle(bool),
+    Spinner { value: i32, min: i32, max: i32, step: i32 },
+    Choice { options: Vec<String>, selected: usize },
+    Text { value: String, max_len: usize },
+}
+
+/// One entry of a `Menu`
+pub struct MenuItem {
+    label: String,
+    key: Option<String>,
+    kind: Kind,
+}
+
+impl MenuItem {
+    /// Item running `action` when selected
+    pub fn action<F: FnMut() + Send + 'static>(label: &str, action: F) -> MenuItem {
+        MenuItem::new(label, None, Kind::Action(Box::new(action)))
+    }
+
+    /// Item opening a nested list of items
+    pub fn submenu(label: &str, items: Vec<MenuItem>) -> MenuItem {
+        MenuItem::new(label, None, Kind::Submenu(items))
+    }
+
+    /// On/off item, flipped by `Select`
+    pub fn toggle(key: &str, label: &str, value: bool) -> MenuItem {
+        MenuItem::new(label, Some(key), Kind::Toggle(value))
+    }
+
+    /// Numeric item stepping between `min` and `max`
+    pub fn spinner(key: &str, label: &str, value: i32, min: i32, max: i32, step: i32) -> MenuItem {
+        let (min, max) = (min.min(max), min.max(max));
+        MenuItem::new(label, Some(key), Kind::Spinner { value: value.clamp(min, max), min, max, step: step.max(1) })
+    }
+
+    /// Item picking one of `options`
+    pub fn choice(key: &str, label: &str, options: &[&str], selected: usize) -> MenuItem {
+        let options: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+        let selected = selected.min(options.len().saturating_sub(1));
+        MenuItem::new(label, Some(key), Kind::Choice { options, selected })
+    }
+
+    /// Free text item of at most `max_len` characters
+    pub fn text(key: &str, label: &str, value: &str, max_len: usize) -> MenuItem {
+        let max_len = max_len.max(1);
+        MenuItem::new(label, Some(key), Kind::Text { value: value.chars().take(max_len).collect(), max_len })
+    }
+
+    fn new(label: &str, key: Option<&str>, kind: Kind) -> MenuItem {
+        MenuItem { label: label.to_string(), key: key.map(str::to_string), kind }
+    }
+
+    /// Label shown on the display
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Key of a value item, `None` for actions and submenus
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Current value of a value item
+    pub fn value(&self) -> Option<MenuValue> {
+        match &self.kind {
+            Kind::Toggle(value) => Some(MenuValue::Bool(*value)),
+            Kind::Spinner { value, .. } => Some(MenuValue::Number(*value)),
+            Kind::Choice { options, selected } => options.get(*selected).cloned().map(MenuValue::Text),
+            Kind::Text { value, .. } => Some(MenuValue::Text(value.clone())),
+            Kind::Action(_) | Kind::Submenu(_) => None,
+        }
+    }
+
+    /// Sets the value of a value item; returns whether it was accepted
+    fn set_value(&mut self, new: &MenuValue) -> bool {
+        match (&mut self.kind, new) {
+            (Kind::Toggle(value), MenuValue::Bool(new)) => *value = *new,
+            (Kind::Spinner { value, min, max, .. }, MenuValue::Number(new)) => *value = (*new).clamp(*min, *max),
+            (Kind::Choice { options, selected }, MenuValue::Text(new)) => match options.iter().position(|o| o == new) {
+                Some(index) => *selected = index,
+                None => return false,
+            },
+            (Kind::Text { value, max_len }, MenuValue::Text(new)) => *value = new.chars().take(*max_len).collect(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Text shown right-aligned after the label
+    fn display_value(&self) -> Vec<u8> {
+        match &self.kind {
+            Kind::Action(_) => Vec::new(),
+            Kind::Submenu(_) => vec![ARROW],
+            Kind::Toggle(true) => b"ON".to_vec(),
+            Kind::Toggle(false) => b"OFF".to_vec(),
+            Kind::Text { value, max_len } => format!("{:<width$}", value, width = max_len).into_bytes(),
+            _ => self.value().map(|value| value.to_string().into_bytes()).unwrap_or_default(),
+        }
+    }
+}
+
+/// Value being edited, restored on `Back`
+struct Edit {
+    original: MenuValue,
+    position: usize,
+}
+
+struct Level {
+    /// Index of the submenu item in the parent level
+    parent: usize,
+    selected: usize,
+    top: usize,
+}
+
+/// Hierarchical menu driven by abstract `Input` events
+///
+/// `Up`/`Down` move the selection, `Select` or `Right` runs an action,
+/// opens a submenu or starts editing, and `Back` or `Left` returns to the
+/// parent. Toggles flip on `Select`. Spinners and choices are edited with
+/// `Up`/`Down` (or `Left`/`Right`); text items cycle the character under
+/// the cursor with `Up`/`Down` and move with `Left`/`Right`. `Select`
+/// commits an edit and `Back` restores the previous value.
+///
+/// The selected row is marked with `>` (`*` while editing) and the list
+/// scrolls to keep it within `max_rows`.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let mut menu = Menu::new(2, vec![
+///     MenuItem::toggle("wifi", "Wi-Fi", true),
+///     MenuItem::spinner("volume", "Volume", 5, 0, 10, 1),
+/// ]);
+/// menu.handle(Input::Down);
+/// menu.handle(Input::Select);
+/// menu.handle(Input::Up);
+/// assert_eq!(menu.handle(Input::Select), MenuEvent::Changed("volume".to_string()));
+/// assert_eq!(menu.value("volume"), Some(MenuValue::Number(6)));
+///
+/// let mut frame = FrameBuffer::new(2, 16);
+/// menu.render_to(&mut frame);
+/// assert_eq!(frame.row_text(0), " Wi-Fi        ON");
+/// assert_eq!(frame.row_text(1), ">Volume        6");
+/// ```
+pub struct Menu {
+    items: Vec<MenuItem>,
+    max_rows: usize,
+    stack: Vec<Level>,
+    edit: Option<Edit>,
+    on_change: Option<ChangeCallback>,
+}
+
+impl Menu {
+    /// Creates a menu showing at most `max_rows` items at once
+    pub fn new(max_rows: u8, items: Vec<MenuItem>) -> Menu {
+        Menu {
+            items,
+            max_rows: (max_rows as usize).max(1),
+            stack: vec![Level { parent: 0, selected: 0, top: 0 }],
+            edit: None,
+            on_change: None,
+        }
+    }
+
+    /// Registers a callback invoked with the key and new value of every committed change
+    pub fn on_change<F>(&mut self, callback: F)
+    where
+        F: FnMut(&str, &MenuValue) + Send + 'static,
+    {
+        self.on_change = Some(Box::new(callback));
+    }
+
+    /// Nesting depth, 0 at the top level
+    pub fn depth(&self) -> usize {
+        self.stack.len() - 1
+    }
+
+    /// Whether a value is being edited
+    pub fn is_editing(&self) -> bool {
+        self.edit.is_some()
+    }
+
+    /// The selected item of the current level
+    pub fn selected(&self) -> Option<&MenuItem> {
+        self.current().get(self.level().selected)
+    }
+
+    /// Returns the value of the item with the given key, searching submenus
+    pub fn value(&self, key: &str) -> Option<MenuValue> {
+        find(&self.items, key).and_then(MenuItem::value)
+    }
+
+    /// Sets the value of the item with the given key; returns whether it was accepted
+    ///
+    /// The change callback is not invoked.
+    pub fn set_value(&mut self, key: &str, value: MenuValue) -> bool {
+        find_mut(&mut self.items, key).is_some_and(|item| item.set_value(&value))
+    }
+
+    /// Values of every keyed item, searching submenus
+    pub fn values(&self) -> BTreeMap<String, MenuValue> {
+        let mut values = BTreeMap::new();
+        collect(&self.items, &mut values);
+        values
+    }
+
+    /// Serializes every value as `key=value` lines
+    pub fn save(&self) -> String {
+        self.values().iter().map(|(key, value)| format!("{}={}\n", key, value)).collect()
+    }
+
+    /// Restores values written by `save`, returning the number applied
+    ///
+    /// Unknown keys and values that don't fit their item are skipped, so a
+    /// saved state survives menu changes between releases.
+    pub fn load(&mut self, saved: &str) -> usize {
+        let mut applied = 0;
+        for line in saved.lines() {
+            let Some((key, raw)) = line.split_once('=') else { continue };
+            let value = match self.value(key) {
+                Some(MenuValue::Bool(_)) => raw.parse().ok().map(MenuValue::Bool),
+                Some(MenuValue::Number(_)) => raw.parse().ok().map(MenuValue::Number),
+                Some(MenuValue::Text(_)) => Some(MenuValue::Text(raw.to_string())),
+                None => None,
+            };
+            if value.is_some_and(|value| self.set_value(key, value)) {
+                applied += 1;
+            }
+        }
+        applied
+    }
+
+    /// Handles one input event
+    pub fn handle(&mut self, input: Input) -> MenuEvent {
+        if self.edit.is_some() {
+            return self.handle_edit(input);
+        }
+        let count = self.current().len();
+        let max_rows = self.max_rows;
+        let level = self.level_mut();
+        match input {
+            Input::Up if level.selected > 0 => {
+                level.selected -= 1;
+                level.top = level.top.min(level.selected);
+                MenuEvent::Moved
+            }
+            Input::Down if level.selected + 1 < count => {
+                level.selected += 1;
+                if level.selected >= level.top + max_rows {
+                    level.top = level.selected + 1 - max_rows;
+                }
+                MenuEvent::Moved
+            }
+            Input::Select | Input::Right => self.activate(input),
+            Input::Back | Input::Left if self.stack.len() > 1 => {
+                self.stack.pop();
+                MenuEvent::Exited
+            }
+            Input::Back => MenuEvent::Closed,
+            _ => MenuEvent::Ignored,
+        }
+    }
+
+    /// Renders the visible part of the current level into `frame`
+    pub fn render_to(&self, frame: &mut FrameBuffer) {
+        frame.clear();
+        let columns = frame.columns() as usize;
+        let level = self.level();
+        let rows = self.max_rows.min(frame.rows() as usize);
+        for (row, (index, item)) in self.current().iter().enumerate().skip(level.top).take(rows).enumerate() {
+            let marker = match (index == level.selected, self.edit.is_some()) {
+                (false, _) => b' ',
+                (true, false) => b'>',
+                (true, true) => b'*',
+            };
+            frame.set(row, 0, marker);
+            let value = item.display_value();
+            let value_start = columns.saturating_sub(value.len());
+            for (col, c) in item.label.chars().enumerate().take(value_start.saturating_sub(2)) {
+                frame.set(row, col + 1, u8::try_from(c).unwrap_or(b'?'));
+            }
+            for (col, byte) in value.iter().enumerate() {
+                frame.set(row, value_start + col, *byte);
+            }
+        }
+    }
+
+    /// Renders the menu on `screen`, sending only the cells that changed
+    pub fn render<T: Transport>(&self, screen: &mut Screen<T>) -> Result<(), QwiicLcdError> {
+        let mut frame = FrameBuffer::new(screen.framebuffer().rows(), screen.framebuffer().columns());
+        self.render_to(&mut frame);
+        screen.present(&frame)
+    }
+
+    /// Display position of the character being edited in a text item, for `enable_cursor`
+    pub fn cursor(&self, columns: u8) -> Option<(usize, usize)> {
+        let edit = self.edit.as_ref()?;
+        let item = self.selected()?;
+        let Kind::Text { max_len, .. } = item.kind else { return None };
+        let level = self.level();
+        let start = (columns as usize).saturating_sub(max_len);
+        Some((level.selected - level.top, start + edit.position))
+    }
+
+    fn activate(&mut self, input: Input) -> MenuEvent {
+        let index = self.level().selected;
+        let Some(item) = self.current_mut().get_mut(index) else { return MenuEvent::Ignored };
+        match &mut item.kind {
+            Kind::Action(action) => {
+                action();
+                MenuEvent::Activated(item.label.clone())
+            }
+            Kind::Submenu(_) => {
+                self.stack.push(Level { parent: index, selected: 0, top: 0 });
+                MenuEvent::Entered
+            }
+            Kind::Toggle(value) if input == Input::Select => {
+                *value = !*value;
+                self.commit()
+            }
+            Kind::Toggle(_) => MenuEvent::Ignored,
+            _ => match item.value() {
+                Some(original) => {
+                    self.edit = Some(Edit { original, position: 0 });
+                    MenuEvent::Moved
+                }
+                // A choice without options has nothing to edit
+                None => MenuEvent::Ignored,
+            },
+        }
+    }
+
+    fn handle_edit(&mut self, input: Input) -> MenuEvent {
+        let index = self.level().selected;
+        let edit = self.edit.as_mut().expect("editing");
+        let item = &mut current_mut(&mut self.items, &self.stack)[index];
+        match (input, &mut item.kind) {
+            (Input::Select, _) => {
+                if let Kind::Text { value, .. } = &mut item.kind {
+                    value.truncate(value.trim_end().len());
+                }
+                self.edit = None;
+                return self.commit();
+            }
+            (Input::Back, _) => {
+                item.set_value(&edit.original);
+                self.edit = None;
+                return MenuEvent::Exited;
+            }
+            (Input::Up | Input::Right, Kind::Spinner { value, max, step, .. }) => {
+                *value = value.saturating_add(*step).min(*max)
+            }
+            (Input::Down | Input::Left, Kind::Spinner { value, min, step, .. }) => {
+                *value = value.saturating_sub(*step).max(*min)
+            }
+            (Input::Up | Input::Right, Kind::Choice { options, selected }) => {
+                *selected = (*selected + 1) % options.len().max(1)
+            }
+            (Input::Down | Input::Left, Kind::Choice { options, selected }) => {
+                *selected = (*selected + options.len().max(1) - 1) % options.len().max(1)
+            }
+            (Input::Up | Input::Down, Kind::Text { value, .. }) => {
+                let mut chars: Vec<char> = value.chars().collect();
+                if edit.position == chars.len() {
+                    chars.push(' ');
+                }
+                chars[edit.position] = cycle(chars[edit.position], input == Input::Up);
+                *value = chars.into_iter().collect();
+            }
+            (Input::Right, Kind::Text { value, max_len }) => {
+                if edit.position + 1 >= *max_len || edit.position >= value.chars().count() {
+                    return MenuEvent::Ignored;
+                }
+                edit.position += 1;
+            }
+            (Input::Left, Kind::Text { .. }) => {
+                if edit.position == 0 {
+                    return MenuEvent::Ignored;
+                }
+                edit.position -= 1;
+            }
+            _ => return MenuEvent::Ignored,
+        }
+        MenuEvent::Moved
+    }
+
+    /// Reports the selected item's value to the change callback
+    fn commit(&mut self) -> MenuEvent {
+        let Some(item) = self.selected() else { return MenuEvent::Ignored };
+        let (Some(key), Some(value)) = (item.key.clone(), item.value()) else { return MenuEvent::Ignored };
+        if let Some(callback) = self.on_change.as_mut() {
+            callback(&key, &value);
+        }
+        MenuEvent::Changed(key)
+    }
+
+    fn level(&self) -> &Level {
+        self.stack.last().expect("menu stack is never empty")
+    }
+
+    fn level_mut(&mut self) -> &mut Level {
+        self.stack.last_mut().expect("menu stack is never empty")
+    }
+
+    fn current(&self) -> &[MenuItem] {
+        let mut items = &self.items[..];
+        for level in &self.stack[1..] {
+            if let Kind::Submenu(children) = &items[level.parent].kind {
+                items = children;
+            }
+        }
+        items
+    }
+
+    fn current_mut(&mut self) -> &mut Vec<MenuItem> {
+        current_mut(&mut self.items, &self.stack)
+    }
+}
+
+fn current_mut<'a>(mut items: &'a mut Vec<MenuItem>, stack: &[Level]) -> &'a mut Vec<MenuItem> {
+    for level in &stack[1..] {
+        items = match &mut items[level.parent].kind {
+            Kind::Submenu(children) => children,
+            _ => unreachable!("menu stack only enters submenus"),
+        };
+    }
+    items
+}
+
+fn find<'a>(items: &'a [MenuItem], key: &str) -> Option<&'a MenuItem> {
+    items.iter().find_map(|item| match &item.kind {
+        Kind::Submenu(children) => find(children, key),
+        _ if item.key.as_deref() == Some(key) => Some(item),
+        _ => None,
+    })
+}
+
+fn find_mut<'a>(items: &'a mut [MenuItem], key: &str) -> Option<&'a mut MenuItem> {
+    items.iter_mut().find_map(|item| {
+        if item.key.as_deref() == Some(key) {
+            return Some(item);
+        }
+        match &mut item.kind {
+            Kind::Submenu(children) => find_mut(children, key),
+            _ => None,
+        }
+    })
+}
+
+fn collect(items: &[MenuItem], values: &mut BTreeMap<String, MenuValue>) {
+    for item in items {
+        match (&item.kind, &item.key, item.value()) {
+            (Kind::Submenu(children), _, _) => collect(children, values),
+            (_, Some(key), Some(value)) => {
+                values.insert(key.clone(), value);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Next (or previous) character of `TEXT_CHARSET`
+fn cycle(c: char, forward: bool) -> char {
+    let charset: Vec<char> = TEXT_CHARSET.chars().collect();
+    let len = charset.len();
+    match charset.iter().position(|&candidate| candidate == c) {
+        Some(i) if forward => charset[(i + 1) % len],
+        Some(i) => charset[(i + len - 1) % len],
+        None => charset[0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+    use std::sync::{Arc, Mutex};
+
+    fn settings() -> Menu {
+        Menu::new(2, vec![
+            MenuItem::toggle("backlight", "Backlight", true),
+            MenuItem::submenu("Network", vec![
+                MenuItem::choice("mode", "Mode", &["DHCP", "Static"], 0),
+                MenuItem::text("name", "Name", "lcd", 6),
+            ]),
+            MenuItem::spinner("contrast", "Contrast", 40, 0, 100, 10),
+        ])
+    }
+
+    fn feed(menu: &mut Menu, inputs: &[Input]) -> Vec<MenuEvent> {
+        inputs.iter().map(|input| menu.handle(*input)).collect()
+    }
+
+    #[test]
+    fn test_navigation_scrolls_and_enters_submenus() {
+        let mut menu = settings();
+        let mut frame = FrameBuffer::new(2, 16);
+        feed(&mut menu, &[Input::Down, Input::Down]);
+        menu.render_to(&mut frame);
+        assert_eq!(frame.row_text(0), " Network       \u{7E}");
+        assert_eq!(frame.row_text(1), ">Contrast     40");
+        assert_eq!(menu.handle(Input::Down), MenuEvent::Ignored);
+
+        assert_eq!(feed(&mut menu, &[Input::Up, Input::Select]), [MenuEvent::Moved, MenuEvent::Entered]);
+        assert_eq!(menu.depth(), 1);
+        menu.render_to(&mut frame);
+        assert_eq!(frame.row_text(0), ">Mode       DHCP");
+        assert_eq!(frame.row_text(1), " Name     lcd   ");
+
+        assert_eq!(feed(&mut menu, &[Input::Back, Input::Back]), [MenuEvent::Exited, MenuEvent::Closed]);
+        assert_eq!(menu.selected().unwrap().label(), "Network");
+    }
+
+    #[test]
+    fn test_editing_commits_and_cancels() {
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let mut menu = settings();
+        let seen = Arc::clone(&changes);
+        menu.on_change(move |key, value| seen.lock().unwrap().push(format!("{}={}", key, value)));
+
+        menu.handle(Input::Select);
+        feed(&mut menu, &[Input::Down, Input::Select, Input::Select, Input::Up, Input::Select]);
+        assert_eq!(menu.value("mode"), Some(MenuValue::Text("Static".to_string())));
+
+        feed(&mut menu, &[Input::Down, Input::Select, Input::Right, Input::Right, Input::Right, Input::Up]);
+        assert_eq!(menu.cursor(16), Some((1, 13)));
+        assert_eq!(menu.value("name"), Some(MenuValue::Text("lcdA".to_string())));
+        assert_eq!(menu.handle(Input::Back), MenuEvent::Exited);
+        assert_eq!(menu.value("name"), Some(MenuValue::Text("lcd".to_string())));
+
+        feed(&mut menu, &[Input::Back, Input::Down, Input::Select, Input::Up, Input::Up, Input::Up]);
+        assert!(menu.is_editing());
+        assert_eq!(menu.handle(Input::Select), MenuEvent::Changed("contrast".to_string()));
+        assert_eq!(*changes.lock().unwrap(), ["backlight=false", "mode=Static", "contrast=70"]);
+
+        let mut empty = Menu::new(2, vec![MenuItem::choice("mode", "Mode", &[], 0)]);
+        assert_eq!(empty.handle(Input::Select), MenuEvent::Ignored);
+        assert!(!empty.is_editing());
+    }
+
+    #[test]
+    fn test_actions_and_persistence() {
+        let runs = Arc::new(Mutex::new(0));
+        let counter = Arc::clone(&runs);
+        let mut menu = Menu::new(4, vec![MenuItem::action("Reboot", move || *counter.lock().unwrap() += 1)]);
+        assert_eq!(menu.handle(Input::Select), MenuEvent::Activated("Reboot".to_string()));
+        assert_eq!(*runs.lock().unwrap(), 1);
+
+        let mut menu = settings();
+        menu.set_value("contrast", MenuValue::Number(500));
+        menu.set_value("mode", MenuValue::Text("Static".to_string()));
+        let saved = menu.save();
+        assert_eq!(saved, "backlight=true\ncontrast=100\nmode=Static\nname=lcd\n");
+
+        let mut restored = settings();
+        assert_eq!(restored.load(&format!("{}mode=PPP\nunknown=1\n", saved)), 4);
+        assert_eq!(restored.values(), menu.values());
+    }
+
+    #[test]
+    fn test_render_on_screen() {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        let menu = settings();
+        menu.render(&mut screen).unwrap();
+        assert_eq!(screen.transport().row_text(0), ">Backlight    ON");
+    }
+}
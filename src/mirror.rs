@@ -0,0 +1,215 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use i2cdev::linux::LinuxI2CError;
+
+use crate::{I2COperation, Transport};
+
+type ErrorCallback = Box<dyn FnMut(&str, &LinuxI2CError) + Send>;
+
+/// Delivery counters of one mirror target
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MirrorTarget {
+    /// Name given to `Mirror::with`
+    pub name: String,
+    /// Transactions delivered
+    pub delivered: u64,
+    /// Transactions the target rejected
+    pub failures: u64,
+    /// Message of the most recent failure
+    pub last_error: Option<String>,
+}
+
+struct Secondary {
+    status: MirrorTarget,
+    transport: Box<dyn Transport>,
+}
+
+/// Transport that duplicates every write to secondary targets
+///
+/// The primary transport decides the outcome of each write; once it
+/// succeeds, the same transaction is sent to every secondary (another
+/// panel, a `TerminalSimulator`, a `Recorder`...). Secondary failures are
+/// counted, passed to the `on_error` callback and otherwise ignored, so a
+/// broken mirror never fails the primary write. Writes the primary rejects
+/// are not mirrored, which keeps retries from being duplicated. Command
+/// delays only apply to the primary.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// # use std::sync::{Arc, Mutex};
+/// let copy = Arc::new(Mutex::new(Emulator::new(2, 16)));
+/// let mirror = Mirror::new(Emulator::new(2, 16)).with("copy", Arc::clone(&copy));
+/// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), mirror);
+/// screen.print("Field unit 7").unwrap();
+/// assert_eq!(copy.lock().unwrap().row_text(0), "Field unit 7    ");
+/// ```
+pub struct Mirror<T: Transport> {
+    primary: T,
+    secondaries: Vec<Secondary>,
+    on_error: Option<ErrorCallback>,
+}
+
+impl<T: Transport> Mirror<T> {
+    /// Mirrors `primary`, with no secondary target yet
+    pub fn new(primary: T) -> Mirror<T> {
+        Mirror { primary, secondaries: Vec::new(), on_error: None }
+    }
+
+    /// Adds a secondary target
+    pub fn with<S: Transport + 'static>(mut self, name: &str, secondary: S) -> Mirror<T> {
+        self.add(name, secondary);
+        self
+    }
+
+    /// Adds a secondary target to an existing mirror
+    pub fn add<S: Transport + 'static>(&mut self, name: &str, secondary: S) {
+        self.secondaries.push(Secondary {
+            status: MirrorTarget { name: name.to_string(), ..MirrorTarget::default() },
+            transport: Box::new(secondary),
+        });
+    }
+
+    /// Removes the secondary targets with the given name; returns whether any was found
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.secondaries.len();
+        self.secondaries.retain(|secondary| secondary.status.name != name);
+        self.secondaries.len() != before
+    }
+
+    /// Registers a callback invoked with the target name on every secondary failure
+    pub fn on_error<F>(&mut self, callback: F)
+    where
+        F: FnMut(&str, &LinuxI2CError) + Send + 'static,
+    {
+        self.on_error = Some(Box::new(callback));
+    }
+
+    /// Returns the primary transport
+    pub fn primary(&self) -> &T {
+        &self.primary
+    }
+
+    /// Returns the primary transport mutably
+    pub fn primary_mut(&mut self) -> &mut T {
+        &mut self.primary
+    }
+
+    /// Delivery counters of every secondary target, in the order they were added
+    pub fn targets(&self) -> Vec<MirrorTarget> {
+        self.secondaries.iter().map(|secondary| secondary.status.clone()).collect()
+    }
+
+    /// Consumes the mirror and returns the primary transport
+    pub fn into_primary(self) -> T {
+        self.primary
+    }
+
+    /// Sends a transaction the primary accepted to every secondary
+    fn mirror(&mut self, operation: I2COperation, register: Option<u8>, bytes: &[u8]) {
+        for secondary in &mut self.secondaries {
+            match operation.send(&mut secondary.transport, register, bytes) {
+                Ok(()) => secondary.status.delivered += 1,
+                Err(e) => {
+                    secondary.status.failures += 1;
+                    secondary.status.last_error = Some(e.to_string());
+                    #[cfg(feature = "log")]
+                    log::warn!("mirror {} failed: {}", secondary.status.name, e);
+                    if let Some(callback) = self.on_error.as_mut() {
+                        callback(&secondary.status.name, &e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Transport> Transport for Mirror<T> {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.primary.write_byte(value)?;
+        self.mirror(I2COperation::WriteByte, None, &[value]);
+        Ok(())
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.primary.write_byte_data(register, value)?;
+        self.mirror(I2COperation::WriteByteData, Some(register), &[value]);
+        Ok(())
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        self.primary.write_block(register, data)?;
+        self.mirror(I2COperation::WriteBlock, Some(register), data);
+        Ok(())
+    }
+
+    fn reopen(&mut self) -> Result<(), LinuxI2CError> {
+        self.primary.reopen()
+    }
+
+    fn delay(&mut self, duration: Duration) {
+        self.primary.delay(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ReplayTransport, Screen, ScreenConfig, TerminalSimulator, Transaction};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_mirrors_to_simulator_and_survives_failures() {
+        let simulator = Arc::new(Mutex::new(TerminalSimulator::with_writer(2, 16, Vec::new())));
+        // Expects a single write, then rejects everything
+        let strict = ReplayTransport::new(vec![Transaction::byte(b'H')]);
+        let failures = Arc::new(Mutex::new(Vec::new()));
+
+        let mut mirror = Mirror::new(Emulator::new(2, 16))
+            .with("sim", Arc::clone(&simulator))
+            .with("strict", strict);
+        let seen = Arc::clone(&failures);
+        mirror.on_error(move |name, _| seen.lock().unwrap().push(name.to_string()));
+
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), mirror);
+        screen.print("Hi").unwrap();
+        screen.change_backlight(0, 255, 0).unwrap();
+
+        assert_eq!(screen.transport().primary().row_text(0), "Hi              ");
+        let simulator = simulator.lock().unwrap();
+        assert_eq!(simulator.emulator().row_text(0), "Hi              ");
+        assert_eq!(simulator.emulator().backlight(), (0, 255, 0));
+
+        let targets = screen.transport().targets();
+        assert_eq!((targets[0].delivered, targets[0].failures), (3, 0));
+        assert_eq!((targets[1].delivered, targets[1].failures), (1, 2));
+        assert!(targets[1].last_error.is_some());
+        assert_eq!(*failures.lock().unwrap(), ["strict", "strict"]);
+    }
+
+    #[test]
+    fn test_primary_failures_are_not_mirrored() {
+        let copy = Arc::new(Mutex::new(Emulator::new(2, 16)));
+        let mut mirror = Mirror::new(ReplayTransport::new(Vec::new())).with("copy", Arc::clone(&copy));
+        assert!(mirror.write_byte(b'x').is_err());
+        assert_eq!(copy.lock().unwrap().row_text(0), " ".repeat(16));
+        assert_eq!(mirror.targets()[0].delivered, 0);
+
+        assert!(mirror.remove("copy"));
+        assert!(!mirror.remove("copy"));
+    }
+}
@@ -0,0 +1,464 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use crate::{FrameBuffer, Input, QwiicLcdError, Screen, Snapshot, Transport};
+
+type ScreenResult = Result<(), QwiicLcdError>;
+
+/// How a `Dialog` was closed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DialogOutcome {
+    Yes,
+    No,
+    /// An alert was acknowledged
+    Dismissed,
+    /// The timeout expired without an answer
+    TimedOut,
+}
+
+/// Full-screen modal: a Yes/No confirmation or an alert
+///
+/// `open` snapshots the screen and draws the dialog, `handle` and `poll`
+/// report the outcome once there is one, and `close` restores the screen
+/// exactly as it was. `Left` picks Yes, `Right` picks No, `Up`/`Down`
+/// switch between them, `Select` answers and `Back` answers No. Any input dismisses an
+/// alert. The blocking `confirm` and `alert` functions wrap all of this.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+/// screen.print("Temp 21C").unwrap();
+///
+/// let mut dialog = Dialog::confirm("Reboot?");
+/// dialog.open(&mut screen).unwrap();
+/// assert_eq!(screen.transport().row_text(1), "   Yes   [No]   ");
+/// dialog.handle(Input::Left);
+/// assert_eq!(dialog.handle(Input::Select), Some(DialogOutcome::Yes));
+/// dialog.close(&mut screen).unwrap();
+/// assert_eq!(screen.transport().row_text(0), "Temp 21C        ");
+/// ```
+pub struct Dialog {
+    message: String,
+    confirm: bool,
+    yes: bool,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    snapshot: Option<Snapshot>,
+}
+
+impl Dialog {
+    /// Yes/No question, with No selected
+    pub fn confirm(message: &str) -> Dialog {
+        Dialog::new(message, true)
+    }
+
+    /// Message acknowledged with any input
+    pub fn alert(message: &str) -> Dialog {
+        Dialog::new(message, false)
+    }
+
+    fn new(message: &str, confirm: bool) -> Dialog {
+        Dialog { message: message.to_string(), confirm, yes: false, timeout: None, deadline: None, snapshot: None }
+    }
+
+    /// Closes the dialog with `TimedOut` when unanswered for `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Dialog {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Whether the dialog is currently shown
+    pub fn is_open(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// Snapshots the screen and draws the dialog over it
+    pub fn open<T: Transport>(&mut self, screen: &mut Screen<T>) -> ScreenResult {
+        if self.snapshot.is_none() {
+            self.snapshot = Some(screen.snapshot());
+        }
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.render(screen)
+    }
+
+    /// Handles an input, returning the outcome once the dialog is answered
+    pub fn handle(&mut self, input: Input) -> Option<DialogOutcome> {
+        if !self.confirm {
+            return Some(DialogOutcome::Dismissed);
+        }
+        match input {
+            Input::Left => {
+                self.yes = true;
+                None
+            }
+            Input::Right => {
+                self.yes = false;
+                None
+            }
+            Input::Up | Input::Down => {
+                self.yes = !self.yes;
+                None
+            }
+            Input::Select if self.yes => Some(DialogOutcome::Yes),
+            Input::Select | Input::Back => Some(DialogOutcome::No),
+        }
+    }
+
+    /// Returns `TimedOut` once the timeout has expired
+    pub fn poll(&self, now: Instant) -> Option<DialogOutcome> {
+        self.deadline.filter(|deadline| now >= *deadline).map(|_| DialogOutcome::TimedOut)
+    }
+
+    /// Draws the dialog, e.g. after `handle` moved the selection
+    pub fn render<T: Transport>(&self, screen: &mut Screen<T>) -> ScreenResult {
+        let mut frame = FrameBuffer::new(screen.framebuffer().rows(), screen.framebuffer().columns());
+        self.render_to(&mut frame);
+        screen.present(&frame)
+    }
+
+    /// Draws the dialog into `frame`
+    ///
+    /// The message is wrapped and centered; a confirmation keeps the last
+    /// row for its buttons, with the selected one in brackets.
+    pub fn render_to(&self, frame: &mut FrameBuffer) {
+        frame.clear();
+        let rows = frame.rows() as usize;
+        let text_rows = if self.confirm { rows.saturating_sub(1) } else { rows };
+        draw_centered(frame, 0, text_rows, &self.message);
+        if self.confirm && rows > 0 {
+            let buttons = if self.yes { "[Yes]   No " } else { " Yes   [No]" };
+            draw_line(frame, rows - 1, buttons.as_bytes());
+        }
+    }
+
+    /// Restores the screen as it was before `open`
+    pub fn close<T: Transport>(&mut self, screen: &mut Screen<T>) -> ScreenResult {
+        match self.snapshot.take() {
+            Some(snapshot) => screen.restore(&snapshot),
+            None => Ok(()),
+        }
+    }
+
+    /// Shows the dialog until it is answered or times out, reading inputs from `inputs`
+    ///
+    /// A closed input channel counts as `No` for a confirmation and
+    /// `Dismissed` for an alert. The screen is restored in every case, also
+    /// when drawing the dialog fails.
+    pub fn run<T: Transport>(mut self, screen: &mut Screen<T>, inputs: &Receiver<Input>) -> Result<DialogOutcome, QwiicLcdError> {
+        let outcome = self.wait(screen, inputs);
+        let closed = self.close(screen);
+        let outcome = outcome?;
+        closed?;
+        Ok(outcome)
+    }
+
+    fn wait<T: Transport>(&mut self, screen: &mut Screen<T>, inputs: &Receiver<Input>) -> Result<DialogOutcome, QwiicLcdError> {
+        self.open(screen)?;
+        loop {
+            let input = match self.deadline {
+                Some(deadline) => inputs.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+                None => inputs.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match input {
+                Ok(input) => match self.handle(input) {
+                    Some(outcome) => return Ok(outcome),
+                    None => self.render(screen)?,
+                },
+                Err(RecvTimeoutError::Timeout) => return Ok(DialogOutcome::TimedOut),
+                Err(RecvTimeoutError::Disconnected) if self.confirm => return Ok(DialogOutcome::No),
+                Err(RecvTimeoutError::Disconnected) => return Ok(DialogOutcome::Dismissed),
+            }
+        }
+    }
+}
+
+/// Asks a Yes/No question and waits for the answer; `true` means Yes
+pub fn confirm<T: Transport>(screen: &mut Screen<T>, message: &str, inputs: &Receiver<Input>) -> Result<bool, QwiicLcdError> {
+    Ok(Dialog::confirm(message).run(screen, inputs)? == DialogOutcome::Yes)
+}
+
+/// Shows a message until any input arrives or `timeout` expires
+pub fn alert<T: Transport>(
+    screen: &mut Screen<T>,
+    message: &str,
+    timeout: Duration,
+    inputs: &Receiver<Input>,
+) -> Result<DialogOutcome, QwiicLcdError> {
+    Dialog::alert(message).with_timeout(timeout).run(screen, inputs)
+}
+
+/// Short message covering one row for a while
+///
+/// `show` snapshots the screen, draws the message centered on its row and
+/// optionally flashes the backlight; `poll` restores the screen and the
+/// backlight once the duration has passed, so it can be called from the
+/// main loop. Content written to the screen while a toast is shown is
+/// replaced by the snapshot when the toast goes away.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// # use std::time::{Duration, Instant};
+/// let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+/// screen.print("Disk 42%").unwrap();
+/// let mut toast = Toast::new("Saved", Duration::from_secs(2)).with_flash(0, 255, 0);
+/// let start = Instant::now();
+/// toast.show(&mut screen, start).unwrap();
+/// assert_eq!(screen.transport().row_text(1), "     Saved      ");
+/// assert!(toast.poll(&mut screen, start + Duration::from_secs(3)).unwrap());
+/// assert_eq!(screen.transport().row_text(1), " ".repeat(16));
+/// ```
+pub struct Toast {
+    message: String,
+    duration: Duration,
+    row: Option<usize>,
+    flash: Option<(u8, u8, u8)>,
+    until: Option<Instant>,
+    snapshot: Option<Snapshot>,
+}
+
+impl Toast {
+    /// Toast shown on the bottom row for `duration`
+    pub fn new(message: &str, duration: Duration) -> Toast {
+        Toast { message: message.to_string(), duration, row: None, flash: None, until: None, snapshot: None }
+    }
+
+    /// Shows the toast on `row` instead of the bottom row
+    pub fn at_row(mut self, row: usize) -> Toast {
+        self.row = Some(row);
+        self
+    }
+
+    /// Switches the backlight to this color while the toast is shown
+    pub fn with_flash(mut self, r: u8, g: u8, b: u8) -> Toast {
+        self.flash = Some((r, g, b));
+        self
+    }
+
+    /// Whether the toast is currently shown
+    pub fn is_visible(&self) -> bool {
+        self.snapshot.is_some()
+    }
+
+    /// Snapshots the screen and draws the toast over it
+    pub fn show<T: Transport>(&mut self, screen: &mut Screen<T>, now: Instant) -> ScreenResult {
+        if self.snapshot.is_none() {
+            self.snapshot = Some(screen.snapshot());
+        }
+        self.until = Some(now + self.duration);
+
+        let rows = screen.framebuffer().rows() as usize;
+        let row = self.row.unwrap_or(rows.saturating_sub(1)).min(rows.saturating_sub(1));
+        let mut frame = screen.framebuffer().clone();
+        draw_centered(&mut frame, row, 1, &self.message);
+        screen.present(&frame)?;
+        match self.flash {
+            Some((r, g, b)) => screen.change_backlight(r, g, b),
+            None => Ok(()),
+        }
+    }
+
+    /// Dismisses the toast once it has expired; returns whether it did
+    pub fn poll<T: Transport>(&mut self, screen: &mut Screen<T>, now: Instant) -> Result<bool, QwiicLcdError> {
+        match self.until {
+            Some(until) if now >= until => {
+                self.dismiss(screen)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Removes the toast now, restoring the screen and backlight
+    pub fn dismiss<T: Transport>(&mut self, screen: &mut Screen<T>) -> ScreenResult {
+        self.until = None;
+        match self.snapshot.take() {
+            Some(snapshot) => screen.restore(&snapshot),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Word-wraps `text` and centers it within `rows` rows starting at `top`, blanking the rest of them
+fn draw_centered(frame: &mut FrameBuffer, top: usize, rows: usize, text: &str) {
+    let columns = frame.columns() as usize;
+    let lines = wrap(text, columns.max(1));
+    let lines = &lines[..lines.len().min(rows)];
+    let first = top + (rows - lines.len()) / 2;
+    for row in top..top + rows {
+        draw_line(frame, row, &[]);
+    }
+    for (i, line) in lines.iter().enumerate() {
+        draw_line(frame, first + i, line);
+    }
+}
+
+/// Writes `line` centered on `row`, blanking the rest of the row
+fn draw_line(frame: &mut FrameBuffer, row: usize, line: &[u8]) {
+    let columns = frame.columns() as usize;
+    let start = columns.saturating_sub(line.len()) / 2;
+    for col in 0..columns {
+        let byte = col.checked_sub(start).and_then(|c| line.get(c)).copied().unwrap_or(b' ');
+        frame.set(row, col, byte);
+    }
+}
+
+/// Splits text into lines of at most `width` bytes, breaking between words where possible
+fn wrap(text: &str, width: usize) -> Vec<Vec<u8>> {
+    let mut lines: Vec<Vec<u8>> = Vec::new();
+    let mut line: Vec<u8> = Vec::new();
+    for word in text.split_whitespace() {
+        let word: Vec<u8> = word.chars().map(|c| u8::try_from(c).unwrap_or(b'?')).collect();
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        for chunk in word.chunks(width) {
+            if !line.is_empty() {
+                line.push(b' ');
+            }
+            line.extend_from_slice(chunk);
+            if line.len() >= width {
+                lines.push(std::mem::take(&mut line));
+            }
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig, Transaction, ENODEV};
+    use i2cdev::linux::LinuxI2CError;
+    use std::sync::mpsc;
+
+    fn screen() -> Screen<Emulator> {
+        let mut screen = Screen::with_transport(ScreenConfig::new(4, 20), Emulator::new(4, 20));
+        screen.change_backlight(0, 0, 255).unwrap();
+        screen.enable_cursor(false).unwrap();
+        screen.enable_blink(false).unwrap();
+        screen.print("CPU 12%  Load 0.40").unwrap();
+        screen.move_cursor(3, 2).unwrap();
+        screen.print("eth0 up").unwrap();
+        screen
+    }
+
+    #[test]
+    fn test_confirm_restores_exactly() {
+        let mut screen = screen();
+        let before = screen.transport().clone();
+        let (tx, rx) = mpsc::channel();
+        for input in [Input::Right, Input::Left, Input::Select] {
+            tx.send(input).unwrap();
+        }
+        assert!(confirm(&mut screen, "Reboot the controller now?", &rx).unwrap());
+
+        let after = screen.transport();
+        for row in 0..4 {
+            assert_eq!(after.row_text(row), before.row_text(row));
+        }
+        assert_eq!(after.cursor(), before.cursor());
+        assert_eq!(after.backlight(), (0, 0, 255));
+        assert!(!after.cursor_visible() && !after.blink());
+    }
+
+    /// Emulator whose next write can be made to fail
+    struct Glitchy {
+        lcd: Emulator,
+        fail_next: bool,
+    }
+
+    impl Glitchy {
+        fn send(&mut self, transaction: Transaction) -> Result<(), LinuxI2CError> {
+            if std::mem::take(&mut self.fail_next) {
+                return Err(LinuxI2CError::Errno(ENODEV));
+            }
+            transaction.send(&mut self.lcd)
+        }
+    }
+
+    impl Transport for Glitchy {
+        fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+            self.send(Transaction::byte(value))
+        }
+
+        fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+            self.send(Transaction::byte_data(register, value))
+        }
+
+        fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+            self.send(Transaction::block(register, data))
+        }
+
+        fn delay(&mut self, _duration: Duration) {}
+    }
+
+    #[test]
+    fn test_dialog_restores_after_render_error() {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Glitchy { lcd: Emulator::new(2, 16), fail_next: false });
+        screen.print("Uptime 3d").unwrap();
+        let mut dialog = Dialog::confirm("Reboot?");
+        dialog.open(&mut screen).unwrap();
+        screen.transport_mut().fail_next = true;
+
+        let (tx, rx) = mpsc::channel();
+        tx.send(Input::Left).unwrap();
+        assert!(matches!(dialog.run(&mut screen, &rx), Err(QwiicLcdError::Transfer { .. })));
+        assert_eq!(screen.transport().lcd.row_text(0), "Uptime 3d       ");
+        assert_eq!(screen.transport().lcd.row_text(1), " ".repeat(16));
+    }
+
+    #[test]
+    fn test_dialog_layout_and_timeout() {
+        let mut dialog = Dialog::confirm("Factory reset? All settings are lost");
+        let mut frame = FrameBuffer::new(4, 20);
+        dialog.render_to(&mut frame);
+        assert_eq!(frame.row_text(0), " Factory reset? All ");
+        assert_eq!(frame.row_text(1), " settings are lost  ");
+        assert_eq!(frame.row_text(2), " ".repeat(20));
+        assert_eq!(frame.row_text(3), "     Yes   [No]     ");
+        assert_eq!(dialog.handle(Input::Back), Some(DialogOutcome::No));
+
+        let mut screen = screen();
+        let (_tx, rx) = mpsc::channel();
+        let outcome = alert(&mut screen, "Filter due", Duration::from_millis(20), &rx).unwrap();
+        assert_eq!(outcome, DialogOutcome::TimedOut);
+        assert_eq!(screen.transport().row_text(0), "CPU 12%  Load 0.40  ");
+    }
+
+    #[test]
+    fn test_toast_flashes_and_restores_backlight() {
+        let mut screen = screen();
+        let start = Instant::now();
+        let mut toast = Toast::new("Backup done", Duration::from_secs(1)).at_row(1).with_flash(255, 0, 0);
+        toast.show(&mut screen, start).unwrap();
+        assert!(toast.is_visible());
+        assert_eq!(screen.transport().row_text(1), "    Backup done     ");
+        assert_eq!(screen.transport().backlight(), (255, 0, 0));
+
+        assert!(!toast.poll(&mut screen, start + Duration::from_millis(500)).unwrap());
+        assert!(toast.poll(&mut screen, start + Duration::from_secs(1)).unwrap());
+        assert!(!toast.is_visible());
+        assert_eq!(screen.transport().row_text(1), " ".repeat(20));
+        assert_eq!(screen.transport().backlight(), (0, 0, 255));
+        assert_eq!(screen.transport().cursor(), (3, 9));
+    }
+}
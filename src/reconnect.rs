@@ -0,0 +1,422 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use crate::{DisplayState, FrameBuffer, QwiicLcdError, Screen, ScreenResult, Transport};
+
+/// Configuration for the opt-in resilient mode
+///
+/// When enabled with `Screen::enable_reconnect`, a write that still fails
+/// after all retries makes the screen reopen the I2C device, re-run `init`
+/// and replay its tracked state before running the whole operation (e.g. a
+/// `print` or `create_character`) once more.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct ReconnectConfig {
+    /// Maximum number of times to reopen the device per failure
+    pub max_attempts: u32,
+    /// Delay before each reopen attempt in milliseconds
+    pub delay_ms: u64,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        ReconnectConfig {
+            max_attempts: 5,
+            delay_ms: 500,
+        }
+    }
+}
+
+/// What `replay` restores, saved before each resilient operation
+#[derive(Clone)]
+struct TrackedState {
+    state: DisplayState,
+    backlight: Option<(u8, u8, u8)>,
+    contrast: Option<u8>,
+    glyphs: [Option<[u8; 8]>; 8],
+    framebuffer: FrameBuffer,
+    display_shift: i32,
+}
+
+/// Events reported to the reconnect callback
+#[derive(Debug)]
+pub enum ReconnectEvent<'a> {
+    /// Retries were exhausted and the device is considered lost
+    DeviceLost(&'a QwiicLcdError),
+    /// About to reopen the device (attempt number, starting at 1)
+    Attempt(u32),
+    /// The device was reopened and its state replayed
+    Restored { attempts: u32 },
+    /// Every reopen attempt failed; carries the last error
+    Failed(&'a QwiicLcdError),
+}
+
+pub(crate) type ReconnectCallback = Box<dyn FnMut(&ReconnectEvent) + Send>;
+
+impl<T: Transport> Screen<T> {
+    /// Enables resilient mode with the given reconnect configuration
+    pub fn enable_reconnect(&mut self, config: ReconnectConfig) {
+        self.reconnect = Some(config);
+    }
+
+    /// Disables resilient mode; failed writes return their error directly
+    pub fn disable_reconnect(&mut self) {
+        self.reconnect = None;
+    }
+
+    /// Registers a callback invoked on device loss and reconnect progress
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use qwiic_lcd_rs::*;
+    /// # let mut screen = Screen::new(ScreenConfig::default(), "/dev/i2c-1", 0x72).unwrap();
+    /// screen.enable_reconnect(ReconnectConfig::default());
+    /// screen.on_reconnect(|event| match event {
+    ///     ReconnectEvent::Restored { attempts } => println!("LCD back after {} attempt(s)", attempts),
+    ///     ReconnectEvent::Failed(e) => eprintln!("LCD lost: {}", e),
+    ///     _ => {}
+    /// });
+    /// ```
+    pub fn on_reconnect<F>(&mut self, callback: F)
+    where
+        F: FnMut(&ReconnectEvent) + Send + 'static,
+    {
+        self.reconnect_callback = Some(Box::new(callback));
+    }
+
+    /// Reopens the I2C device, re-runs `init` and replays the tracked state
+    ///
+    /// The display state, backlight color, contrast, custom characters,
+    /// framebuffer contents and cursor position are restored. This is called
+    /// automatically in resilient mode but can also be used directly, e.g.
+    /// after a supervisor sees a permanent error.
+    pub fn reconnect(&mut self) -> ScreenResult {
+        let config = self.reconnect.unwrap_or_default();
+        // A failed attempt leaves `self` reset by `init`, so every attempt
+        // replays this copy
+        let tracked = self.tracked_state();
+        let mut last_error = None;
+
+        for attempt in 1..=config.max_attempts.max(1) {
+            self.emit(&ReconnectEvent::Attempt(attempt));
+            self.transport.delay(Duration::from_millis(config.delay_ms));
+
+            let result = self
+                .transport
+                .reopen()
+                .map_err(QwiicLcdError::from)
+                .and_then(|()| self.replay(&tracked));
+
+            match result {
+                Ok(()) => {
+                    self.restore_tracked_state(tracked);
+                    self.emit(&ReconnectEvent::Restored { attempts: attempt });
+                    return Ok(());
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        self.restore_tracked_state(tracked);
+        let error = last_error.unwrap_or(QwiicLcdError::CommunicationTimeout);
+        self.emit(&ReconnectEvent::Failed(&error));
+        Err(error)
+    }
+
+    /// Runs an operation, reconnecting and re-running it once if it fails in resilient mode
+    ///
+    /// The transfers of the outermost call share one retry deadline, and
+    /// only the outermost call reconnects: a failure anywhere inside a
+    /// multi-transfer operation such as `create_character` rolls the tracked
+    /// state back to where the operation started, replays it and runs the
+    /// whole operation again, so the panel never sees half of it.
+    pub(crate) fn resilient<F>(&mut self, mut op: F) -> ScreenResult
+    where
+        F: FnMut(&mut Screen<T>) -> ScreenResult,
+    {
+        if self.in_operation || self.replaying {
+            return op(self);
+        }
+        if self.reconnect.is_none() {
+            return self.run_operation(&mut op);
+        }
+        let before = self.tracked_state();
+        match self.run_operation(&mut op) {
+            Err(e) if self.should_reconnect(&e) => {
+                self.emit(&ReconnectEvent::DeviceLost(&e));
+                self.restore_tracked_state(before);
+                self.reconnect()?;
+                self.run_operation(&mut op)
+            }
+            result => result,
+        }
+    }
+
+    fn run_operation<F>(&mut self, op: &mut F) -> ScreenResult
+    where
+        F: FnMut(&mut Screen<T>) -> ScreenResult,
+    {
+        self.in_operation = true;
+        self.retry.begin_operation();
+        let result = op(self);
+        self.retry.end_operation();
+        self.in_operation = false;
+        result
+    }
+
+    fn tracked_state(&self) -> TrackedState {
+        TrackedState {
+            state: self.state,
+            backlight: self.backlight,
+            contrast: self.contrast,
+            glyphs: self.glyphs,
+            framebuffer: self.framebuffer.clone(),
+            display_shift: self.display_shift,
+        }
+    }
+
+    fn restore_tracked_state(&mut self, tracked: TrackedState) {
+        self.state = tracked.state;
+        self.backlight = tracked.backlight;
+        self.contrast = tracked.contrast;
+        self.glyphs = tracked.glyphs;
+        self.framebuffer = tracked.framebuffer;
+        self.display_shift = tracked.display_shift;
+    }
+
+    fn should_reconnect(&self, error: &QwiicLcdError) -> bool {
+        self.reconnect.is_some()
+            && !self.replaying
+            && matches!(error, QwiicLcdError::Transfer { .. } | QwiicLcdError::I2CError(_))
+    }
+
+    fn replay(&mut self, tracked: &TrackedState) -> ScreenResult {
+        self.replaying = true;
+        let result = self.replay_state(tracked);
+        self.replaying = false;
+        result
+    }
+
+    fn replay_state(&mut self, tracked: &TrackedState) -> ScreenResult {
+        self.init()?;
+
+        self.state = tracked.state;
+        self.apply_display_state()?;
+        if let Some((r, g, b)) = tracked.backlight {
+            self.change_backlight(r, g, b)?;
+        }
+        if let Some(contrast) = tracked.contrast {
+            self.set_contrast(contrast)?;
+        }
+        for (index, glyph) in tracked.glyphs.iter().enumerate() {
+            if let Some(data) = glyph {
+                self.create_character(index as u8, *data)?;
+            }
+        }
+
+        let framebuffer = &tracked.framebuffer;
+        for row in 0..framebuffer.rows() as usize {
+            self.move_cursor(row, 0)?;
+            for byte in framebuffer.row(row) {
+                self.write_byte(*byte)?;
+            }
+        }
+
+        let direction = if tracked.display_shift < 0 {
+            crate::MoveDirection::Left
+        } else {
+            crate::MoveDirection::Right
+        };
+        for _ in 0..tracked.display_shift.unsigned_abs() {
+            self.shift_display(direction)?;
+        }
+
+        let (row, col) = framebuffer.cursor();
+        self.move_cursor(row, col)
+    }
+
+    fn emit(&mut self, event: &ReconnectEvent) {
+        if let Some(callback) = self.reconnect_callback.as_mut() {
+            callback(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, FixedBackoff, ScreenConfig, Transaction, ENODEV};
+    use i2cdev::linux::LinuxI2CError;
+    use std::sync::{Arc, Mutex};
+
+    /// Transport that drops off the bus until it is reopened
+    #[derive(Default)]
+    struct FlakyTransport {
+        lost: bool,
+        /// Transactions to let through before dropping off the bus
+        fail_after: Option<usize>,
+        /// `fail_after` to arm on the next reopen
+        fail_after_reopen: Option<usize>,
+        reopens: u32,
+        sent: Vec<Transaction>,
+        /// Panel receiving the transactions that got through
+        lcd: Option<Emulator>,
+    }
+
+    impl FlakyTransport {
+        fn send(&mut self, transaction: Transaction) -> Result<(), LinuxI2CError> {
+            match self.fail_after {
+                Some(0) => {
+                    self.lost = true;
+                    self.fail_after = None;
+                }
+                Some(ref mut n) => *n -= 1,
+                None => {}
+            }
+            if self.lost {
+                return Err(LinuxI2CError::Errno(ENODEV));
+            }
+            if let Some(lcd) = self.lcd.as_mut() {
+                transaction.send(lcd)?;
+            }
+            self.sent.push(transaction);
+            Ok(())
+        }
+    }
+
+    impl Transport for FlakyTransport {
+        fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+            self.send(Transaction::byte(value))
+        }
+
+        fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+            self.send(Transaction::byte_data(register, value))
+        }
+
+        fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+            self.send(Transaction::block(register, data))
+        }
+
+        fn reopen(&mut self) -> Result<(), LinuxI2CError> {
+            self.reopens += 1;
+            self.lost = false;
+            self.fail_after = self.fail_after_reopen.take();
+            Ok(())
+        }
+
+        fn delay(&mut self, _duration: Duration) {}
+    }
+
+    fn flaky_screen() -> Screen<FlakyTransport> {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 4), FlakyTransport::default());
+        screen.set_retry_policy(FixedBackoff { max_retries: 1, delay: Duration::ZERO });
+        screen
+    }
+
+    #[test]
+    fn test_failure_without_resilient_mode() {
+        let mut screen = flaky_screen();
+        screen.transport_mut().lost = true;
+        assert!(matches!(screen.print("A"), Err(QwiicLcdError::Transfer { errno: Some(ENODEV), .. })));
+        assert_eq!(screen.transport().reopens, 0);
+    }
+
+    #[test]
+    fn test_reconnect_replays_state() {
+        let mut screen = flaky_screen();
+        screen.change_backlight(10, 20, 30).unwrap();
+        screen.set_contrast(40).unwrap();
+        screen.create_character(1, [1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        screen.move_cursor(1, 0).unwrap();
+        screen.print("Hi").unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        screen.enable_reconnect(ReconnectConfig { max_attempts: 2, delay_ms: 0 });
+        screen.on_reconnect(move |event| log.lock().unwrap().push(format!("{:?}", event)));
+
+        screen.transport_mut().lost = true;
+        screen.transport_mut().sent.clear();
+        screen.print("!").unwrap();
+
+        let transport = screen.transport();
+        assert_eq!(transport.reopens, 1);
+        let sent = &transport.sent;
+        assert!(sent.contains(&Transaction::block(0x7C, &[0x2B, 10, 20, 30])));
+        assert!(sent.contains(&Transaction::block(0x7C, &[0x18, 40])));
+        assert!(sent.contains(&Transaction::byte_data(0xFE, 0x48)));
+        // Row 1 is redrawn, then the cursor goes back to (1, 2) before the retried '!'
+        assert!(sent.windows(5).any(|w| w
+            == [
+                Transaction::byte_data(0xFE, 0xC0),
+                Transaction::byte(b'H'),
+                Transaction::byte(b'i'),
+                Transaction::byte(b' '),
+                Transaction::byte(b' ')
+            ]));
+        assert_eq!(&sent[sent.len() - 2..], &[Transaction::byte_data(0xFE, 0xC2), Transaction::byte(b'!')]);
+        assert_eq!(screen.framebuffer().row_text(1), "Hi! ");
+
+        let events = events.lock().unwrap();
+        assert!(events[0].starts_with("DeviceLost"));
+        assert_eq!(events[1], "Attempt(1)");
+        assert_eq!(events[2], "Restored { attempts: 1 }");
+    }
+
+    #[test]
+    fn test_glyph_failing_mid_write_is_rewritten_whole() {
+        let mut screen = flaky_screen();
+        screen.transport_mut().lcd = Some(Emulator::new(2, 4));
+        screen.print("Hi").unwrap();
+        screen.enable_reconnect(ReconnectConfig { max_attempts: 2, delay_ms: 0 });
+
+        // Drop off the bus after the CGRAM address and three glyph rows
+        let heart = [0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00];
+        screen.transport_mut().fail_after = Some(4);
+        screen.create_character(2, heart).unwrap();
+
+        let lcd = screen.transport().lcd.as_ref().unwrap();
+        assert_eq!(screen.transport().reopens, 1);
+        assert_eq!(lcd.glyph(2), heart);
+        assert_eq!(lcd.row_text(0), "Hi  ");
+        assert_eq!(lcd.row_text(1), "    ");
+        assert_eq!(screen.framebuffer().row_text(0), "Hi  ");
+    }
+
+    #[test]
+    fn test_reconnect_retry_replays_original_state() {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 8), FlakyTransport::default());
+        screen.set_retry_policy(FixedBackoff { max_retries: 1, delay: Duration::ZERO });
+        screen.transport_mut().lcd = Some(Emulator::new(2, 8));
+        screen.print("KEEPME").unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        screen.enable_reconnect(ReconnectConfig { max_attempts: 3, delay_ms: 0 });
+        screen.on_reconnect(move |event| log.lock().unwrap().push(format!("{:?}", event)));
+
+        // The first replay drops off the bus again right after clearing the panel
+        screen.transport_mut().lost = true;
+        screen.transport_mut().fail_after_reopen = Some(3);
+        screen.print("!").unwrap();
+
+        assert_eq!(screen.transport().reopens, 2);
+        assert_eq!(screen.transport().lcd.as_ref().unwrap().row_text(0), "KEEPME! ");
+        assert_eq!(screen.framebuffer().row_text(0), "KEEPME! ");
+        let events = events.lock().unwrap();
+        assert_eq!(events[1..], ["Attempt(1)", "Attempt(2)", "Restored { attempts: 2 }"]);
+    }
+}
@@ -0,0 +1,636 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use i2cdev::linux::LinuxI2CError;
+
+use crate::{classify_errno, i2c_errno, ErrorClass, I2COperation, QwiicLcdError, ScreenResult, Transport};
+
+/// Configuration for retry logic
+///
+/// This is the default exponential backoff policy used by `Screen`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct RetryConfig {
+    /// Maximum number of retry attempts
+    pub max_retries: u32,
+    /// Initial delay between retries in milliseconds
+    pub initial_delay_ms: u64,
+    /// Multiplier for exponential backoff
+    pub backoff_multiplier: f32,
+    /// Maximum delay between retries in milliseconds
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_retries: 3,
+            initial_delay_ms: 10,
+            backoff_multiplier: 2.0,
+            max_delay_ms: 1000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Backoff delay before the given retry (1 = first retry), without jitter
+    fn backoff(&self, retry: u32) -> Duration {
+        let mut delay_ms = self.initial_delay_ms.min(self.max_delay_ms);
+        for _ in 1..retry {
+            delay_ms = ((delay_ms as f32 * self.backoff_multiplier) as u64).min(self.max_delay_ms);
+        }
+        Duration::from_millis(delay_ms)
+    }
+}
+
+/// Decides how long to wait before retrying a failed transfer
+///
+/// Any `FnMut(u32) -> Option<Duration>` closure is a policy, which makes
+/// one-off custom schedules easy:
+///
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// # use std::time::Duration;
+/// // Three quick retries, then give up
+/// let policy = |retry: u32| if retry <= 3 { Some(Duration::from_millis(5)) } else { None };
+/// # fn takes<P: RetryPolicy>(_: P) {}
+/// # takes(policy);
+/// ```
+pub trait RetryPolicy: Send {
+    /// Returns the delay before retry number `retry` (starting at 1), or
+    /// `None` to stop retrying
+    fn next_delay(&mut self, retry: u32) -> Option<Duration>;
+}
+
+impl RetryPolicy for RetryConfig {
+    fn next_delay(&mut self, retry: u32) -> Option<Duration> {
+        if retry > self.max_retries {
+            return None;
+        }
+        Some(self.backoff(retry))
+    }
+}
+
+impl<F> RetryPolicy for F
+where
+    F: FnMut(u32) -> Option<Duration> + Send,
+{
+    fn next_delay(&mut self, retry: u32) -> Option<Duration> {
+        self(retry)
+    }
+}
+
+/// Retries a fixed number of times with a constant delay
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FixedBackoff {
+    /// Maximum number of retry attempts
+    pub max_retries: u32,
+    /// Delay between retries
+    pub delay: Duration,
+}
+
+impl RetryPolicy for FixedBackoff {
+    fn next_delay(&mut self, retry: u32) -> Option<Duration> {
+        if retry > self.max_retries {
+            return None;
+        }
+        Some(self.delay)
+    }
+}
+
+/// Exponential backoff with random jitter
+///
+/// Each delay is drawn uniformly from `[(1 - jitter) * d, d]`, where `d` is
+/// the exponential delay from the wrapped `RetryConfig`. Jitter spreads out
+/// retries from several panels that failed at the same time. A
+/// deserialized policy starts from a fresh random state.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JitteredBackoff {
+    config: RetryConfig,
+    jitter: f32,
+    #[cfg_attr(feature = "serde", serde(skip, default = "random_seed"))]
+    state: u64,
+}
+
+/// Nonzero xorshift seed taken from the clock
+fn random_seed() -> u64 {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    seed | 1
+}
+
+impl JitteredBackoff {
+    /// Creates a jittered policy; `jitter` is clamped to 0.0-1.0
+    pub fn new(config: RetryConfig, jitter: f32) -> JitteredBackoff {
+        JitteredBackoff {
+            config,
+            jitter: jitter.clamp(0.0, 1.0),
+            state: random_seed(),
+        }
+    }
+
+    /// xorshift64: plenty for spreading retries, no extra dependency
+    fn next_unit(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+impl RetryPolicy for JitteredBackoff {
+    fn next_delay(&mut self, retry: u32) -> Option<Duration> {
+        let delay = self.config.next_delay(retry)?;
+        let factor = 1.0 - self.jitter * self.next_unit();
+        Some(delay.mul_f32(factor))
+    }
+}
+
+/// Selects which failures are worth retrying
+#[derive(Default)]
+pub enum RetryFilter {
+    /// Retry every failure, whatever its errno
+    All,
+    /// Only retry errors classified as transient; `ENODEV`, `EACCES` and
+    /// other permanent errors fail immediately (default)
+    #[default]
+    TransientOnly,
+    /// Retry when the closure returns true for the failure's errno
+    Custom(Box<dyn Fn(Option<i32>) -> bool + Send>),
+}
+
+impl RetryFilter {
+    fn allows(&self, error: &LinuxI2CError) -> bool {
+        match self {
+            RetryFilter::All => true,
+            RetryFilter::TransientOnly => classify_errno(i2c_errno(error)) == ErrorClass::Transient,
+            RetryFilter::Custom(filter) => filter(i2c_errno(error)),
+        }
+    }
+}
+
+/// Configuration for the retry circuit breaker
+///
+/// After `failure_threshold` operations in a row fail, the circuit opens
+/// and further operations fail immediately with `QwiicLcdError::CircuitOpen`
+/// instead of waiting through their retries. Once `cooldown` has elapsed a
+/// single trial attempt is let through; success closes the circuit again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed operations before the circuit opens
+    pub failure_threshold: u32,
+    /// Time to fail fast before letting a trial attempt through
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(5),
+        }
+    }
+}
+
+/// State of the retry circuit breaker
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CircuitState {
+    /// Operations run normally
+    Closed,
+    /// Operations fail fast until the cooldown elapses
+    Open,
+    /// The cooldown elapsed; the next operation is a single trial attempt
+    HalfOpen,
+}
+
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> CircuitBreaker {
+        CircuitBreaker {
+            config,
+            consecutive_failures: 0,
+            opened_at: None,
+        }
+    }
+
+    fn state(&self) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(opened) if opened.elapsed() >= self.config.cooldown => CircuitState::HalfOpen,
+            Some(_) => CircuitState::Open,
+        }
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.opened_at = None;
+    }
+
+    fn record_failure(&mut self) {
+        self.consecutive_failures += 1;
+        if self.opened_at.is_some() || self.consecutive_failures >= self.config.failure_threshold {
+            self.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// Retry loop shared by every I2C transfer made by `Screen`
+pub(crate) struct RetryEngine {
+    policy: Box<dyn RetryPolicy>,
+    deadline: Option<Duration>,
+    /// Transfer and backoff time charged against the deadline so far
+    spent: Duration,
+    /// Whether `spent` carries over between transfers
+    in_operation: bool,
+    filter: RetryFilter,
+    breaker: Option<CircuitBreaker>,
+}
+
+impl RetryEngine {
+    pub(crate) fn new(config: RetryConfig) -> RetryEngine {
+        RetryEngine {
+            policy: Box::new(config),
+            deadline: None,
+            spent: Duration::ZERO,
+            in_operation: false,
+            filter: RetryFilter::default(),
+            breaker: None,
+        }
+    }
+
+    pub(crate) fn set_policy(&mut self, policy: Box<dyn RetryPolicy>) {
+        self.policy = policy;
+    }
+
+    pub(crate) fn set_deadline(&mut self, deadline: Option<Duration>) {
+        self.deadline = deadline;
+    }
+
+    pub(crate) fn set_filter(&mut self, filter: RetryFilter) {
+        self.filter = filter;
+    }
+
+    pub(crate) fn set_circuit_breaker(&mut self, config: Option<CircuitBreakerConfig>) {
+        self.breaker = config.map(CircuitBreaker::new);
+    }
+
+    pub(crate) fn circuit_state(&self) -> CircuitState {
+        self.breaker.as_ref().map_or(CircuitState::Closed, CircuitBreaker::state)
+    }
+
+    /// Starts a public operation; its transfers share one deadline until `end_operation`
+    pub(crate) fn begin_operation(&mut self) {
+        self.spent = Duration::ZERO;
+        self.in_operation = true;
+    }
+
+    pub(crate) fn end_operation(&mut self) {
+        self.in_operation = false;
+    }
+
+    /// Called before the first attempt of a transfer; outside an operation
+    /// every transfer gets a deadline of its own
+    pub(crate) fn start_transfer(&mut self) {
+        if !self.in_operation {
+            self.spent = Duration::ZERO;
+        }
+    }
+
+    /// Charges an attempt or a backoff wait against the deadline
+    pub(crate) fn spend(&mut self, time: Duration) {
+        self.spent += time;
+    }
+
+    /// Runs `attempt` until it succeeds, the policy gives up, the error is
+    /// filtered out or the deadline would be exceeded
+    ///
+    /// Backoff waits go through `Transport::delay`, so transports that don't
+    /// talk to hardware retry without sleeping. The deadline counts the time
+    /// spent in attempts and the waits requested, not the wall clock.
+    pub(crate) fn run<T, F>(
+        &mut self,
+        transport: &mut T,
+        operation: I2COperation,
+        register: Option<u8>,
+        bytes: &[u8],
+        mut attempt: F,
+    ) -> ScreenResult
+    where
+        T: Transport + ?Sized,
+        F: FnMut(&mut T) -> Result<(), LinuxI2CError>,
+    {
+        let trial_only = self.admit()?;
+        self.start_transfer();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let started = Instant::now();
+            let result = attempt(transport);
+            self.spend(started.elapsed());
+            let error = match result {
+                Ok(()) => {
+                    self.succeeded();
+                    return Ok(());
+                }
+                Err(e) => e,
+            };
+
+            match self.backoff(attempts, trial_only, &error) {
+                Some(delay) => {
+                    transport.delay(delay);
+                    self.spend(delay);
+                }
+                None => {
+                    self.failed();
+                    return Err(QwiicLcdError::transfer(operation, register, bytes, attempts, error));
+                }
+            }
+        }
+    }
+
+    /// Checks the circuit breaker before a transfer
+    ///
+    /// Returns whether only a single trial attempt is allowed (half-open).
+    pub(crate) fn admit(&self) -> Result<bool, QwiicLcdError> {
+        match self.breaker.as_ref().map(|b| (b.state(), b)) {
+            Some((CircuitState::Open, breaker)) => {
+                let opened = breaker.opened_at.expect("open circuit has an opening time");
+                let retry_after = breaker.config.cooldown.saturating_sub(opened.elapsed());
+                Err(QwiicLcdError::CircuitOpen { retry_after })
+            }
+            Some((CircuitState::HalfOpen, _)) => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Returns how long to wait before retrying after failed attempt
+    /// number `attempts`, or `None` to give up
+    pub(crate) fn backoff(&mut self, attempts: u32, trial_only: bool, error: &LinuxI2CError) -> Option<Duration> {
+        if trial_only || !self.filter.allows(error) {
+            return None;
+        }
+        self.policy.next_delay(attempts).filter(|delay| match self.deadline {
+            Some(deadline) => self.spent + *delay < deadline,
+            None => true,
+        })
+    }
+
+    /// Records a successful transfer with the circuit breaker
+    pub(crate) fn succeeded(&mut self) {
+        if let Some(breaker) = self.breaker.as_mut() {
+            breaker.record_success();
+        }
+    }
+
+    /// Records a transfer that failed for good with the circuit breaker
+    pub(crate) fn failed(&mut self) {
+        if let Some(breaker) = self.breaker.as_mut() {
+            breaker.record_failure();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Screen, ScreenConfig, EIO, ENODEV};
+    use std::thread;
+
+    /// Records backoff waits instead of sleeping
+    #[derive(Default)]
+    struct Waits(Vec<Duration>);
+
+    impl Transport for Waits {
+        fn write_byte(&mut self, _value: u8) -> Result<(), LinuxI2CError> {
+            Ok(())
+        }
+
+        fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<(), LinuxI2CError> {
+            Ok(())
+        }
+
+        fn write_block(&mut self, _register: u8, _data: &[u8]) -> Result<(), LinuxI2CError> {
+            Ok(())
+        }
+
+        fn delay(&mut self, duration: Duration) {
+            self.0.push(duration);
+        }
+    }
+
+    fn failing(errno: i32, failures: u32) -> impl FnMut(&mut Waits) -> Result<(), LinuxI2CError> {
+        let mut remaining = failures;
+        move |_| {
+            if remaining == 0 {
+                Ok(())
+            } else {
+                remaining -= 1;
+                Err(LinuxI2CError::Errno(errno))
+            }
+        }
+    }
+
+    fn no_delay(max_retries: u32) -> FixedBackoff {
+        FixedBackoff { max_retries, delay: Duration::ZERO }
+    }
+
+    fn attempts(result: ScreenResult) -> u32 {
+        match result {
+            Err(QwiicLcdError::Transfer { attempts, .. }) => attempts,
+            other => panic!("Expected transfer error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_retry_config_backoff() {
+        let mut config = RetryConfig::default();
+        assert_eq!(config.next_delay(1), Some(Duration::from_millis(10)));
+        assert_eq!(config.next_delay(2), Some(Duration::from_millis(20)));
+        assert_eq!(config.next_delay(3), Some(Duration::from_millis(40)));
+        assert_eq!(config.next_delay(4), None);
+
+        let mut capped = RetryConfig { max_retries: 10, initial_delay_ms: 300, backoff_multiplier: 2.0, max_delay_ms: 1000 };
+        assert_eq!(capped.next_delay(3), Some(Duration::from_millis(1000)));
+    }
+
+    #[test]
+    fn test_jittered_backoff_stays_in_range() {
+        let config = RetryConfig { max_retries: 20, initial_delay_ms: 100, backoff_multiplier: 1.0, max_delay_ms: 100 };
+        let mut policy = JitteredBackoff::new(config, 0.5);
+        for retry in 1..=20 {
+            let delay = policy.next_delay(retry).unwrap();
+            assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100), "{:?}", delay);
+        }
+        assert_eq!(policy.next_delay(21), None);
+    }
+
+    #[test]
+    fn test_engine_retries_until_success() {
+        let mut engine = RetryEngine::new(RetryConfig::default());
+        engine.set_policy(Box::new(no_delay(3)));
+        assert!(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(EIO, 3)).is_ok());
+        assert_eq!(attempts(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(EIO, 4))), 4);
+    }
+
+    #[test]
+    fn test_engine_waits_through_transport() {
+        let mut engine = RetryEngine::new(RetryConfig::default());
+        engine.set_policy(Box::new(FixedBackoff { max_retries: 3, delay: Duration::from_millis(2) }));
+        let mut waits = Waits::default();
+        assert!(engine.run(&mut waits, I2COperation::WriteByte, None, &[0x41], failing(EIO, 2)).is_ok());
+        assert_eq!(waits.0, vec![Duration::from_millis(2); 2]);
+    }
+
+    #[test]
+    fn test_engine_closure_policy() {
+        let mut engine = RetryEngine::new(RetryConfig::default());
+        engine.set_policy(Box::new(|retry: u32| if retry < 2 { Some(Duration::ZERO) } else { None }));
+        assert_eq!(attempts(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(EIO, 5))), 2);
+    }
+
+    #[test]
+    fn test_engine_filters_permanent_errors() {
+        let mut engine = RetryEngine::new(RetryConfig::default());
+        engine.set_policy(Box::new(no_delay(5)));
+        assert_eq!(attempts(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(ENODEV, 5))), 1);
+
+        engine.set_filter(RetryFilter::All);
+        assert!(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(ENODEV, 5)).is_ok());
+
+        engine.set_filter(RetryFilter::Custom(Box::new(|errno| errno != Some(EIO))));
+        assert_eq!(attempts(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(EIO, 5))), 1);
+    }
+
+    #[test]
+    fn test_engine_deadline_cuts_retries() {
+        let mut engine = RetryEngine::new(RetryConfig::default());
+        engine.set_policy(Box::new(FixedBackoff { max_retries: 100, delay: Duration::from_millis(10) }));
+        engine.set_deadline(Some(Duration::from_millis(35)));
+        let mut waits = Waits::default();
+        let used = attempts(engine.run(&mut waits, I2COperation::WriteByte, None, &[0x41], failing(EIO, 100)));
+        assert_eq!(used, 4);
+        assert_eq!(waits.0, vec![Duration::from_millis(10); 3]);
+
+        // Transfers inside one operation share the deadline
+        engine.begin_operation();
+        assert!(engine.run(&mut waits, I2COperation::WriteByte, None, &[0x41], failing(EIO, 2)).is_ok());
+        assert_eq!(attempts(engine.run(&mut waits, I2COperation::WriteByte, None, &[0x42], failing(EIO, 100))), 2);
+        engine.end_operation();
+        assert_eq!(attempts(engine.run(&mut waits, I2COperation::WriteByte, None, &[0x43], failing(EIO, 100))), 4);
+    }
+
+    /// Fails every other write, waiting out backoff without sleeping
+    #[derive(Default)]
+    struct Stutter {
+        fail: bool,
+        waits: Vec<Duration>,
+    }
+
+    impl Transport for Stutter {
+        fn write_byte(&mut self, _value: u8) -> Result<(), LinuxI2CError> {
+            self.fail = !self.fail;
+            if self.fail {
+                Err(LinuxI2CError::Errno(EIO))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<(), LinuxI2CError> {
+            Ok(())
+        }
+
+        fn write_block(&mut self, _register: u8, _data: &[u8]) -> Result<(), LinuxI2CError> {
+            Ok(())
+        }
+
+        fn delay(&mut self, duration: Duration) {
+            self.waits.push(duration);
+        }
+    }
+
+    #[test]
+    fn test_deadline_covers_whole_screen_call() {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Stutter::default());
+        screen.set_retry_policy(FixedBackoff { max_retries: 5, delay: Duration::from_millis(10) });
+        screen.set_operation_deadline(Some(Duration::from_millis(35)));
+
+        // Each character needs one retry; the fourth would exceed the budget of the print
+        assert!(matches!(screen.print("abcd"), Err(QwiicLcdError::Transfer { attempts: 1, .. })));
+        let backoffs = screen.transport().waits.iter().filter(|w| **w == Duration::from_millis(10)).count();
+        assert_eq!(backoffs, 3);
+        assert_eq!(screen.framebuffer().row_text(0).trim_end(), "abc");
+
+        // The next call starts with a fresh budget
+        screen.transport_mut().fail = false;
+        screen.print("xyz").unwrap();
+    }
+
+    #[test]
+    fn test_circuit_breaker_fails_fast() {
+        let mut engine = RetryEngine::new(RetryConfig::default());
+        engine.set_policy(Box::new(no_delay(1)));
+        engine.set_circuit_breaker(Some(CircuitBreakerConfig { failure_threshold: 2, cooldown: Duration::from_millis(30) }));
+
+        assert_eq!(attempts(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(EIO, 10))), 2);
+        assert_eq!(engine.circuit_state(), CircuitState::Closed);
+        assert_eq!(attempts(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(EIO, 10))), 2);
+        assert_eq!(engine.circuit_state(), CircuitState::Open);
+
+        let mut called = false;
+        let result = engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], |_| {
+            called = true;
+            Ok(())
+        });
+        assert!(matches!(result, Err(QwiicLcdError::CircuitOpen { .. })));
+        assert!(!called);
+
+        thread::sleep(Duration::from_millis(40));
+        assert_eq!(engine.circuit_state(), CircuitState::HalfOpen);
+        // The trial gets a single attempt and reopens the circuit on failure
+        assert_eq!(attempts(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(EIO, 10))), 1);
+        assert_eq!(engine.circuit_state(), CircuitState::Open);
+
+        thread::sleep(Duration::from_millis(40));
+        assert!(engine.run(&mut Waits::default(), I2COperation::WriteByte, None, &[0x41], failing(EIO, 0)).is_ok());
+        assert_eq!(engine.circuit_state(), CircuitState::Closed);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_jittered_backoff_round_trip() {
+        let policy = JitteredBackoff::new(RetryConfig::default(), 0.25);
+        let json = serde_json::to_string(&policy).unwrap();
+        assert!(!json.contains("state"));
+        let mut back: JitteredBackoff = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+        // The reseeded policy still produces jittered delays
+        let delay = back.next_delay(1).unwrap();
+        let full = RetryConfig::default().next_delay(1).unwrap();
+        assert!(delay <= full && delay >= full.mul_f32(0.75));
+    }
+}
@@ -0,0 +1,305 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use i2cdev::linux::LinuxI2CError;
+
+use crate::{Emulator, Screen, ScreenConfig, Transport};
+
+const RESET: &str = "\x1b[0m";
+
+/// Transport that renders the emulated panel in a terminal
+///
+/// Every SMBus call is decoded by an `Emulator` and the panel is redrawn
+/// in place: a box with the configured number of rows and columns, the
+/// backlight as a 24-bit ANSI background, an underlined cursor, a blinking
+/// block when blink is on, and custom glyphs as Braille block art. A
+/// legend below the panel shows the defined glyphs at full 5x8 resolution.
+///
+/// # Example
+/// ```no_run
+/// # use qwiic_lcd_rs::*;
+/// let mut screen = Screen::simulated(ScreenConfig::default());
+/// screen.change_backlight(0, 128, 255).unwrap();
+/// screen.print("Hello from the desk").unwrap();
+/// ```
+pub struct TerminalSimulator<W: Write + Send = io::Stdout> {
+    emulator: Emulator,
+    out: W,
+    auto_render: bool,
+    show_glyphs: bool,
+    drawn_lines: usize,
+}
+
+impl TerminalSimulator<io::Stdout> {
+    /// Creates a simulator that draws to stdout
+    pub fn new(rows: u8, columns: u8) -> TerminalSimulator<io::Stdout> {
+        TerminalSimulator::with_writer(rows, columns, io::stdout())
+    }
+}
+
+impl<W: Write + Send> TerminalSimulator<W> {
+    /// Creates a simulator that draws to the given writer
+    pub fn with_writer(rows: u8, columns: u8, out: W) -> TerminalSimulator<W> {
+        TerminalSimulator {
+            emulator: Emulator::new(rows, columns),
+            out,
+            auto_render: true,
+            show_glyphs: true,
+            drawn_lines: 0,
+        }
+    }
+
+    /// The emulated panel state
+    pub fn emulator(&self) -> &Emulator {
+        &self.emulator
+    }
+
+    /// The writer the panel is drawn to
+    pub fn writer(&self) -> &W {
+        &self.out
+    }
+
+    /// Redraw after every command (default) or only on `render`
+    pub fn set_auto_render(&mut self, enabled: bool) {
+        self.auto_render = enabled;
+    }
+
+    /// Show or hide the custom glyph legend below the panel
+    pub fn set_show_glyphs(&mut self, enabled: bool) {
+        self.show_glyphs = enabled;
+    }
+
+    /// Draws the panel, replacing the previous drawing
+    pub fn render(&mut self) -> io::Result<()> {
+        let frame = render_frame(&self.emulator, self.show_glyphs);
+        let mut output = String::new();
+        if self.drawn_lines > 0 {
+            // Jump back to the top-left corner of the previous drawing
+            let _ = write!(output, "\x1b[{}A\r", self.drawn_lines);
+        }
+        for line in &frame {
+            let _ = writeln!(output, "{}\x1b[K", line);
+        }
+        self.drawn_lines = frame.len();
+        self.out.write_all(output.as_bytes())?;
+        self.out.flush()
+    }
+
+    fn after_command(&mut self) -> Result<(), LinuxI2CError> {
+        if self.auto_render {
+            self.render()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> Transport for TerminalSimulator<W> {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.emulator.write_byte(value)?;
+        self.after_command()
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.emulator.write_byte_data(register, value)?;
+        self.after_command()
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        self.emulator.write_block(register, data)?;
+        self.after_command()
+    }
+
+    fn delay(&mut self, _duration: Duration) {}
+}
+
+impl Screen<TerminalSimulator> {
+    /// Creates a Screen drawn in the terminal instead of on a real panel
+    pub fn simulated(config: ScreenConfig) -> Screen<TerminalSimulator> {
+        let simulator = TerminalSimulator::new(config.max_rows(), config.max_columns());
+        Screen::with_transport(config, simulator)
+    }
+}
+
+/// Text color: the backlight darkened according to the contrast (0 = black text)
+fn text_color(lcd: &Emulator) -> (u8, u8, u8) {
+    let (r, g, b) = lcd.backlight();
+    let scale = |c: u8| (c as u32 * lcd.contrast() as u32 / 510) as u8;
+    (scale(r), scale(g), scale(b))
+}
+
+/// Converts a cell byte to the character shown in the terminal
+fn cell_char(lcd: &Emulator, byte: u8) -> char {
+    match byte {
+        // CGRAM characters 0-7 (mirrored at 8-15)
+        0x00..=0x0F => glyph_braille(&lcd.glyph(byte as usize & 0x07)),
+        0x20..=0x7E => byte as char,
+        // Solid block in the HD44780 A00 ROM
+        0xFF => '█',
+        _ => char::from_u32(byte as u32).filter(|c| !c.is_control()).unwrap_or('?'),
+    }
+}
+
+/// Downsamples a 5x8 glyph to a single 2x4 Braille character
+fn glyph_braille(glyph: &[u8; 8]) -> char {
+    // Braille dot bits, indexed by [dot row][dot column]
+    const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    let mut bits = 0;
+    for (row, line) in glyph.iter().enumerate() {
+        for col in 0..5 {
+            if line & (0x10 >> col) != 0 {
+                bits |= DOTS[row / 2][if col < 3 { 0 } else { 1 }];
+            }
+        }
+    }
+    char::from_u32(0x2800 + bits).unwrap_or('?')
+}
+
+/// Draws the glyph legend: 8 glyphs of 5x8 pixels in 4 lines of half blocks
+fn glyph_legend(lcd: &Emulator) -> Vec<String> {
+    let mut lines = vec![String::from("glyphs "); 4];
+    for index in 0..8 {
+        let glyph = lcd.glyph(index);
+        for (line_number, line) in lines.iter_mut().enumerate() {
+            let (top, bottom) = (glyph[line_number * 2], glyph[line_number * 2 + 1]);
+            line.push(if line_number == 0 { char::from_digit(index as u32, 10).unwrap_or(' ') } else { ' ' });
+            for col in 0..5 {
+                let mask = 0x10 >> col;
+                line.push(match (top & mask != 0, bottom & mask != 0) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => '·',
+                });
+            }
+            line.push(' ');
+        }
+    }
+    lines
+}
+
+/// Renders the whole panel as lines of ANSI-styled text
+pub(crate) fn render_frame(lcd: &Emulator, show_glyphs: bool) -> Vec<String> {
+    let (br, bg, bb) = lcd.backlight();
+    let (fr, fg, fb) = text_color(lcd);
+    let width = lcd.columns() as usize;
+    let mut frame = Vec::with_capacity(lcd.rows() as usize + 6);
+
+    frame.push(format!("┌{}┐", "─".repeat(width)));
+    for row in 0..lcd.rows() as usize {
+        let mut line = String::from("│");
+        let _ = write!(line, "\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m", br, bg, bb, fr, fg, fb);
+        for col in 0..width {
+            let ch = if lcd.display_on() {
+                lcd.cell(row, col).map_or(' ', |byte| cell_char(lcd, byte))
+            } else {
+                ' '
+            };
+            let at_cursor = lcd.display_on() && lcd.cursor() == (row, col);
+            match (at_cursor && lcd.blink(), at_cursor && lcd.cursor_visible()) {
+                (true, _) => {
+                    let _ = write!(line, "\x1b[5;7m{}\x1b[25;27m", ch);
+                }
+                (false, true) => {
+                    let _ = write!(line, "\x1b[4m{}\x1b[24m", ch);
+                }
+                _ => line.push(ch),
+            }
+        }
+        line.push_str(RESET);
+        line.push('│');
+        frame.push(line);
+    }
+    frame.push(format!("└{}┘", "─".repeat(width)));
+
+    if show_glyphs {
+        frame.extend(glyph_legend(lcd));
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simulated(rows: u8, columns: u8) -> Screen<TerminalSimulator<Vec<u8>>> {
+        let simulator = TerminalSimulator::with_writer(rows, columns, Vec::new());
+        Screen::with_transport(ScreenConfig::new(rows, columns), simulator)
+    }
+
+    fn output(screen: &Screen<TerminalSimulator<Vec<u8>>>) -> String {
+        String::from_utf8(screen.transport().writer().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_render_draws_box_and_backlight() {
+        let mut screen = simulated(2, 16);
+        screen.transport_mut().set_show_glyphs(false);
+        screen.change_backlight(0, 128, 255).unwrap();
+        screen.print("Hi").unwrap();
+
+        let frame = render_frame(screen.transport().emulator(), false);
+        assert_eq!(frame.len(), 4);
+        assert_eq!(frame[0], format!("┌{}┐", "─".repeat(16)));
+        assert!(frame[1].contains("\x1b[48;2;0;128;255m"));
+        assert!(frame[1].contains("Hi"));
+        assert_eq!(frame[3], format!("└{}┘", "─".repeat(16)));
+
+        // Later draws move back up over the previous frame
+        assert!(output(&screen).contains("\x1b[4A\r"));
+    }
+
+    #[test]
+    fn test_render_cursor_and_blink() {
+        let mut screen = simulated(1, 8);
+        screen.enable_cursor(true).unwrap();
+        screen.enable_blink(false).unwrap();
+        let frame = render_frame(screen.transport().emulator(), false);
+        assert!(frame[1].contains("\x1b[4m \x1b[24m"));
+
+        screen.enable_blink(true).unwrap();
+        let frame = render_frame(screen.transport().emulator(), false);
+        assert!(frame[1].contains("\x1b[5;7m \x1b[25;27m"));
+
+        screen.enable_display(false).unwrap();
+        let frame = render_frame(screen.transport().emulator(), false);
+        assert!(!frame[1].contains("\x1b[5;7m"));
+    }
+
+    #[test]
+    fn test_render_custom_glyphs() {
+        let mut screen = simulated(1, 8);
+        screen.create_character(0, [0x1F; 8]).unwrap();
+        screen.write_byte(0).unwrap();
+
+        let frame = render_frame(screen.transport().emulator(), true);
+        assert!(frame[1].contains('⣿'));
+        assert_eq!(frame.len(), 3 + 4);
+        assert!(frame[3].starts_with("glyphs 0█████"));
+        assert!(frame[3].contains("1·····"));
+    }
+
+    #[test]
+    fn test_manual_render() {
+        let mut screen = simulated(1, 8);
+        screen.transport_mut().set_auto_render(false);
+        screen.print("quiet").unwrap();
+        assert!(output(&screen).is_empty());
+        screen.transport_mut().render().unwrap();
+        assert!(output(&screen).contains("quiet"));
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use crate::{I2COperation, ScreenResult};
+
+/// Traffic counters for one kind of I2C operation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationStats {
+    /// Operations issued, successful or not
+    pub transactions: u64,
+    /// Bytes put on the bus, including the register byte and every retry
+    pub bytes: u64,
+    /// Extra attempts made after a first failure
+    pub retries: u64,
+    /// Operations that still failed after retrying
+    pub failures: u64,
+    /// Total time spent in these operations, including retry backoff
+    pub latency: Duration,
+}
+
+impl OperationStats {
+    /// Average time per operation, or `None` if nothing was sent yet
+    pub fn average_latency(&self) -> Option<Duration> {
+        if self.transactions == 0 {
+            return None;
+        }
+        Some(Duration::from_nanos((self.latency.as_nanos() / self.transactions as u128) as u64))
+    }
+
+    fn add(&mut self, other: &OperationStats) {
+        self.transactions += other.transactions;
+        self.bytes += other.bytes;
+        self.retries += other.retries;
+        self.failures += other.failures;
+        self.latency += other.latency;
+    }
+}
+
+/// Snapshot of the I2C traffic generated by a `Screen`
+///
+/// # Example
+/// ```no_run
+/// # use qwiic_lcd_rs::*;
+/// # let mut screen = Screen::new(ScreenConfig::default(), "/dev/i2c-1", 0x72).unwrap();
+/// screen.print("Hello").unwrap();
+/// let stats = screen.stats();
+/// println!("{} bytes in {:?}", stats.total().bytes, stats.total().latency);
+/// screen.reset_stats();
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct I2CStats {
+    /// Single byte writes (characters and custom glyph rows)
+    pub write_byte: OperationStats,
+    /// Register/value writes (setting and special commands)
+    pub write_byte_data: OperationStats,
+    /// Block writes (backlight color)
+    pub write_block: OperationStats,
+}
+
+impl I2CStats {
+    /// Returns the counters for one operation type
+    pub fn get(&self, operation: I2COperation) -> &OperationStats {
+        match operation {
+            I2COperation::WriteByte => &self.write_byte,
+            I2COperation::WriteByteData => &self.write_byte_data,
+            I2COperation::WriteBlock => &self.write_block,
+        }
+    }
+
+    /// Sums the counters of every operation type
+    pub fn total(&self) -> OperationStats {
+        let mut total = OperationStats::default();
+        total.add(&self.write_byte);
+        total.add(&self.write_byte_data);
+        total.add(&self.write_block);
+        total
+    }
+
+    /// Records one operation that took `attempts` tries
+    pub(crate) fn record(
+        &mut self,
+        operation: I2COperation,
+        wire_bytes: usize,
+        attempts: u32,
+        latency: Duration,
+        failed: bool,
+    ) {
+        let stats = match operation {
+            I2COperation::WriteByte => &mut self.write_byte,
+            I2COperation::WriteByteData => &mut self.write_byte_data,
+            I2COperation::WriteBlock => &mut self.write_block,
+        };
+        stats.transactions += 1;
+        stats.bytes += wire_bytes as u64 * attempts as u64;
+        stats.retries += attempts.saturating_sub(1) as u64;
+        stats.failures += failed as u64;
+        stats.latency += latency;
+    }
+}
+
+/// Emits a per-transaction event when the `log` or `tracing` feature is enabled
+#[allow(unused_variables)]
+pub(crate) fn trace_transaction(
+    operation: I2COperation,
+    register: Option<u8>,
+    bytes: &[u8],
+    attempts: u32,
+    latency: Duration,
+    result: &ScreenResult,
+) {
+    #[cfg(feature = "log")]
+    match result {
+        Ok(()) => log::trace!(
+            "i2c {} register={:02X?} bytes={:02X?} attempts={} latency={:?}",
+            operation,
+            register,
+            bytes,
+            attempts,
+            latency
+        ),
+        Err(e) => log::warn!(
+            "i2c {} register={:02X?} bytes={:02X?} attempts={} latency={:?} failed: {}",
+            operation,
+            register,
+            bytes,
+            attempts,
+            latency,
+            e
+        ),
+    };
+
+    #[cfg(feature = "tracing")]
+    match result {
+        Ok(()) => tracing::trace!(
+            operation = %operation,
+            register = ?register,
+            bytes = ?bytes,
+            attempts,
+            latency_us = latency.as_micros() as u64,
+            "i2c transaction"
+        ),
+        Err(e) => tracing::warn!(
+            operation = %operation,
+            register = ?register,
+            bytes = ?bytes,
+            attempts,
+            latency_us = latency.as_micros() as u64,
+            error = %e,
+            "i2c transaction failed"
+        ),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_counts_per_operation() {
+        let mut stats = I2CStats::default();
+        stats.record(I2COperation::WriteByte, 1, 1, Duration::from_micros(100), false);
+        stats.record(I2COperation::WriteByte, 1, 3, Duration::from_micros(500), false);
+        stats.record(I2COperation::WriteBlock, 5, 4, Duration::from_millis(2), true);
+
+        assert_eq!(stats.write_byte.transactions, 2);
+        assert_eq!(stats.write_byte.bytes, 4);
+        assert_eq!(stats.write_byte.retries, 2);
+        assert_eq!(stats.write_byte.failures, 0);
+        assert_eq!(stats.write_byte.average_latency(), Some(Duration::from_micros(300)));
+
+        let block = stats.get(I2COperation::WriteBlock);
+        assert_eq!(block.transactions, 1);
+        assert_eq!(block.bytes, 20);
+        assert_eq!(block.retries, 3);
+        assert_eq!(block.failures, 1);
+
+        assert_eq!(stats.write_byte_data, OperationStats::default());
+        assert_eq!(stats.write_byte_data.average_latency(), None);
+
+        // Counts past u32::MAX used to truncate to zero and divide by it
+        let long_running = OperationStats { transactions: 1 << 32, latency: Duration::from_secs(1 << 32), ..Default::default() };
+        assert_eq!(long_running.average_latency(), Some(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_total_sums_operations() {
+        let mut stats = I2CStats::default();
+        stats.record(I2COperation::WriteByte, 1, 1, Duration::from_micros(10), false);
+        stats.record(I2COperation::WriteByteData, 2, 2, Duration::from_micros(20), false);
+        stats.record(I2COperation::WriteBlock, 5, 1, Duration::from_micros(30), true);
+
+        let total = stats.total();
+        assert_eq!(total.transactions, 3);
+        assert_eq!(total.bytes, 10);
+        assert_eq!(total.retries, 1);
+        assert_eq!(total.failures, 1);
+        assert_eq!(total.latency, Duration::from_micros(60));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut stats = I2CStats::default();
+        stats.record(I2COperation::WriteByteData, 2, 2, Duration::from_micros(250), true);
+        let json = serde_json::to_string(&stats).unwrap();
+        assert_eq!(serde_json::from_str::<I2CStats>(&json).unwrap(), stats);
+        let total = stats.total();
+        assert_eq!(serde_json::from_str::<OperationStats>(&serde_json::to_string(&total).unwrap()).unwrap(), total);
+    }
+}
@@ -0,0 +1,439 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::{FrameBuffer, QwiicLcdError, Screen, Transport};
+
+/// Error compiling a `Template`; positions are 0-based (row, col) in the source
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{` without its closing `}`
+    Unclosed { row: usize, col: usize },
+    /// A `}` that doesn't close a field; write `}}` for a literal brace
+    UnmatchedBrace { row: usize, col: usize },
+    /// A field without a name
+    EmptyName { row: usize, col: usize },
+    /// A format spec that isn't `[[fill]align][0][width][.precision]`
+    InvalidSpec { row: usize, col: usize, spec: String },
+    /// The row is wider than the panel once fields take their width
+    TooWide { row: usize, col: usize, max_columns: u8 },
+    /// The template has more rows than the panel
+    TooManyRows { rows: usize, max_rows: u8 },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::Unclosed { row, col } => write!(f, "Unclosed field at row {}, column {}", row, col),
+            TemplateError::UnmatchedBrace { row, col } => write!(f, "Unmatched '}}' at row {}, column {}", row, col),
+            TemplateError::EmptyName { row, col } => write!(f, "Field without a name at row {}, column {}", row, col),
+            TemplateError::InvalidSpec { row, col, spec } => {
+                write!(f, "Invalid format spec '{}' at row {}, column {}", spec, row, col)
+            }
+            TemplateError::TooWide { row, col, max_columns } => {
+                write!(f, "Row {} is wider than {} columns at column {}", row, max_columns, col)
+            }
+            TemplateError::TooManyRows { rows, max_rows } => {
+                write!(f, "Template has {} rows but the panel only {}", rows, max_rows)
+            }
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+/// Value bound to a template field
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+macro_rules! int_value {
+    ($($ty:ty),*) => {
+        $(impl From<$ty> for FieldValue {
+            fn from(value: $ty) -> Self {
+                FieldValue::Int(value as i64)
+            }
+        })*
+    };
+}
+
+int_value!(i8, i16, i32, i64, u8, u16, u32, usize);
+
+impl From<f32> for FieldValue {
+    fn from(value: f32) -> Self {
+        FieldValue::Float(value as f64)
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        FieldValue::Float(value)
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        FieldValue::Text(value)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Field {
+    name: String,
+    row: usize,
+    col: usize,
+    width: usize,
+    fill: char,
+    align: Option<Align>,
+    zero: bool,
+    precision: Option<usize>,
+}
+
+impl Field {
+    /// Formats `value` to exactly `width` characters
+    ///
+    /// Numbers too wide for the field show as `#`s rather than a
+    /// misleading truncated value; text is cut.
+    pub(crate) fn format(&self, value: Option<&FieldValue>) -> String {
+        let text = match value {
+            None => return " ".repeat(self.width),
+            Some(FieldValue::Int(v)) if self.zero => format!("{:0width$}", v, width = self.width),
+            Some(FieldValue::Float(v)) if self.zero => match self.precision {
+                Some(p) => format!("{:0width$.p$}", v, width = self.width, p = p),
+                None => format!("{:0width$}", v, width = self.width),
+            },
+            Some(FieldValue::Int(v)) => v.to_string(),
+            Some(FieldValue::Float(v)) => match self.precision {
+                Some(p) => format!("{:.p$}", v, p = p),
+                None => v.to_string(),
+            },
+            Some(FieldValue::Text(s)) => match self.precision {
+                Some(p) => s.chars().take(p).collect(),
+                None => s.clone(),
+            },
+        };
+        let numeric = !matches!(value, Some(FieldValue::Text(_)));
+        let len = text.chars().count();
+        if len > self.width {
+            return match numeric {
+                true => "#".repeat(self.width),
+                false => text.chars().take(self.width).collect(),
+            };
+        }
+        let pad = self.width - len;
+        let align = self.align.unwrap_or(if numeric { Align::Right } else { Align::Left });
+        let (before, after) = match align {
+            Align::Left => (0, pad),
+            Align::Right => (pad, 0),
+            Align::Center => (pad / 2, pad - pad / 2),
+        };
+        let fill = |n: usize| std::iter::repeat_n(self.fill, n);
+        fill(before).chain(text.chars()).chain(fill(after)).collect()
+    }
+}
+
+/// Screen layout with named fields, compiled to fixed positions
+///
+/// Each line of the source is a row. `{name}` or `{name:spec}` places a
+/// field, with the spec following Rust's `[[fill]align][0][width][.precision]`:
+/// `{cpu:>3}`, `{temp:.1}`, `{host:^10}`, `{count:05}`. A field is `width`
+/// cells wide, or as wide as its placeholder when no width is given, so
+/// the source reads like the screen. `{{` and `}}` are literal braces.
+///
+/// Setting a value only reformats the fields bound to it, and `render`
+/// sends the panel only the cells that changed, so updating one number
+/// costs a cursor move and a few bytes instead of reprinting the row.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let mut status = Template::compile(2, 20, "CPU {cpu:>3}% {temp:.1}C\nUp {uptime}").unwrap();
+/// status.set("cpu", 7);
+/// status.set("temp", 48.3);
+/// assert_eq!(status.framebuffer().row_text(0), "CPU   7%      48.3C ");
+///
+/// let mut screen = Screen::with_transport(ScreenConfig::new(2, 20), Emulator::new(2, 20));
+/// status.render(&mut screen).unwrap();
+/// assert_eq!(screen.transport().row_text(0), "CPU   7%      48.3C ");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Template {
+    fields: Vec<Field>,
+    values: BTreeMap<String, FieldValue>,
+    frame: FrameBuffer,
+}
+
+impl Template {
+    /// Compiles `source` for a panel of `rows` x `columns`
+    pub fn compile(rows: u8, columns: u8, source: &str) -> Result<Template, TemplateError> {
+        let lines: Vec<&str> = source.lines().collect();
+        if lines.len() > rows as usize {
+            return Err(TemplateError::TooManyRows { rows: lines.len(), max_rows: rows });
+        }
+        let mut frame = FrameBuffer::new(rows, columns);
+        let mut fields = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            let mut col = 0;
+            let mut i = 0;
+            while i < chars.len() {
+                let literal = match (chars[i], chars.get(i + 1)) {
+                    ('{', Some('{')) | ('}', Some('}')) => {
+                        i += 1;
+                        Some(chars[i])
+                    }
+                    ('}', _) => return Err(TemplateError::UnmatchedBrace { row, col: i }),
+                    ('{', _) => None,
+                    (c, _) => Some(c),
+                };
+                match literal {
+                    Some(c) => {
+                        if col >= columns as usize {
+                            return Err(TemplateError::TooWide { row, col: i, max_columns: columns });
+                        }
+                        frame.set(row, col, u8::try_from(c).unwrap_or(b'?'));
+                        col += 1;
+                        i += 1;
+                    }
+                    None => {
+                        let end = chars[i..]
+                            .iter()
+                            .position(|c| *c == '}')
+                            .map(|offset| i + offset)
+                            .ok_or(TemplateError::Unclosed { row, col: i })?;
+                        let body: String = chars[i + 1..end].iter().collect();
+                        let mut field = parse_field(&body, row, col).map_err(|spec| match spec {
+                            None => TemplateError::EmptyName { row, col: i },
+                            Some(spec) => TemplateError::InvalidSpec { row, col: i, spec },
+                        })?;
+                        if field.width == 0 {
+                            field.width = end + 1 - i;
+                        }
+                        if col + field.width > columns as usize {
+                            return Err(TemplateError::TooWide { row, col: i, max_columns: columns });
+                        }
+                        col += field.width;
+                        fields.push(field);
+                        i = end + 1;
+                    }
+                }
+            }
+        }
+
+        let mut template = Template { fields, values: BTreeMap::new(), frame };
+        for index in 0..template.fields.len() {
+            template.draw(index);
+        }
+        Ok(template)
+    }
+
+    /// Names of the fields, in the order they appear
+    pub fn fields(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for field in &self.fields {
+            if !names.contains(&field.name.as_str()) {
+                names.push(&field.name);
+            }
+        }
+        names
+    }
+
+    /// Binds a value, reformatting only the fields that show it
+    ///
+    /// Returns `false` when no field has that name.
+    pub fn set<V: Into<FieldValue>>(&mut self, name: &str, value: V) -> bool {
+        let value = value.into();
+        if !self.fields.iter().any(|field| field.name == name) {
+            return false;
+        }
+        if self.values.get(name) != Some(&value) {
+            self.values.insert(name.to_string(), value);
+            for index in 0..self.fields.len() {
+                if self.fields[index].name == name {
+                    self.draw(index);
+                }
+            }
+        }
+        true
+    }
+
+    /// Value bound to a field
+    pub fn get(&self, name: &str) -> Option<&FieldValue> {
+        self.values.get(name)
+    }
+
+    /// The rendered layout
+    pub fn framebuffer(&self) -> &FrameBuffer {
+        &self.frame
+    }
+
+    /// Shows the layout on `screen`, sending only the cells that changed
+    pub fn render<T: Transport>(&self, screen: &mut Screen<T>) -> Result<(), QwiicLcdError> {
+        screen.present(&self.frame)
+    }
+
+    fn draw(&mut self, index: usize) {
+        let field = &self.fields[index];
+        let text = field.format(self.values.get(&field.name));
+        for (i, c) in text.chars().enumerate() {
+            self.frame.set(field.row, field.col + i, u8::try_from(c).unwrap_or(b'?'));
+        }
+    }
+}
+
+/// Parses a standalone format spec for a field `width` cells wide
+#[cfg(any(feature = "toml", feature = "yaml"))]
+pub(crate) fn field_spec(spec: &str, width: usize) -> Option<Field> {
+    let mut field = parse_field(&format!("_:{}", spec), 0, 0).ok()?;
+    field.width = width;
+    Some(field)
+}
+
+/// Parses `name[:spec]`; `Err(None)` for a missing name, `Err(Some(spec))` for a bad spec
+fn parse_field(body: &str, row: usize, col: usize) -> Result<Field, Option<String>> {
+    let (name, spec) = match body.split_once(':') {
+        Some((name, spec)) => (name.trim(), spec),
+        None => (body.trim(), ""),
+    };
+    if name.is_empty() {
+        return Err(None);
+    }
+    let invalid = || Some(spec.to_string());
+    let align_of = |c: char| match c {
+        '<' => Some(Align::Left),
+        '>' => Some(Align::Right),
+        '^' => Some(Align::Center),
+        _ => None,
+    };
+
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+    let mut fill = ' ';
+    let mut align = None;
+    if let Some(a) = chars.get(1).copied().and_then(align_of) {
+        fill = chars[0];
+        align = Some(a);
+        i = 2;
+    } else if let Some(a) = chars.first().copied().and_then(align_of) {
+        align = Some(a);
+        i = 1;
+    }
+    let zero = chars.get(i) == Some(&'0');
+    if zero {
+        i += 1;
+    }
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>().parse::<usize>().ok()
+    };
+    let width = digits(&mut i).unwrap_or(0);
+    let precision = match chars.get(i) {
+        Some('.') => {
+            i += 1;
+            Some(digits(&mut i).ok_or_else(invalid)?)
+        }
+        _ => None,
+    };
+    if i != chars.len() || (zero && width == 0) {
+        return Err(invalid());
+    }
+    Ok(Field { name: name.to_string(), row, col, width, fill, align, zero, precision })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+
+    /// Text of a single field compiled from `spec`
+    fn format(spec: &str, value: impl Into<FieldValue>) -> String {
+        let mut template = Template::compile(1, 20, &format!("{{v:{}}}", spec)).unwrap();
+        template.set("v", value);
+        template.framebuffer().row_text(0)[..template.fields[0].width].to_string()
+    }
+
+    #[test]
+    fn test_format_specs() {
+        assert_eq!(format(">5", 42), "   42");
+        assert_eq!(format("<5", 42), "42   ");
+        assert_eq!(format("*^7", "ab"), "**ab***");
+        assert_eq!(format("05", -7), "-0007");
+        assert_eq!(format("6.2", 1.23456), "  1.23");
+        assert_eq!(format("3", 1234), "###");
+        assert_eq!(format("4.2", "abcdef"), "ab  ");
+        // Without a width, the field is as wide as its placeholder
+        assert_eq!(format(".1", 21.56), "  21.6");
+    }
+
+    #[test]
+    fn test_compile_errors_point_at_source() {
+        assert_eq!(Template::compile(2, 16, "CPU {cpu").unwrap_err(), TemplateError::Unclosed { row: 0, col: 4 });
+        assert_eq!(
+            Template::compile(2, 16, "ok\nT {temp:x}").unwrap_err(),
+            TemplateError::InvalidSpec { row: 1, col: 2, spec: "x".to_string() }
+        );
+        assert_eq!(
+            Template::compile(2, 16, "Load {load:>12}").unwrap_err(),
+            TemplateError::TooWide { row: 0, col: 5, max_columns: 16 }
+        );
+        assert_eq!(Template::compile(1, 16, "a\nb").unwrap_err(), TemplateError::TooManyRows { rows: 2, max_rows: 1 });
+        assert_eq!(Template::compile(1, 16, "a } b").unwrap_err(), TemplateError::UnmatchedBrace { row: 0, col: 2 });
+        assert_eq!(Template::compile(1, 16, "{{{n:2}}}").unwrap().framebuffer().row_text(0), "{  }            ");
+    }
+
+    #[test]
+    fn test_updates_only_send_changed_cells() {
+        let mut template = Template::compile(2, 20, "CPU {cpu:>3}% {temp:5.1}C\nHost {host:<15}").unwrap();
+        assert_eq!(template.fields(), ["cpu", "temp", "host"]);
+        assert!(!template.set("fan", 1));
+        template.set("cpu", 12);
+        template.set("temp", 41.0);
+        template.set("host", "gateway");
+
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 20), Emulator::new(2, 20));
+        template.render(&mut screen).unwrap();
+        assert_eq!(screen.transport().row_text(0), "CPU  12%  41.0C     ");
+        assert_eq!(screen.transport().row_text(1), "Host gateway        ");
+
+        let before = screen.stats().total().bytes;
+        template.set("cpu", 13);
+        template.set("host", "gateway");
+        template.render(&mut screen).unwrap();
+        assert_eq!(screen.transport().row_text(0), "CPU  13%  41.0C     ");
+        // One cursor move plus a single character
+        assert!(screen.stats().total().bytes - before <= 4, "{}", screen.stats().total().bytes - before);
+    }
+}
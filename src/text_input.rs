@@ -0,0 +1,340 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{FrameBuffer, Input, QwiicLcdError, Screen, Transport};
+
+type Validator = Box<dyn Fn(&str) -> Result<(), String> + Send>;
+
+/// Group of characters offered by `TextInput`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharSet {
+    Upper,
+    Lower,
+    Digits,
+    Symbols,
+}
+
+impl CharSet {
+    /// Characters of the set, in cycling order
+    ///
+    /// Symbols leave out `\` and `~`, which the HD44780 ROM shows as a yen
+    /// sign and an arrow.
+    pub fn chars(self) -> &'static str {
+        match self {
+            CharSet::Upper => "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+            CharSet::Lower => "abcdefghijklmnopqrstuvwxyz",
+            CharSet::Digits => "0123456789",
+            CharSet::Symbols => " !\"#$%&'()*+,-./:;<=>?@[]^_`{|}",
+        }
+    }
+}
+
+/// What a `TextInput` did with an input
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextInputEvent {
+    /// The input had no effect
+    Ignored,
+    /// The value, cursor or character set changed
+    Edited,
+    /// The value passed validation and was accepted
+    Confirmed(String),
+    /// The validation hook rejected the value (its message)
+    Invalid(String),
+    /// Editing was abandoned
+    Cancelled,
+}
+
+/// Character-by-character text entry for a handful of buttons
+///
+/// The blinking cursor sits on the character being edited:
+///
+/// * `Up`/`Down` cycle that character through the active set
+/// * `Left`/`Right` move the cursor; `Right` past the end adds a character
+/// * `Select` switches to the next set (upper, lower, digits, symbols)
+/// * `Back` deletes the character under the cursor, or the last one at the
+///   end; on an empty value it cancels
+///
+/// Holding `Select` confirms and holding `Back` cancels (`handle_hold`).
+/// The value is limited to `max_len` characters and scrolls horizontally
+/// when it is wider than the field.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let mut input = TextInput::new(1, 0, 16, 32);
+/// for key in [Input::Up, Input::Right, Input::Select, Input::Up, Input::Up] {
+///     input.handle(key);
+/// }
+/// assert_eq!(input.value(), "Ab");
+/// assert_eq!(input.handle_hold(Input::Select), TextInputEvent::Confirmed("Ab".to_string()));
+/// ```
+pub struct TextInput {
+    value: Vec<char>,
+    position: usize,
+    scroll: usize,
+    charsets: Vec<CharSet>,
+    charset: usize,
+    max_len: usize,
+    row: usize,
+    col: usize,
+    width: usize,
+    validator: Option<Validator>,
+    cursor_shown: bool,
+}
+
+impl TextInput {
+    /// Creates an empty field `width` cells wide at (row, col), holding up to `max_len` characters
+    pub fn new(row: usize, col: usize, width: usize, max_len: usize) -> TextInput {
+        TextInput {
+            value: Vec::new(),
+            position: 0,
+            scroll: 0,
+            charsets: vec![CharSet::Upper, CharSet::Lower, CharSet::Digits, CharSet::Symbols],
+            charset: 0,
+            max_len: max_len.max(1),
+            row,
+            col,
+            width: width.max(1),
+            validator: None,
+            cursor_shown: false,
+        }
+    }
+
+    /// Starts from an existing value, with the cursor after its end
+    pub fn with_value(mut self, value: &str) -> TextInput {
+        self.value = value.chars().take(self.max_len).collect();
+        self.position = self.value.len().min(self.max_len - 1);
+        self.follow();
+        self
+    }
+
+    /// Restricts the character sets offered, in switching order
+    pub fn with_charsets(mut self, charsets: &[CharSet]) -> TextInput {
+        if !charsets.is_empty() {
+            self.charsets = charsets.to_vec();
+            self.charset = 0;
+        }
+        self
+    }
+
+    /// Checks the value on confirm; an `Err` message keeps the field open
+    pub fn with_validator<F>(mut self, validator: F) -> TextInput
+    where
+        F: Fn(&str) -> Result<(), String> + Send + 'static,
+    {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Current value
+    pub fn value(&self) -> String {
+        self.value.iter().collect()
+    }
+
+    /// Active character set
+    pub fn charset(&self) -> CharSet {
+        self.charsets[self.charset]
+    }
+
+    /// Display position of the edited character, as (row, col)
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.col + self.position - self.scroll)
+    }
+
+    /// Handles a short press
+    pub fn handle(&mut self, input: Input) -> TextInputEvent {
+        let event = match input {
+            Input::Up | Input::Down => self.cycle(input == Input::Up),
+            Input::Left if self.position > 0 => {
+                self.position -= 1;
+                TextInputEvent::Edited
+            }
+            Input::Right if self.position < self.value.len() && self.position + 1 < self.max_len => {
+                self.position += 1;
+                TextInputEvent::Edited
+            }
+            Input::Select => {
+                self.charset = (self.charset + 1) % self.charsets.len();
+                let replacement = first(self.charset());
+                if let Some(c) = self.value.get_mut(self.position) {
+                    *c = replacement;
+                }
+                TextInputEvent::Edited
+            }
+            Input::Back => self.backspace(),
+            _ => TextInputEvent::Ignored,
+        };
+        self.follow();
+        event
+    }
+
+    /// Handles a long press: `Select` confirms, `Back` cancels, anything else acts as a short press
+    pub fn handle_hold(&mut self, input: Input) -> TextInputEvent {
+        match input {
+            Input::Select => self.confirm(),
+            Input::Back => TextInputEvent::Cancelled,
+            _ => self.handle(input),
+        }
+    }
+
+    /// Runs the validation hook and accepts the value if it passes
+    pub fn confirm(&mut self) -> TextInputEvent {
+        let value = self.value();
+        match self.validator.as_ref().map_or(Ok(()), |validator| validator(&value)) {
+            Ok(()) => TextInputEvent::Confirmed(value),
+            Err(message) => TextInputEvent::Invalid(message),
+        }
+    }
+
+    /// Draws the visible part of the value into `frame`
+    pub fn render_to(&self, frame: &mut FrameBuffer) {
+        for i in 0..self.width {
+            let c = self.value.get(self.scroll + i).copied().unwrap_or(' ');
+            frame.set(self.row, self.col + i, u8::try_from(c).unwrap_or(b'?'));
+        }
+    }
+
+    /// Draws the field on `screen` and puts the blinking cursor on the edited character
+    pub fn render<T: Transport>(&mut self, screen: &mut Screen<T>) -> Result<(), QwiicLcdError> {
+        let mut frame = screen.framebuffer().clone();
+        self.render_to(&mut frame);
+        screen.present(&frame)?;
+        if !self.cursor_shown {
+            screen.enable_cursor(true)?;
+            screen.enable_blink(true)?;
+            self.cursor_shown = true;
+        }
+        let (row, col) = self.cursor();
+        screen.move_cursor(row, col)
+    }
+
+    /// Turns the cursor off again once editing is over
+    pub fn hide_cursor<T: Transport>(&mut self, screen: &mut Screen<T>) -> Result<(), QwiicLcdError> {
+        screen.enable_cursor(false)?;
+        screen.enable_blink(false)?;
+        self.cursor_shown = false;
+        Ok(())
+    }
+
+    /// Changes the character under the cursor, adding one at the end
+    fn cycle(&mut self, forward: bool) -> TextInputEvent {
+        let chars: Vec<char> = self.charset().chars().chars().collect();
+        let full = self.value.len() >= self.max_len;
+        match self.value.get_mut(self.position) {
+            Some(c) => {
+                let len = chars.len();
+                *c = match chars.iter().position(|candidate| candidate == c) {
+                    Some(i) if forward => chars[(i + 1) % len],
+                    Some(i) => chars[(i + len - 1) % len],
+                    None => chars[0],
+                };
+            }
+            None if !full => {
+                self.value.push(if forward { chars[0] } else { chars[chars.len() - 1] });
+            }
+            None => return TextInputEvent::Ignored,
+        }
+        TextInputEvent::Edited
+    }
+
+    fn backspace(&mut self) -> TextInputEvent {
+        if self.value.is_empty() {
+            return TextInputEvent::Cancelled;
+        }
+        if self.position < self.value.len() {
+            self.value.remove(self.position);
+        } else {
+            self.value.pop();
+            self.position = self.value.len();
+        }
+        TextInputEvent::Edited
+    }
+
+    /// Scrolls so the cursor stays within the field
+    fn follow(&mut self) {
+        if self.position < self.scroll {
+            self.scroll = self.position;
+        } else if self.position >= self.scroll + self.width {
+            self.scroll = self.position + 1 - self.width;
+        }
+    }
+}
+
+fn first(charset: CharSet) -> char {
+    charset.chars().chars().next().unwrap_or(' ')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+
+    fn feed(input: &mut TextInput, keys: &[Input]) {
+        for key in keys {
+            input.handle(*key);
+        }
+    }
+
+    #[test]
+    fn test_charsets_backspace_and_validation() {
+        let mut input = TextInput::new(0, 0, 16, 4)
+            .with_validator(|value| if value.len() >= 3 { Ok(()) } else { Err("too short".to_string()) });
+        feed(&mut input, &[Input::Down, Input::Right, Input::Select, Input::Select, Input::Up]);
+        assert_eq!(input.value(), "Z0");
+        assert_eq!(input.charset(), CharSet::Digits);
+        assert_eq!(input.handle_hold(Input::Select), TextInputEvent::Invalid("too short".to_string()));
+
+        feed(&mut input, &[Input::Right, Input::Up, Input::Right, Input::Up]);
+        assert_eq!(input.value(), "Z000");
+        assert_eq!(input.handle(Input::Right), TextInputEvent::Ignored);
+
+        feed(&mut input, &[Input::Left, Input::Back]);
+        assert_eq!(input.value(), "Z00");
+        assert_eq!(input.handle_hold(Input::Select), TextInputEvent::Confirmed("Z00".to_string()));
+
+        feed(&mut input, &[Input::Back, Input::Back, Input::Back]);
+        assert_eq!(input.value(), "");
+        assert_eq!(input.handle(Input::Back), TextInputEvent::Cancelled);
+    }
+
+    #[test]
+    fn test_scrolls_long_values() {
+        let mut input = TextInput::new(1, 10, 6, 32).with_value("correct-horse");
+        assert_eq!(input.cursor(), (1, 15));
+        let mut frame = FrameBuffer::new(2, 16);
+        input.render_to(&mut frame);
+        assert_eq!(frame.row_text(1), "          horse ");
+
+        for _ in 0..10 {
+            input.handle(Input::Left);
+        }
+        assert_eq!(input.cursor(), (1, 10));
+        input.render_to(&mut frame);
+        assert_eq!(frame.row_text(1), "          rect-h");
+    }
+
+    #[test]
+    fn test_render_shows_blinking_cursor() {
+        let mut screen = Screen::with_transport(ScreenConfig::new(2, 16), Emulator::new(2, 16));
+        screen.print("SSID:").unwrap();
+        let mut input = TextInput::new(0, 6, 10, 32).with_value("home");
+        input.render(&mut screen).unwrap();
+        assert_eq!(screen.transport().row_text(0), "SSID: home      ");
+        assert_eq!(screen.transport().cursor(), (0, 10));
+        assert!(screen.transport().cursor_visible() && screen.transport().blink());
+
+        input.hide_cursor(&mut screen).unwrap();
+        assert!(!screen.transport().cursor_visible());
+    }
+}
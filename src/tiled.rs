@@ -0,0 +1,297 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{FrameBuffer, LinuxTransport, QwiicLcdError, Screen, Transport};
+
+type ScreenResult = Result<(), QwiicLcdError>;
+
+struct Tile<T: Transport> {
+    row: usize,
+    col: usize,
+    screen: Screen<T>,
+}
+
+/// One large display made of several panels
+///
+/// Panels are laid out as a grid: each inner `Vec` of `grid` is a band of
+/// panels placed left to right, and bands are stacked top to bottom. Two
+/// 20x4 panels side by side make a 40x4 surface, stacked they make 20x8.
+/// The tiled display keeps a framebuffer of the whole surface and sends
+/// every panel only the cells that changed in its part. Printing wraps at
+/// the end of a virtual row, so text flows across panels.
+///
+/// Settings that affect the look of the panels (backlight, contrast,
+/// custom glyphs, display on/off) are applied to every panel so they stay
+/// in sync.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let panel = || Screen::with_transport(ScreenConfig::new(4, 20), Emulator::new(4, 20));
+/// let mut wide = TiledDisplay::grid(vec![vec![panel(), panel()]]);
+/// assert_eq!((wide.rows(), wide.columns()), (4, 40));
+///
+/// wide.move_cursor(0, 16).unwrap();
+/// wide.print("spanning").unwrap();
+/// assert_eq!(wide.panel(0).unwrap().transport().row_text(0), "                span");
+/// assert_eq!(wide.panel(1).unwrap().transport().row_text(0), "ning                ");
+/// ```
+pub struct TiledDisplay<T: Transport = LinuxTransport> {
+    tiles: Vec<Tile<T>>,
+    frame: FrameBuffer,
+}
+
+impl<T: Transport> TiledDisplay<T> {
+    /// Builds a display from bands of panels (left to right, top to bottom)
+    ///
+    /// A band is as tall as its tallest panel; the surface is as wide as the
+    /// widest band. Cells not covered by any panel are kept in the
+    /// framebuffer but never shown.
+    pub fn grid(grid: Vec<Vec<Screen<T>>>) -> TiledDisplay<T> {
+        let mut tiles = Vec::new();
+        let (mut height, mut width) = (0, 0);
+        for band in grid {
+            let mut col = 0;
+            let mut band_height = 0;
+            for screen in band {
+                let (rows, columns) = (screen.framebuffer().rows() as usize, screen.framebuffer().columns() as usize);
+                tiles.push(Tile { row: height, col, screen });
+                col += columns;
+                band_height = band_height.max(rows);
+            }
+            width = width.max(col);
+            height += band_height;
+        }
+        TiledDisplay {
+            tiles,
+            frame: FrameBuffer::new(height.min(u8::MAX as usize) as u8, width.min(u8::MAX as usize) as u8),
+        }
+    }
+
+    /// Places panels left to right
+    pub fn horizontal(panels: Vec<Screen<T>>) -> TiledDisplay<T> {
+        TiledDisplay::grid(vec![panels])
+    }
+
+    /// Stacks panels top to bottom
+    pub fn vertical(panels: Vec<Screen<T>>) -> TiledDisplay<T> {
+        TiledDisplay::grid(panels.into_iter().map(|panel| vec![panel]).collect())
+    }
+
+    /// Rows of the whole surface
+    pub fn rows(&self) -> u8 {
+        self.frame.rows()
+    }
+
+    /// Columns of the whole surface
+    pub fn columns(&self) -> u8 {
+        self.frame.columns()
+    }
+
+    /// Returns the tracked contents of the whole surface
+    pub fn framebuffer(&self) -> &FrameBuffer {
+        &self.frame
+    }
+
+    /// Number of panels
+    pub fn panel_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns a panel, in layout order
+    pub fn panel(&self, index: usize) -> Option<&Screen<T>> {
+        self.tiles.get(index).map(|tile| &tile.screen)
+    }
+
+    /// Returns a panel mutably, in layout order
+    ///
+    /// Text written directly to a panel is not reflected in the surface
+    /// framebuffer and may be overwritten by the next update.
+    pub fn panel_mut(&mut self, index: usize) -> Option<&mut Screen<T>> {
+        self.tiles.get_mut(index).map(|tile| &mut tile.screen)
+    }
+
+    /// Position of a panel's top left cell on the surface
+    pub fn panel_origin(&self, index: usize) -> Option<(usize, usize)> {
+        self.tiles.get(index).map(|tile| (tile.row, tile.col))
+    }
+
+    /// Consumes the display and returns the panels
+    pub fn into_panels(self) -> Vec<Screen<T>> {
+        self.tiles.into_iter().map(|tile| tile.screen).collect()
+    }
+
+    /// Initializes every panel and blanks the surface
+    pub fn init(&mut self) -> ScreenResult {
+        self.frame.clear();
+        self.each(|screen| screen.init())
+    }
+
+    /// Clears the whole surface and homes the cursor
+    pub fn clear(&mut self) -> ScreenResult {
+        self.frame.clear();
+        self.each(|screen| screen.clear())
+    }
+
+    /// Moves the virtual cursor to (row, col) of the surface
+    pub fn move_cursor(&mut self, row: usize, col: usize) -> ScreenResult {
+        if row >= self.rows() as usize || col >= self.columns() as usize {
+            return Err(QwiicLcdError::InvalidPosition {
+                row,
+                col,
+                max_rows: self.rows(),
+                max_columns: self.columns(),
+            });
+        }
+        self.frame.set_cursor(row, col);
+        Ok(())
+    }
+
+    /// Moves the virtual cursor to the top left corner
+    pub fn home(&mut self) -> ScreenResult {
+        self.frame.home();
+        Ok(())
+    }
+
+    /// Prints a string at the cursor, with the same character mapping as `Screen::print`
+    pub fn print(&mut self, s: &str) -> ScreenResult {
+        let bytes: Vec<u8> = match self.tiles.first() {
+            Some(tile) => s.chars().map(|c| tile.screen.map_character(c)).collect(),
+            None => return Ok(()),
+        };
+        self.print_bytes(&bytes)
+    }
+
+    /// Writes raw bytes (custom glyphs 0-7, ROM characters) at the cursor
+    pub fn print_bytes(&mut self, bytes: &[u8]) -> ScreenResult {
+        for byte in bytes {
+            self.frame.write(*byte);
+        }
+        self.flush()
+    }
+
+    /// Shows `frame` on the surface, sending each panel only its changed cells
+    pub fn present(&mut self, frame: &FrameBuffer) -> ScreenResult {
+        for row in 0..self.rows().min(frame.rows()) as usize {
+            for (col, byte) in frame.row(row).iter().enumerate() {
+                self.frame.set(row, col, *byte);
+            }
+        }
+        self.flush()
+    }
+
+    /// Changes the backlight of every panel
+    pub fn change_backlight(&mut self, r: u8, g: u8, b: u8) -> ScreenResult {
+        self.each(|screen| screen.change_backlight(r, g, b))
+    }
+
+    /// Sets the contrast of every panel
+    pub fn set_contrast(&mut self, contrast: u8) -> ScreenResult {
+        self.each(|screen| screen.set_contrast(contrast))
+    }
+
+    /// Defines a custom character on every panel
+    pub fn create_character(&mut self, index: u8, data: [u8; 8]) -> ScreenResult {
+        self.each(|screen| screen.create_character(index, data))
+    }
+
+    /// Turns every panel on or off
+    pub fn enable_display(&mut self, activated: bool) -> ScreenResult {
+        self.each(|screen| screen.enable_display(activated))
+    }
+
+    /// Sends the surface framebuffer to the panels
+    fn flush(&mut self) -> ScreenResult {
+        let frame = &self.frame;
+        let mut first_error = None;
+        for tile in &mut self.tiles {
+            let part = tile.screen.framebuffer();
+            let mut wanted = FrameBuffer::new(part.rows(), part.columns());
+            for row in 0..part.rows() as usize {
+                for col in 0..part.columns() as usize {
+                    if let Some(byte) = frame.get(tile.row + row, tile.col + col) {
+                        wanted.set(row, col, byte);
+                    }
+                }
+            }
+            if let Err(e) = tile.screen.present(&wanted) {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Runs `f` on every panel, even after one fails, returning the first error
+    fn each<F>(&mut self, mut f: F) -> ScreenResult
+    where
+        F: FnMut(&mut Screen<T>) -> ScreenResult,
+    {
+        let mut first_error = None;
+        for tile in &mut self.tiles {
+            if let Err(e) = f(&mut tile.screen) {
+                first_error.get_or_insert(e);
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Emulator, ScreenConfig};
+
+    fn panel() -> Screen<Emulator> {
+        Screen::with_transport(ScreenConfig::new(4, 20), Emulator::new(4, 20))
+    }
+
+    #[test]
+    fn test_stacked_panels_wrap_across() {
+        let mut tall = TiledDisplay::vertical(vec![panel(), panel()]);
+        assert_eq!((tall.rows(), tall.columns()), (8, 20));
+        assert_eq!(tall.panel_origin(1), Some((4, 0)));
+
+        tall.move_cursor(3, 15).unwrap();
+        tall.print("over the edge").unwrap();
+        assert_eq!(tall.panel(0).unwrap().transport().row_text(3), "               over ");
+        assert_eq!(tall.panel(1).unwrap().transport().row_text(0), "the edge            ");
+        assert_eq!(tall.framebuffer().cursor(), (4, 8));
+        assert!(matches!(
+            tall.move_cursor(8, 0),
+            Err(QwiicLcdError::InvalidPosition { max_rows: 8, max_columns: 20, .. })
+        ));
+    }
+
+    #[test]
+    fn test_grid_and_synced_settings() {
+        let mut wall = TiledDisplay::grid(vec![vec![panel(), panel()], vec![panel(), panel()]]);
+        assert_eq!((wall.rows(), wall.columns(), wall.panel_count()), (8, 40, 4));
+
+        let mut frame = FrameBuffer::new(8, 40);
+        frame.set(7, 39, b'#');
+        frame.set(0, 0, b'@');
+        wall.present(&frame).unwrap();
+        wall.change_backlight(10, 20, 30).unwrap();
+        wall.create_character(1, [0x1F; 8]).unwrap();
+
+        let panels = wall.into_panels();
+        assert_eq!(panels[0].transport().row_text(0), format!("@{}", " ".repeat(19)));
+        assert_eq!(panels[3].transport().row_text(3), format!("{}#", " ".repeat(19)));
+        assert_eq!(panels[1].transport().row_text(0), " ".repeat(20));
+        for panel in &panels {
+            assert_eq!(panel.transport().backlight(), (10, 20, 30));
+            assert_eq!(panel.transport().glyph(1), [0x1F; 8]);
+        }
+    }
+}
@@ -0,0 +1,693 @@
+// Copyright 2021 Caleb Mitchell Smith-Woolrich (PixelCoda)
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use i2cdev::core::I2CDevice;
+use i2cdev::linux::{LinuxI2CDevice, LinuxI2CError};
+
+use crate::{I2COperation, EINVAL};
+
+/// The SMBus writes `Screen` needs from the bus
+///
+/// `LinuxTransport` talks to a real `/dev/i2c-*` device. Other
+/// implementations record, replay or emulate the traffic so the rest of
+/// the library can run without hardware.
+pub trait Transport: Send {
+    /// Sends a single byte (`smbus_write_byte`)
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError>;
+
+    /// Sends a register byte followed by a value (`smbus_write_byte_data`)
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError>;
+
+    /// Sends a register byte followed by a block (`smbus_write_i2c_block_data`)
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError>;
+
+    /// Reopens the device after it was lost; used by the reconnect logic
+    fn reopen(&mut self) -> Result<(), LinuxI2CError> {
+        Ok(())
+    }
+
+    /// Waits for the panel to process a command, or between retries
+    ///
+    /// Transports that don't talk to hardware can skip the wait.
+    fn delay(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        (**self).write_byte(value)
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        (**self).write_byte_data(register, value)
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        (**self).write_block(register, data)
+    }
+
+    fn reopen(&mut self) -> Result<(), LinuxI2CError> {
+        (**self).reopen()
+    }
+
+    fn delay(&mut self, duration: Duration) {
+        (**self).delay(duration)
+    }
+}
+
+/// A shared transport, e.g. an `Emulator` inspected by another thread while
+/// a `Mirror` writes to it
+impl<T: Transport> Transport for Arc<Mutex<T>> {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.lock().unwrap_or_else(|e| e.into_inner()).write_byte(value)
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.lock().unwrap_or_else(|e| e.into_inner()).write_byte_data(register, value)
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        self.lock().unwrap_or_else(|e| e.into_inner()).write_block(register, data)
+    }
+
+    fn reopen(&mut self) -> Result<(), LinuxI2CError> {
+        self.lock().unwrap_or_else(|e| e.into_inner()).reopen()
+    }
+
+    fn delay(&mut self, duration: Duration) {
+        self.lock().unwrap_or_else(|e| e.into_inner()).delay(duration)
+    }
+}
+
+/// Transport backed by a Linux i2c-dev device
+pub struct LinuxTransport {
+    dev: LinuxI2CDevice,
+    bus: String,
+    address: u16,
+}
+
+impl LinuxTransport {
+    /// Opens the device at `address` on the given bus (e.g. "/dev/i2c-1")
+    pub fn open(bus: &str, address: u16) -> Result<LinuxTransport, LinuxI2CError> {
+        Ok(LinuxTransport {
+            dev: LinuxI2CDevice::new(bus, address)?,
+            bus: bus.to_string(),
+            address,
+        })
+    }
+
+    /// Path of the I2C bus
+    pub fn bus(&self) -> &str {
+        &self.bus
+    }
+
+    /// I2C address of the device
+    pub fn address(&self) -> u16 {
+        self.address
+    }
+
+    /// Probes every 7-bit address on a bus and returns those that answer
+    ///
+    /// Like `i2cdetect -r`, each address gets a one-byte read. Addresses
+    /// claimed by a kernel driver cannot be opened and are skipped.
+    pub fn scan(bus: &str) -> Result<Vec<u16>, LinuxI2CError> {
+        // Report a missing bus or permission problem instead of "no devices"
+        File::open(bus).map_err(LinuxI2CError::Io)?;
+        let answers = |address: &u16| {
+            LinuxI2CDevice::new(bus, *address)
+                .and_then(|mut dev| dev.smbus_read_byte())
+                .is_ok()
+        };
+        Ok((0x08..=0x77).filter(answers).collect())
+    }
+}
+
+impl Transport for LinuxTransport {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.dev.smbus_write_byte(value)
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.dev.smbus_write_byte_data(register, value)
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        self.dev.smbus_write_i2c_block_data(register, data)
+    }
+
+    fn reopen(&mut self) -> Result<(), LinuxI2CError> {
+        self.dev = LinuxI2CDevice::new(&self.bus, self.address)?;
+        Ok(())
+    }
+}
+
+/// Transport that accepts and discards every write
+///
+/// Handy for dry runs and as the inner transport of a `Recorder`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NullTransport;
+
+impl Transport for NullTransport {
+    fn write_byte(&mut self, _value: u8) -> Result<(), LinuxI2CError> {
+        Ok(())
+    }
+
+    fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<(), LinuxI2CError> {
+        Ok(())
+    }
+
+    fn write_block(&mut self, _register: u8, _data: &[u8]) -> Result<(), LinuxI2CError> {
+        Ok(())
+    }
+
+    fn delay(&mut self, _duration: Duration) {}
+}
+
+/// One SMBus call made by `Screen`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    /// Kind of SMBus call
+    pub operation: I2COperation,
+    /// Register (command prefix) byte; `None` for `WriteByte`
+    pub register: Option<u8>,
+    /// Data bytes following the register
+    pub payload: Vec<u8>,
+}
+
+impl Transaction {
+    /// Convenience constructor for a `WriteByte` call
+    pub fn byte(value: u8) -> Transaction {
+        Transaction { operation: I2COperation::WriteByte, register: None, payload: vec![value] }
+    }
+
+    /// Convenience constructor for a `WriteByteData` call
+    pub fn byte_data(register: u8, value: u8) -> Transaction {
+        Transaction { operation: I2COperation::WriteByteData, register: Some(register), payload: vec![value] }
+    }
+
+    /// Convenience constructor for a `WriteBlock` call
+    pub fn block(register: u8, data: &[u8]) -> Transaction {
+        Transaction { operation: I2COperation::WriteBlock, register: Some(register), payload: data.to_vec() }
+    }
+
+    /// Sends this transaction through a transport
+    pub fn send<T: Transport + ?Sized>(&self, transport: &mut T) -> Result<(), LinuxI2CError> {
+        match (self.operation, self.register, self.payload.as_slice()) {
+            (I2COperation::WriteByte, _, [value]) => transport.write_byte(*value),
+            (I2COperation::WriteByteData, Some(register), [value]) => transport.write_byte_data(register, *value),
+            (I2COperation::WriteBlock, Some(register), data) => transport.write_block(register, data),
+            _ => Err(invalid_input()),
+        }
+    }
+
+    /// The bytes this transaction puts on the wire (register first)
+    pub fn wire_bytes(&self) -> Vec<u8> {
+        self.register.iter().copied().chain(self.payload.iter().copied()).collect()
+    }
+
+    fn to_text(&self, timestamp: Duration) -> String {
+        let mut line = format!("{} {}", timestamp.as_micros(), self.operation);
+        match self.register {
+            Some(register) => line.push_str(&format!(" {:02X}", register)),
+            None => line.push_str(" --"),
+        }
+        for byte in &self.payload {
+            line.push_str(&format!(" {:02X}", byte));
+        }
+        line
+    }
+
+    #[cfg(feature = "json")]
+    fn to_json(&self, timestamp: Duration) -> String {
+        let line = JsonLine {
+            t_us: timestamp.as_micros() as u64,
+            kind: self.operation.to_string(),
+            register: self.register,
+            payload: self.payload.clone(),
+        };
+        serde_json::to_string(&line).expect("trace lines serialize")
+    }
+
+    /// Parses one trace line in either the text or the JSON format
+    ///
+    /// Returns `Ok(None)` for blank lines and `#` comments.
+    pub fn parse_line(line: &str) -> Result<Option<Transaction>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        if line.starts_with('{') {
+            parse_json_line(line).map(Some)
+        } else {
+            parse_text_line(line).map(Some)
+        }
+    }
+}
+
+impl fmt::Display for Transaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.operation)?;
+        if let Some(register) = self.register {
+            write!(f, " {:02X}", register)?;
+        }
+        for byte in &self.payload {
+            write!(f, " {:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_operation(kind: &str) -> Result<I2COperation, String> {
+    match kind {
+        "write_byte" => Ok(I2COperation::WriteByte),
+        "write_byte_data" => Ok(I2COperation::WriteByteData),
+        "write_block" => Ok(I2COperation::WriteBlock),
+        other => Err(format!("unknown transaction kind '{}'", other)),
+    }
+}
+
+fn parse_text_line(line: &str) -> Result<Transaction, String> {
+    let mut fields = line.split_whitespace();
+    let timestamp = fields.next().ok_or("missing timestamp")?;
+    timestamp.parse::<u64>().map_err(|_| format!("invalid timestamp '{}'", timestamp))?;
+    let operation = parse_operation(fields.next().ok_or("missing transaction kind")?)?;
+    let register = match fields.next().ok_or("missing register")? {
+        "--" => None,
+        hex => Some(u8::from_str_radix(hex, 16).map_err(|_| format!("invalid register '{}'", hex))?),
+    };
+    let payload = fields
+        .map(|hex| u8::from_str_radix(hex, 16).map_err(|_| format!("invalid byte '{}'", hex)))
+        .collect::<Result<Vec<u8>, String>>()?;
+    Ok(Transaction { operation, register, payload })
+}
+
+/// One line of a JSON trace; unknown fields are ignored
+#[cfg(feature = "json")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct JsonLine {
+    t_us: u64,
+    kind: String,
+    register: Option<u8>,
+    payload: Vec<u8>,
+}
+
+#[cfg(feature = "json")]
+fn parse_json_line(line: &str) -> Result<Transaction, String> {
+    let line: JsonLine = serde_json::from_str(line).map_err(|e| e.to_string())?;
+    Ok(Transaction { operation: parse_operation(&line.kind)?, register: line.register, payload: line.payload })
+}
+
+#[cfg(not(feature = "json"))]
+fn parse_json_line(_line: &str) -> Result<Transaction, String> {
+    Err("JSON trace lines need the `json` feature".to_string())
+}
+
+/// Line format used by `Recorder`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// `<t_us> <kind> <register|--> <hex bytes...>`
+    Text,
+    /// One JSON object per line: `{"t_us":..,"kind":..,"register":..,"payload":[..]}`
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Transport wrapper that logs every SMBus call the wrapped transport accepted
+///
+/// Calls are written once they complete, so a transfer that is retried
+/// shows up once; attempts that fail are not recorded.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let recorder = Recorder::new(NullTransport, Vec::new(), TraceFormat::Text);
+/// let mut screen = Screen::with_transport(ScreenConfig::default(), recorder);
+/// screen.move_cursor(1, 2).unwrap();
+///
+/// let (_, trace) = screen.into_transport().into_parts();
+/// assert!(String::from_utf8(trace).unwrap().ends_with("write_byte_data FE C2\n"));
+/// ```
+pub struct Recorder<T: Transport, W: Write + Send> {
+    inner: T,
+    sink: W,
+    format: TraceFormat,
+    started: Instant,
+}
+
+impl<T: Transport, W: Write + Send> Recorder<T, W> {
+    /// Records the calls made on `inner` to `sink`
+    pub fn new(inner: T, sink: W, format: TraceFormat) -> Recorder<T, W> {
+        Recorder { inner, sink, format, started: Instant::now() }
+    }
+
+    /// The wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// The trace written so far
+    pub fn sink(&self) -> &W {
+        &self.sink
+    }
+
+    /// Splits the recorder into the wrapped transport and the trace sink
+    pub fn into_parts(self) -> (T, W) {
+        (self.inner, self.sink)
+    }
+
+    fn record(&mut self, transaction: &Transaction) -> Result<(), LinuxI2CError> {
+        let timestamp = self.started.elapsed();
+        let line = match self.format {
+            TraceFormat::Text => transaction.to_text(timestamp),
+            #[cfg(feature = "json")]
+            TraceFormat::Json => transaction.to_json(timestamp),
+        };
+        writeln!(self.sink, "{}", line).map_err(LinuxI2CError::Io)
+    }
+}
+
+impl<T: Transport> Recorder<T, BufWriter<File>> {
+    /// Records the calls made on `inner` to a new file at `path`
+    pub fn create<P: AsRef<Path>>(inner: T, path: P, format: TraceFormat) -> io::Result<Self> {
+        Ok(Recorder::new(inner, BufWriter::new(File::create(path)?), format))
+    }
+}
+
+impl<T: Transport, W: Write + Send> Transport for Recorder<T, W> {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.inner.write_byte(value)?;
+        self.record(&Transaction::byte(value))
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.inner.write_byte_data(register, value)?;
+        self.record(&Transaction::byte_data(register, value))
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        self.inner.write_block(register, data)?;
+        self.record(&Transaction::block(register, data))
+    }
+
+    fn reopen(&mut self) -> Result<(), LinuxI2CError> {
+        self.inner.reopen()
+    }
+
+    fn delay(&mut self, duration: Duration) {
+        self.inner.delay(duration)
+    }
+}
+
+/// Transport that checks each SMBus call against a recorded trace
+///
+/// Use it to lock down the byte-level protocol of `Screen` methods: run
+/// the code under test against a replay of a golden trace and call
+/// `finish` to make sure every expected call was made, in order.
+///
+/// # Example
+/// ```
+/// # use qwiic_lcd_rs::*;
+/// let golden = "0 write_byte_data FE C2\n";
+/// let replay = ReplayTransport::parse(golden).unwrap();
+/// let mut screen = Screen::with_transport(ScreenConfig::default(), replay);
+/// screen.move_cursor(1, 2).unwrap();
+/// screen.into_transport().finish().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct ReplayTransport {
+    expected: Vec<Transaction>,
+    position: usize,
+    mismatch: Option<String>,
+}
+
+impl ReplayTransport {
+    /// Replays the given transactions
+    pub fn new(expected: Vec<Transaction>) -> ReplayTransport {
+        ReplayTransport { expected, position: 0, mismatch: None }
+    }
+
+    /// Parses a trace in the text or JSON format (formats may be mixed)
+    pub fn parse(trace: &str) -> Result<ReplayTransport, String> {
+        let mut expected = Vec::new();
+        for (number, line) in trace.lines().enumerate() {
+            match Transaction::parse_line(line) {
+                Ok(Some(transaction)) => expected.push(transaction),
+                Ok(None) => {}
+                Err(e) => return Err(format!("line {}: {}", number + 1, e)),
+            }
+        }
+        Ok(ReplayTransport::new(expected))
+    }
+
+    /// Reads a trace from any reader
+    pub fn from_reader<R: io::Read>(reader: R) -> io::Result<ReplayTransport> {
+        let mut trace = String::new();
+        for line in BufReader::new(reader).lines() {
+            trace.push_str(&line?);
+            trace.push('\n');
+        }
+        ReplayTransport::parse(&trace).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Reads a trace file written by `Recorder`
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<ReplayTransport> {
+        ReplayTransport::from_reader(File::open(path)?)
+    }
+
+    /// Number of expected calls not made yet
+    pub fn remaining(&self) -> usize {
+        self.expected.len() - self.position
+    }
+
+    /// Checks that no call diverged from the trace and none is missing
+    pub fn finish(&self) -> Result<(), String> {
+        if let Some(mismatch) = &self.mismatch {
+            return Err(mismatch.clone());
+        }
+        match self.expected.get(self.position) {
+            Some(next) => Err(format!(
+                "{} expected call(s) not made, next is #{}: {}",
+                self.remaining(),
+                self.position + 1,
+                next
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn check(&mut self, actual: Transaction) -> Result<(), LinuxI2CError> {
+        if self.mismatch.is_some() {
+            return Err(invalid_input());
+        }
+        let message = match self.expected.get(self.position) {
+            Some(expected) if *expected == actual => {
+                self.position += 1;
+                return Ok(());
+            }
+            Some(expected) => format!("call #{}: expected {}, got {}", self.position + 1, expected, actual),
+            None => format!("call #{}: unexpected {} after end of trace", self.position + 1, actual),
+        };
+        self.mismatch = Some(message);
+        Err(invalid_input())
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn write_byte(&mut self, value: u8) -> Result<(), LinuxI2CError> {
+        self.check(Transaction::byte(value))
+    }
+
+    fn write_byte_data(&mut self, register: u8, value: u8) -> Result<(), LinuxI2CError> {
+        self.check(Transaction::byte_data(register, value))
+    }
+
+    fn write_block(&mut self, register: u8, data: &[u8]) -> Result<(), LinuxI2CError> {
+        self.check(Transaction::block(register, data))
+    }
+
+    fn delay(&mut self, _duration: Duration) {}
+}
+
+/// EINVAL is classified as permanent, so the retry engine won't repeat the call
+fn invalid_input() -> LinuxI2CError {
+    LinuxI2CError::Errno(EINVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{QwiicLcdError, Screen, ScreenConfig};
+
+    fn replay_screen(golden: &str) -> Screen<ReplayTransport> {
+        Screen::with_transport(ScreenConfig::default(), ReplayTransport::parse(golden).unwrap())
+    }
+
+    #[test]
+    fn test_text_trace_roundtrip() {
+        let transactions = vec![
+            Transaction::byte(0x41),
+            Transaction::byte_data(0xFE, 0x80),
+            Transaction::block(0x7C, &[0x2B, 0xFF, 0x00, 0x10]),
+        ];
+        for transaction in &transactions {
+            let line = transaction.to_text(Duration::from_micros(1234));
+            assert_eq!(Transaction::parse_line(&line).unwrap().as_ref(), Some(transaction));
+        }
+        assert_eq!(transactions[2].to_text(Duration::from_micros(7)), "7 write_block 7C 2B FF 00 10");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_trace_roundtrip() {
+        let transactions = vec![
+            Transaction::byte(0x41),
+            Transaction::byte_data(0xFE, 0x80),
+            Transaction::block(0x7C, &[0x2B, 0xFF, 0x00, 0x10]),
+        ];
+        for transaction in &transactions {
+            let line = transaction.to_json(Duration::from_micros(1234));
+            assert_eq!(Transaction::parse_line(&line).unwrap().as_ref(), Some(transaction));
+        }
+        assert_eq!(
+            transactions[0].to_json(Duration::from_micros(7)),
+            "{\"t_us\":7,\"kind\":\"write_byte\",\"register\":null,\"payload\":[65]}"
+        );
+
+        // Fields added by other tools may hold separators, escapes and lookalike keys
+        let annotated = transactions[2].to_json(Duration::from_micros(42)).replacen(
+            '{',
+            r#"{"note":"a, \"kind\":\"write_byte\"} [x]\\", "tags":[{"k":"}"}],"#,
+            1,
+        );
+        assert_eq!(Transaction::parse_line(&annotated).unwrap().as_ref(), Some(&transactions[2]));
+        assert!(Transaction::parse_line(r#"{"kind":"write_byte","register":null,"payload":[65}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_garbage() {
+        assert_eq!(Transaction::parse_line("  # comment").unwrap(), None);
+        assert_eq!(Transaction::parse_line("").unwrap(), None);
+        assert!(Transaction::parse_line("12 write_word FE 00").is_err());
+        assert!(Transaction::parse_line("12 write_byte -- ZZ").is_err());
+        assert!(ReplayTransport::parse("0 write_byte -- 41\nbogus\n").unwrap_err().starts_with("line 2"));
+    }
+
+    /// Rejects the first write, then forwards to a `NullTransport`
+    #[derive(Default)]
+    struct Hiccup {
+        failed: bool,
+    }
+
+    impl Transport for Hiccup {
+        fn write_byte(&mut self, _value: u8) -> Result<(), LinuxI2CError> {
+            if !std::mem::replace(&mut self.failed, true) {
+                return Err(LinuxI2CError::Errno(crate::EIO));
+            }
+            Ok(())
+        }
+
+        fn write_byte_data(&mut self, _register: u8, _value: u8) -> Result<(), LinuxI2CError> {
+            Ok(())
+        }
+
+        fn write_block(&mut self, _register: u8, _data: &[u8]) -> Result<(), LinuxI2CError> {
+            Ok(())
+        }
+
+        fn delay(&mut self, _duration: Duration) {}
+    }
+
+    #[test]
+    fn test_recorder_writes_every_call() {
+        let recorder = Recorder::new(Hiccup::default(), Vec::new(), TraceFormat::Text);
+        let mut screen = Screen::with_transport(ScreenConfig::default(), recorder);
+        screen.change_backlight(1, 2, 3).unwrap();
+        // The retried 'H' is recorded once
+        screen.print("Hi").unwrap();
+
+        let (_, trace) = screen.into_transport().into_parts();
+        let replay = ReplayTransport::parse(&String::from_utf8(trace).unwrap()).unwrap();
+        assert_eq!(
+            replay.expected,
+            vec![Transaction::block(0x7C, &[0x2B, 1, 2, 3]), Transaction::byte(b'H'), Transaction::byte(b'i')]
+        );
+    }
+
+    #[test]
+    fn test_golden_move_cursor() {
+        let mut screen = replay_screen(
+            "# move_cursor uses the HD44780 DDRAM row offsets 00/40/14/54\n\
+             0 write_byte_data FE 80\n\
+             0 write_byte_data FE C5\n\
+             0 write_byte_data FE A7\n\
+             0 write_byte_data FE E7\n",
+        );
+        screen.move_cursor(0, 0).unwrap();
+        screen.move_cursor(1, 5).unwrap();
+        screen.move_cursor(2, 19).unwrap();
+        screen.move_cursor(3, 19).unwrap();
+        // Out of range positions never reach the bus
+        assert!(screen.move_cursor(4, 0).is_err());
+        screen.into_transport().finish().unwrap();
+    }
+
+    #[test]
+    fn test_golden_create_character() {
+        let mut screen = replay_screen(
+            "0 write_byte_data FE 58\n\
+             0 write_byte -- 00\n\
+             0 write_byte -- 0A\n\
+             0 write_byte -- 1F\n\
+             0 write_byte -- 1F\n\
+             0 write_byte -- 0E\n\
+             0 write_byte -- 04\n\
+             0 write_byte -- 00\n\
+             0 write_byte -- 00\n\
+             0 write_byte_data FE 02\n",
+        );
+        screen.create_character(3, [0x00, 0x0A, 0x1F, 0x1F, 0x0E, 0x04, 0x00, 0x00]).unwrap();
+        assert!(screen.create_character(8, [0; 8]).is_err());
+        screen.into_transport().finish().unwrap();
+    }
+
+    #[test]
+    fn test_golden_set_contrast() {
+        let mut screen = replay_screen("0 write_block 7C 18 28\n");
+        screen.set_contrast(0x28).unwrap();
+        screen.into_transport().finish().unwrap();
+    }
+
+    #[test]
+    fn test_replay_reports_divergence() {
+        let mut screen = replay_screen("0 write_byte_data FE 80\n0 write_byte_data FE C0\n");
+        screen.move_cursor(0, 0).unwrap();
+        let error = screen.move_cursor(1, 1).unwrap_err();
+        // Divergence is permanent, so it is not retried
+        assert!(matches!(error, QwiicLcdError::Transfer { attempts: 1, .. }));
+        let message = screen.into_transport().finish().unwrap_err();
+        assert!(message.contains("expected write_byte_data FE C0, got write_byte_data FE C1"), "{}", message);
+
+        let screen = replay_screen("0 write_byte -- 41\n");
+        assert!(screen.into_transport().finish().unwrap_err().contains("1 expected call(s) not made"));
+    }
+}